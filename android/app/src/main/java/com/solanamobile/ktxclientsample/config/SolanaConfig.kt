@@ -26,6 +26,12 @@ object SolanaConfig {
     /** Vault PDA seed (vault holds SOL for the game; seeds = [b"vault", game_escrow.key()]). */
     val VAULT_SEED: ByteArray = "vault".encodeToByteArray()
 
+    /** Game result receipt PDA seed (seeds = [b"game_result", game_escrow.key()]); create_game fails once it exists. */
+    val GAME_RESULT_SEED: ByteArray = "game_result".encodeToByteArray()
+
+    /** Emergency pause PDA seed (seeds = [b"pause"]); create_game and join_game fail while it is set. */
+    val PAUSE_SEED: ByteArray = "pause".encodeToByteArray()
+
     /** create_game instruction discriminator (from IDL). */
     val CREATE_GAME_DISCRIMINATOR: ByteArray = byteArrayOf(
         124.toByte(), 69.toByte(), 75.toByte(), 66.toByte(),
@@ -65,11 +65,31 @@ class EscrowUseCase @Inject constructor() {
         }
         Log.d(TAG, "buildCreateGameInstruction: vault PDA=${SolanaPublicKey(vaultPda.bytes).base58()}")
 
-        // Accounts: creator (signer, writable), game_escrow (writable), vault (writable), system_program (readonly)
+        // Game result PDA: seeds = [b"game_result", game_escrow.key()], must not exist yet
+        val gameResultSeeds = listOf(
+            SolanaConfig.GAME_RESULT_SEED,
+            gameEscrowPda.bytes
+        )
+        val gameResultPda = ProgramDerivedAddress.find(gameResultSeeds, SolanaConfig.RPS_ESCROW_PROGRAM_ID).getOrNull()
+        if (gameResultPda == null) {
+            Log.e(TAG, "buildCreateGameInstruction: game_result PDA derivation failed")
+            return null
+        }
+
+        // Pause PDA: seeds = [b"pause"], read by the program even before it is initialized
+        val pausePda = ProgramDerivedAddress.find(listOf(SolanaConfig.PAUSE_SEED), SolanaConfig.RPS_ESCROW_PROGRAM_ID).getOrNull()
+        if (pausePda == null) {
+            Log.e(TAG, "buildCreateGameInstruction: pause PDA derivation failed")
+            return null
+        }
+
+        // Accounts: creator (signer, writable), game_escrow (writable), vault (writable), game_result (readonly), pause (readonly), system_program (readonly)
         val accounts = listOf(
             AccountMeta(creator, true, true),
             AccountMeta(SolanaPublicKey(gameEscrowPda.bytes), false, true),
             AccountMeta(SolanaPublicKey(vaultPda.bytes), false, true),
+            AccountMeta(SolanaPublicKey(gameResultPda.bytes), false, false),
+            AccountMeta(SolanaPublicKey(pausePda.bytes), false, false),
             AccountMeta(SolanaConfig.SYSTEM_PROGRAM_ID, false, false)
         )
 
@@ -141,11 +161,19 @@ class EscrowUseCase @Inject constructor() {
         }
         Log.d(TAG, "buildJoinGameInstruction: vault PDA=${SolanaPublicKey(vaultPda.bytes).base58()}")
 
-        // Accounts: joiner (signer, writable), game_escrow (writable), vault (writable), system_program (readonly)
+        // Pause PDA: seeds = [b"pause"], read by the program even before it is initialized
+        val pausePda = ProgramDerivedAddress.find(listOf(SolanaConfig.PAUSE_SEED), SolanaConfig.RPS_ESCROW_PROGRAM_ID).getOrNull()
+        if (pausePda == null) {
+            Log.e(TAG, "buildJoinGameInstruction: pause PDA derivation failed")
+            return null
+        }
+
+        // Accounts: joiner (signer, writable), game_escrow (writable), vault (writable), pause (readonly), system_program (readonly)
         val accounts = listOf(
             AccountMeta(joiner, true, true),
             AccountMeta(SolanaPublicKey(gameEscrowPda.bytes), false, true),
             AccountMeta(SolanaPublicKey(vaultPda.bytes), false, true),
+            AccountMeta(SolanaPublicKey(pausePda.bytes), false, false),
             AccountMeta(SolanaConfig.SYSTEM_PROGRAM_ID, false, false)
         )
 
@@ -0,0 +1,365 @@
+//! [Solana Actions](https://solana.com/docs/advanced/actions) for sharing a game as a link
+//! (blink) that any Actions-aware wallet can open to join it.
+//!
+//! - `GET /actions.json` maps `/games/<game_id>` links to the action API.
+//! - `GET /api/actions/games/:game_id/join` — metadata: stake, creator, format; disabled with an
+//!   error once the game cannot be joined.
+//! - `POST /api/actions/games/:game_id/join` `{ "account" }` — unsigned `join_game` transaction
+//!   for `account` (built like `POST /games/:game_id/tx/join`), chained to `.../join/next`.
+//! - `POST /api/actions/games/:game_id/join/next` `{ "account", "signature" }` — called by the
+//!   wallet after confirmation: the join is indexed right away (joiner, `join_tx`, `active`).
+//!
+//! Every action response carries `X-Action-Version` and `X-Blockchain-Ids`; errors are
+//! `{ "message" }` as the spec requires. No SIWS: the wallet signing the transaction is the
+//! joiner. Absolute URLs (the icon) are built on the configured `PUBLIC_BASE_URL`, never on
+//! request headers.
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use base64::Engine;
+use mongodb::bson::doc;
+use serde::{Deserialize, Serialize};
+use rps_escrow_client::fees::TREASURY_FEE_PERCENT;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+use crate::error::ApiError;
+use crate::games::{self, AppState, Game, GameIdPath, GameStatus};
+use crate::indexer::{EscrowInstruction, Indexer};
+use crate::transactions::{self, PlayerAction};
+
+/// Actions spec version implemented.
+const ACTION_VERSION: &str = "2.4";
+
+const ACTION_ICON: &[u8] = include_bytes!("../assets/action-icon.png");
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+
+/// Error body of the Actions spec.
+pub struct ActionError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ActionError {
+    pub(crate) fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<ApiError> for ActionError {
+    fn from(e: ApiError) -> Self {
+        Self {
+            status: e.status,
+            message: e.message,
+        }
+    }
+}
+
+impl IntoResponse for ActionError {
+    fn into_response(self) -> Response {
+        (self.status, Json(serde_json::json!({ "message": self.message }))).into_response()
+    }
+}
+
+#[derive(Serialize)]
+pub struct ActionsJson {
+    pub rules: Vec<ActionRule>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionRule {
+    pub path_pattern: &'static str,
+    pub api_path: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct LinkedAction {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub label: String,
+    pub href: String,
+}
+
+#[derive(Serialize)]
+pub struct ActionLinks {
+    pub actions: Vec<LinkedAction>,
+}
+
+#[derive(Serialize)]
+pub struct ActionMessage {
+    pub message: String,
+}
+
+/// `ActionGetResponse`, also used for the `completed` action ending the chain.
+#[derive(Serialize)]
+pub struct ActionMetadata {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub icon: String,
+    pub title: String,
+    pub description: String,
+    pub label: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<ActionLinks>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ActionMessage>,
+}
+
+#[derive(Deserialize)]
+pub struct ActionPostRequest {
+    pub account: String,
+}
+
+#[derive(Serialize)]
+pub struct NextActionLink {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub href: String,
+}
+
+#[derive(Serialize)]
+pub struct ActionTransactionLinks {
+    pub next: NextActionLink,
+}
+
+#[derive(Serialize)]
+pub struct ActionPostResponse {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    /// Base64 bincode transaction, unsigned.
+    pub transaction: String,
+    pub message: String,
+    pub links: ActionTransactionLinks,
+}
+
+#[derive(Deserialize)]
+pub struct NextActionRequest {
+    pub account: String,
+    pub signature: String,
+}
+
+pub(crate) fn sol(lamports: i64) -> String {
+    format!("{} SOL", lamports as f64 / LAMPORTS_PER_SOL)
+}
+
+async fn find_game(state: &AppState, game_id: &str) -> Result<Game, ActionError> {
+    state
+        .db
+        .collection::<Game>("games")
+        .find_one(doc! { "_id": game_id }, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to find game: {}", e);
+            ApiError::internal(e.to_string())
+        })?
+        .ok_or_else(|| ApiError::not_found("Game not found").into())
+}
+
+/// Why the game cannot be joined through the action, if it cannot.
+fn unavailable(game: &Game) -> Option<&'static str> {
+    if game.status != GameStatus::Waiting || game.joiner_pubkey.is_some() {
+        Some("This game has already started or ended")
+    } else if game.create_tx.is_none() {
+        Some("The creator has not deposited yet")
+    } else {
+        None
+    }
+}
+
+async fn actions_json() -> Json<ActionsJson> {
+    Json(ActionsJson {
+        rules: vec![
+            ActionRule {
+                path_pattern: "/games/*",
+                api_path: "/api/actions/games/*/join",
+            },
+            ActionRule {
+                path_pattern: "/api/actions/**",
+                api_path: "/api/actions/**",
+            },
+        ],
+    })
+}
+
+async fn action_icon() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "image/png")], ACTION_ICON)
+}
+
+async fn get_join_action(
+    State(state): State<AppState>,
+    Path(path): Path<GameIdPath>,
+) -> Result<Json<ActionMetadata>, ActionError> {
+    let game = find_game(&state, &path.game_id).await?;
+    let stake = sol(game.amount_per_player);
+    let opponent = game
+        .challenge
+        .as_ref()
+        .map(|c| format!(" Only {} can accept this challenge.", games::short_pk(&c.opponent_pubkey)))
+        .unwrap_or_default();
+    let error = unavailable(&game).map(|m| ActionMessage { message: m.to_string() });
+    Ok(Json(ActionMetadata {
+        kind: "action",
+        icon: format!("{}/actions/icon.png", state.public_base_url),
+        title: format!("Rock Paper Scissors for {}", stake),
+        description: format!(
+            "{} stakes {} per player. {} and the winner takes the pot ({}% fee).{}",
+            games::short_pk(&game.creator_pubkey),
+            stake,
+            game.format.description(),
+            TREASURY_FEE_PERCENT,
+            opponent
+        ),
+        label: format!("Join for {}", stake),
+        disabled: error.is_some(),
+        links: Some(ActionLinks {
+            actions: vec![LinkedAction {
+                kind: "transaction",
+                label: format!("Join for {}", stake),
+                href: format!("/api/actions/games/{}/join", game.id),
+            }],
+        }),
+        error,
+    }))
+}
+
+async fn post_join_action(
+    State(state): State<AppState>,
+    Path(path): Path<GameIdPath>,
+    Json(body): Json<ActionPostRequest>,
+) -> Result<Json<ActionPostResponse>, ActionError> {
+    let solana = state
+        .solana
+        .clone()
+        .ok_or_else(|| ApiError::internal("Solana client not configured"))?;
+    let account = body.account.trim().to_string();
+    let payer: Pubkey = account
+        .parse()
+        .map_err(|_| ActionError::bad_request("account is not a valid base58 pubkey"))?;
+    let game = find_game(&state, &path.game_id).await?;
+    if let Some(message) = unavailable(&game) {
+        return Err(ActionError::bad_request(message));
+    }
+    let ix = transactions::player_instruction(&solana, &state.pause, &game, PlayerAction::Join, &account)?;
+
+    let (tx, _, _) = tokio::task::spawn_blocking(move || solana.build_player_transaction(&ix, &payer))
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?
+        .map_err(|e| {
+            log::warn!("Join action failed game_id={} account={}: {}", game.id, account, e);
+            ActionError::bad_request(e.message.clone())
+        })?;
+    let bytes = bincode::serialize(&tx).map_err(|e| ApiError::internal(e.to_string()))?;
+    log::info!("Join action built game_id={} account={}", game.id, account);
+    Ok(Json(ActionPostResponse {
+        kind: "transaction",
+        transaction: base64::engine::general_purpose::STANDARD.encode(bytes),
+        message: format!("Deposit {} to join the game", sol(game.amount_per_player)),
+        links: ActionTransactionLinks {
+            next: NextActionLink {
+                kind: "post",
+                href: format!("/api/actions/games/{}/join/next", game.id),
+            },
+        },
+    }))
+}
+
+async fn post_join_next(
+    State(state): State<AppState>,
+    Path(path): Path<GameIdPath>,
+    Json(body): Json<NextActionRequest>,
+) -> Result<Json<ActionMetadata>, ActionError> {
+    let solana = state
+        .solana
+        .clone()
+        .ok_or_else(|| ApiError::internal("Solana client not configured"))?;
+    let account: Pubkey = body
+        .account
+        .trim()
+        .parse()
+        .map_err(|_| ActionError::bad_request("account is not a valid base58 pubkey"))?;
+    let signature: Signature = body
+        .signature
+        .trim()
+        .parse()
+        .map_err(|_| ActionError::bad_request("signature is not a valid base58 signature"))?;
+    let game = find_game(&state, &path.game_id).await?;
+
+    let indexer = Indexer::new(state.db.clone(), solana.rpc_url.clone(), solana.program_id);
+    let instructions = indexer.index_signature(&signature).await.map_err(|e| {
+        log::warn!("Join action next: cannot index {}: {}", signature, e);
+        ActionError::bad_request("transaction not found or not confirmed yet")
+    })?;
+    let joined = instructions.iter().any(|(escrow, ix)| {
+        game.game_escrow_pubkey.as_deref() == Some(escrow.to_string().as_str())
+            && matches!(ix, EscrowInstruction::Join { joiner } if *joiner == account)
+    });
+    if !joined {
+        return Err(ActionError::bad_request("transaction does not join this game"));
+    }
+    games::upsert_user(&state.db, &account.to_string()).await?;
+    if game.challenge.is_some() {
+        state
+            .db
+            .collection::<Game>("games")
+            .update_one(
+                doc! { "_id": &game.id, "joiner_pubkey": account.to_string() },
+                doc! { "$set": { "challenge.status": "accepted" } },
+                None,
+            )
+            .await
+            .map_err(|e| {
+                log::error!("Failed to accept challenge: {}", e);
+                ApiError::internal(e.to_string())
+            })?;
+    }
+    log::info!("Join action confirmed game_id={} joiner={} sig={}", game.id, account, signature);
+
+    Ok(Json(ActionMetadata {
+        kind: "completed",
+        icon: format!("{}/actions/icon.png", state.public_base_url),
+        title: "You joined the game".to_string(),
+        description: "Open Ro-Sham-Bo to play your rounds.".to_string(),
+        label: "Joined".to_string(),
+        disabled: false,
+        links: None,
+        error: None,
+    }))
+}
+
+/// Adds the headers every action response must carry.
+async fn action_headers(State(state): State<AppState>, mut response: Response) -> Response {
+    let headers = response.headers_mut();
+    headers.insert("x-action-version", HeaderValue::from_static(ACTION_VERSION));
+    // CAIP-2 chain id: the genesis hash truncated to 32 characters.
+    if let Some(genesis) = state.solana.as_ref().and_then(|s| s.cluster.genesis_hash()) {
+        if let Ok(value) = HeaderValue::from_str(&format!("solana:{}", &genesis[..32])) {
+            headers.insert("x-blockchain-ids", value);
+        }
+    }
+    response
+}
+
+pub fn action_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/actions.json", get(actions_json))
+        .route("/actions/icon.png", get(action_icon))
+        .route(
+            "/api/actions/games/:game_id/join",
+            get(get_join_action).post(post_join_action),
+        )
+        .route("/api/actions/games/:game_id/join/next", post(post_join_next))
+        .layer(middleware::map_response_with_state(state.clone(), action_headers))
+        .with_state(state)
+}
@@ -0,0 +1,169 @@
+//! Operator-only endpoints, authenticated with the `X-Admin-Key` header.
+//!
+//! The key is `ADMIN_API_KEY`; when it is not configured every admin route answers 403.
+//!
+//! `POST /admin/games/:game_id/refund` sends the authority `refund` of a joined game nobody won
+//! (the reconciliation's `refund` remediation), its fees paid by the fee payer. The indexer then
+//! marks the game `refunded`.
+
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRequestParts, Path, State},
+    http::request::Parts,
+    routing::{get, post},
+    Json, Router,
+};
+use mongodb::bson::doc;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use subtle::ConstantTimeEq;
+
+use crate::error::ApiError;
+use crate::games::{AppState, Game};
+use crate::reconcile::{self, ReconciliationReport};
+
+/// Caller presented the configured admin key.
+#[derive(Clone, Debug)]
+pub struct AdminAuth;
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminAuth {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let Some(expected) = state.admin_api_key.as_deref() else {
+            return Err(ApiError::forbidden("admin API disabled (ADMIN_API_KEY not set)"));
+        };
+        let presented = parts
+            .headers
+            .get("x-admin-key")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+        if presented.is_empty() {
+            return Err(ApiError::unauthorized("missing X-Admin-Key"));
+        }
+        // Constant time, so response timing does not leak how much of the key matched.
+        if !bool::from(presented.as_bytes().ct_eq(expected.as_bytes())) {
+            log::warn!("Admin request rejected: bad X-Admin-Key");
+            return Err(ApiError::forbidden("invalid X-Admin-Key"));
+        }
+        Ok(AdminAuth)
+    }
+}
+
+async fn latest_reconciliation(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+) -> Result<Json<ReconciliationReport>, ApiError> {
+    state
+        .db
+        .collection::<ReconciliationReport>("reconciliation_reports")
+        .find_one(doc! { "_id": reconcile::LATEST_REPORT_ID }, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to find reconciliation report: {}", e);
+            ApiError::internal(e.to_string())
+        })?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("No reconciliation report yet"))
+}
+
+async fn run_reconciliation(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+) -> Result<Json<ReconciliationReport>, ApiError> {
+    let solana = state
+        .solana
+        .as_ref()
+        .ok_or_else(|| ApiError::internal("Solana client not configured"))?;
+    let report = reconcile::run(&state.db, solana).await.map_err(|e| {
+        log::error!("Reconciliation failed: {}", e);
+        ApiError::internal(e)
+    })?;
+    Ok(Json(report))
+}
+
+#[derive(Deserialize)]
+struct GameIdPath {
+    game_id: String,
+}
+
+#[derive(Serialize)]
+struct RefundResponse {
+    game_id: String,
+    signature: String,
+}
+
+async fn refund_game(
+    State(state): State<AppState>,
+    _admin: AdminAuth,
+    Path(path): Path<GameIdPath>,
+) -> Result<Json<RefundResponse>, ApiError> {
+    let solana = state
+        .solana
+        .clone()
+        .ok_or_else(|| ApiError::internal("Solana client not configured"))?;
+    let game = state
+        .db
+        .collection::<Game>("games")
+        .find_one(doc! { "_id": &path.game_id }, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to find game: {}", e);
+            ApiError::internal(e.to_string())
+        })?
+        .ok_or_else(|| ApiError::not_found("Game not found"))?;
+    if game.winner_pubkey.is_some() {
+        return Err(ApiError::bad_request("Game has a winner; resolve it instead"));
+    }
+    let game_id = *uuid::Uuid::parse_str(&game.id)
+        .map_err(|e| ApiError::internal(e.to_string()))?
+        .as_bytes();
+    let creator: Pubkey = game
+        .creator_pubkey
+        .parse()
+        .map_err(|_| ApiError::internal("Game has an invalid creator pubkey"))?;
+    let escrow: Pubkey = game
+        .game_escrow_pubkey
+        .as_deref()
+        .and_then(|k| k.parse().ok())
+        .ok_or_else(|| ApiError::bad_request("Game has no game escrow"))?;
+
+    let result = tokio::task::spawn_blocking(move || {
+        let escrow = solana
+            .fetch_game_escrow(&escrow)
+            .map_err(|e| ApiError::service_unavailable(format!("Cannot read the game escrow: {}", e)))?
+            .ok_or_else(|| ApiError::bad_request("Game escrow is not open on-chain"))?;
+        // The joiner on-chain is the one the program refunds, whatever the DB recorded.
+        let joiner = escrow
+            .joiner
+            .ok_or_else(|| ApiError::bad_request("Nobody joined on-chain; only the creator can cancel"))?;
+        let mut attempts = Vec::new();
+        solana.refund(game_id, &creator, &joiner, &mut attempts).map_err(|e| {
+            log::error!("On-chain refund failed game_id={}: {}", uuid::Uuid::from_bytes(game_id), e);
+            if e.permanent {
+                ApiError::bad_request(e.to_string())
+            } else {
+                ApiError::service_unavailable(e.to_string())
+            }
+        })
+    })
+    .await
+    .map_err(|e| ApiError::internal(e.to_string()))??;
+
+    log::info!("Game refunded on-chain game_id={} sig={}", game.id, result.signature);
+    Ok(Json(RefundResponse {
+        game_id: game.id,
+        signature: result.signature,
+    }))
+}
+
+pub fn admin_routes(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/admin/reconciliation",
+            get(latest_reconciliation).post(run_reconciliation),
+        )
+        .route("/admin/games/:game_id/refund", post(refund_game))
+        .with_state(state)
+}
@@ -72,6 +72,28 @@ pub fn verify_siws(
     Ok(pk_str)
 }
 
+/// Optional SIWS auth: `None` when no `X-SIWS-Address` header is sent, otherwise the proof must verify.
+/// For public routes that personalise their response for a signed-in caller.
+#[derive(Clone, Debug)]
+pub struct MaybeAuthUser(pub Option<AuthUser>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for MaybeAuthUser
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, Json<serde_json::Value>);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if !parts.headers.contains_key("x-siws-address") {
+            return Ok(MaybeAuthUser(None));
+        }
+        AuthUser::from_request_parts(parts, state)
+            .await
+            .map(|user| MaybeAuthUser(Some(user)))
+    }
+}
+
 #[async_trait]
 impl<S> FromRequestParts<S> for AuthUser
 where
@@ -0,0 +1,186 @@
+//! Balance monitoring of the accounts behind API transactions.
+//!
+//! The account paying fees (the fee payer, or the resolve authority when no separate fee payer is
+//! configured) is checked against `FEE_PAYER_*_LAMPORTS`; a resolve authority that does not pay
+//! fees against `RESOLVE_AUTHORITY_*_LAMPORTS`. Below the warning threshold the job logs a
+//! warning; below the minimum it logs an error and `/ready` answers 503.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use serde::Serialize;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+use crate::config::Config;
+use crate::games::now_utc_string;
+use crate::solana::SolanaAppClient;
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountRole {
+    FeePayer,
+    ResolveAuthority,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceLevel {
+    Ok,
+    /// Below the warning threshold.
+    Low,
+    /// Below the minimum: not ready.
+    Critical,
+    /// Not checked yet.
+    Unknown,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AccountBalance {
+    pub role: AccountRole,
+    pub pubkey: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lamports: Option<u64>,
+    pub warn_lamports: u64,
+    pub min_lamports: u64,
+    pub level: BalanceLevel,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checked_at: Option<String>,
+}
+
+impl AccountBalance {
+    fn level_for(&self, lamports: u64) -> BalanceLevel {
+        if lamports < self.min_lamports {
+            BalanceLevel::Critical
+        } else if lamports < self.warn_lamports {
+            BalanceLevel::Low
+        } else {
+            BalanceLevel::Ok
+        }
+    }
+}
+
+/// Latest balances of the monitored accounts, shared with `/ready`.
+#[derive(Clone, Default)]
+pub struct BalanceMonitor {
+    accounts: Arc<RwLock<Vec<AccountBalance>>>,
+}
+
+impl BalanceMonitor {
+    /// Watches the fee payer and the resolve authority configured in `solana` (none if resolve is
+    /// disabled).
+    pub fn new(solana: &SolanaAppClient, config: &Config) -> Self {
+        let watch = |role, pubkey: Pubkey, warn_lamports, min_lamports| AccountBalance {
+            role,
+            pubkey: pubkey.to_string(),
+            lamports: None,
+            warn_lamports,
+            min_lamports,
+            level: BalanceLevel::Unknown,
+            checked_at: None,
+        };
+        let mut accounts = Vec::new();
+        if let Some(authority) = solana.resolve_authority() {
+            match &solana.fee_payer {
+                Some(fee_payer) => {
+                    accounts.push(watch(
+                        AccountRole::FeePayer,
+                        fee_payer.pubkey(),
+                        config.fee_payer_warn_lamports,
+                        config.fee_payer_min_lamports,
+                    ));
+                    accounts.push(watch(
+                        AccountRole::ResolveAuthority,
+                        authority,
+                        config.resolve_authority_warn_lamports,
+                        config.resolve_authority_min_lamports,
+                    ));
+                }
+                None => accounts.push(watch(
+                    AccountRole::ResolveAuthority,
+                    authority,
+                    config.fee_payer_warn_lamports,
+                    config.fee_payer_min_lamports,
+                )),
+            }
+        }
+        Self {
+            accounts: Arc::new(RwLock::new(accounts)),
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<AccountBalance> {
+        self.accounts.read().expect("balance lock poisoned").clone()
+    }
+
+    /// False if any monitored account is below its minimum.
+    pub fn is_ready(&self) -> bool {
+        self.snapshot().iter().all(|a| a.level != BalanceLevel::Critical)
+    }
+
+    /// Fetches every balance and logs accounts whose level changed. A failed fetch keeps the
+    /// previous levels.
+    async fn check(&self, solana: &SolanaAppClient) {
+        let pubkeys: Vec<Pubkey> = self
+            .snapshot()
+            .iter()
+            .filter_map(|a| a.pubkey.parse().ok())
+            .collect();
+        if pubkeys.is_empty() {
+            return;
+        }
+        let solana = solana.clone();
+        let fetched = tokio::task::spawn_blocking(move || solana.get_balances(&pubkeys))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r);
+        let balances = match fetched {
+            Ok(balances) => balances,
+            Err(e) => {
+                log::warn!("Balance check failed: {}", e);
+                return;
+            }
+        };
+        let checked_at = now_utc_string();
+
+        let mut accounts = self.accounts.write().expect("balance lock poisoned");
+        for (account, lamports) in accounts.iter_mut().zip(balances) {
+            let level = account.level_for(lamports);
+            if level != account.level {
+                let role = format!("{:?}", account.role);
+                match level {
+                    BalanceLevel::Critical => log::error!(
+                        "{} {} balance {} lamports is below the minimum {}; not ready",
+                        role,
+                        account.pubkey,
+                        lamports,
+                        account.min_lamports
+                    ),
+                    BalanceLevel::Low => log::warn!(
+                        "{} {} balance {} lamports is below {}",
+                        role,
+                        account.pubkey,
+                        lamports,
+                        account.warn_lamports
+                    ),
+                    BalanceLevel::Ok | BalanceLevel::Unknown => {
+                        log::info!("{} {} balance {} lamports", role, account.pubkey, lamports)
+                    }
+                }
+            }
+            account.lamports = Some(lamports);
+            account.level = level;
+            account.checked_at = Some(checked_at.clone());
+        }
+    }
+}
+
+/// Checks balances every `interval` (the first check runs immediately).
+pub fn spawn_balance_job(monitor: BalanceMonitor, solana: SolanaAppClient, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            monitor.check(&solana).await;
+        }
+    });
+}
@@ -0,0 +1,284 @@
+//! Stand-in remote signer for the resolve authority (see `seeker_rps_api::signer`).
+//!
+//! Holds the authority keypair and signs only transactions its policy allows:
+//! - the authority signs, alongside at most a separate fee payer and the other resolve authorities
+//!   co-signing a high-stake game
+//! - every instruction is a compute-budget instruction or an allowed rps-escrow instruction
+//! - the priority fee does not exceed the configured cap
+//! - the decoded instructions sent with the request match the message (the `winner` arg of
+//!   resolve and the `winners` of resolve_batch included)
+//!
+//! Verdicts (`/sign-verdict`) are signed when `resolve_with_attestation` is allowed, the message is
+//! the verdict of the request's fields and the expiry is at most `SIGNER_MAX_VERDICT_SECS` away.
+//!
+//! **Environment:**
+//! - `SIGNER_KEYPAIR_PATH` — authority keypair JSON file (required)
+//! - `SIGNER_PROGRAM_ID` — rps-escrow program id (required)
+//! - `SIGNER_LISTEN` — `host:port` or `unix:/path.sock` (optional, default `127.0.0.1:7070`)
+//! - `SIGNER_ALLOWED_INSTRUCTIONS` — comma-separated rps-escrow instructions (optional, default `resolve,resolve_batch,refund,resolve_with_attestation`)
+//! - `SIGNER_MAX_PRIORITY_FEE_MICRO_LAMPORTS` — cap on the compute-unit price (optional, default 1_000_000)
+//! - `SIGNER_MAX_VERDICT_SECS` — longest verdict lifetime signed (optional, default 3600)
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::os::unix::net::UnixListener;
+
+use base64::Engine;
+use rps_escrow_client::instructions::EscrowInstruction;
+use rps_escrow_client::MAX_RESOLVE_AUTHORITIES;
+use seeker_rps_api::signer::{
+    read_http_message, DecodedInstruction, PubkeyResponse, SignRequest, SignResponse, VerdictSignRequest,
+    VerdictTerms,
+};
+use seeker_rps_api::solana::load_keypair;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    message::Message,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+/// `ComputeBudgetInstruction::SetComputeUnitPrice` tag (borsh enum index).
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+struct Policy {
+    program_id: Pubkey,
+    allowed: Vec<String>,
+    max_micro_lamports: u64,
+    max_verdict_secs: i64,
+}
+
+impl Policy {
+    fn from_env() -> Self {
+        Self {
+            program_id: std::env::var("SIGNER_PROGRAM_ID")
+                .expect("SIGNER_PROGRAM_ID must be set")
+                .parse()
+                .expect("SIGNER_PROGRAM_ID must be a valid base58 pubkey"),
+            allowed: std::env::var("SIGNER_ALLOWED_INSTRUCTIONS")
+                .unwrap_or_else(|_| "resolve,resolve_batch,refund,resolve_with_attestation".to_string())
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            max_micro_lamports: std::env::var("SIGNER_MAX_PRIORITY_FEE_MICRO_LAMPORTS")
+                .ok()
+                .map(|v| v.parse().expect("SIGNER_MAX_PRIORITY_FEE_MICRO_LAMPORTS must be a number"))
+                .unwrap_or(1_000_000),
+            max_verdict_secs: std::env::var("SIGNER_MAX_VERDICT_SECS")
+                .ok()
+                .map(|v| v.parse().expect("SIGNER_MAX_VERDICT_SECS must be a number of seconds"))
+                .unwrap_or(3600),
+        }
+    }
+
+    /// Ok if the verdict `terms` may be signed.
+    fn check_verdict(&self, terms: &VerdictTerms) -> Result<(), String> {
+        if !self.allowed.iter().any(|a| a == "resolve_with_attestation") {
+            return Err("instruction resolve_with_attestation not allowed".to_string());
+        }
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs() as i64;
+        if terms.expiry > now + self.max_verdict_secs {
+            return Err(format!("verdict expiry over {}s away", self.max_verdict_secs));
+        }
+        Ok(())
+    }
+
+    /// Ok if `authority` may sign `message`, described by `decoded`.
+    fn check(&self, authority: &Pubkey, message: &Message, decoded: &[DecodedInstruction]) -> Result<(), String> {
+        let required = usize::from(message.header.num_required_signatures);
+        if required > 1 + MAX_RESOLVE_AUTHORITIES
+            || !message.account_keys.iter().take(required).any(|k| k == authority)
+        {
+            return Err(
+                "the authority must sign, with at most a fee payer and other resolve authorities".to_string(),
+            );
+        }
+        let compute_budget_id = ComputeBudgetInstruction::set_compute_unit_limit(0).program_id;
+        let mut escrow_ixs = Vec::new();
+        for ix in &message.instructions {
+            let program = message
+                .account_keys
+                .get(usize::from(ix.program_id_index))
+                .ok_or("bad program index")?;
+            if *program == compute_budget_id {
+                if ix.data.first() == Some(&SET_COMPUTE_UNIT_PRICE) {
+                    let price = ix
+                        .data
+                        .get(1..9)
+                        .and_then(|b| b.try_into().ok())
+                        .map(u64::from_le_bytes)
+                        .ok_or("bad SetComputeUnitPrice")?;
+                    if price > self.max_micro_lamports {
+                        return Err(format!("priority fee {} over cap {}", price, self.max_micro_lamports));
+                    }
+                }
+            } else if *program == self.program_id {
+                let escrow_ix = EscrowInstruction::decode(&ix.data).ok_or("unknown rps-escrow instruction")?;
+                let name = escrow_ix.name();
+                if !self.allowed.iter().any(|a| a == name) {
+                    return Err(format!("instruction {} not allowed", name));
+                }
+                let accounts: Vec<String> = ix
+                    .accounts
+                    .iter()
+                    .filter_map(|i| message.account_keys.get(usize::from(*i)))
+                    .map(|k| k.to_string())
+                    .collect();
+                escrow_ixs.push((escrow_ix, accounts));
+            } else {
+                return Err(format!("program {} not allowed", program));
+            }
+        }
+
+        if escrow_ixs.len() != decoded.len() {
+            return Err("decoded instructions do not match the message".to_string());
+        }
+        for ((escrow_ix, accounts), d) in escrow_ixs.iter().zip(decoded) {
+            let decoded_accounts: Vec<&String> = d.accounts.iter().map(|a| &a.pubkey).collect();
+            if d.name != escrow_ix.name() || d.program_id != self.program_id.to_string() || decoded_accounts != accounts.iter().collect::<Vec<_>>() {
+                return Err(format!("decoded {} does not match the message", d.name));
+            }
+            match escrow_ix {
+                EscrowInstruction::Resolve { winner } => {
+                    if d.args["winner"].as_str() != Some(winner.to_string().as_str()) {
+                        return Err("decoded winner does not match the message".to_string());
+                    }
+                }
+                EscrowInstruction::ResolveBatch { winners } => {
+                    let winners: Vec<String> = winners.iter().map(|w| w.to_string()).collect();
+                    if d.args["winners"] != serde_json::json!(winners) {
+                        return Err("decoded winners do not match the message".to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn respond(stream: &mut impl Write, status: u16, body: &serde_json::Value) {
+    let body = body.to_string();
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        _ => "Not Found",
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+}
+
+fn handle(stream: &mut (impl Read + Write), keypair: &Keypair, policy: &Policy) {
+    let (request_line, body) = match read_http_message(stream) {
+        Ok(m) => m,
+        Err(e) => {
+            log::warn!("bad request: {}", e);
+            return;
+        }
+    };
+    let mut parts = request_line.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/pubkey")) => {
+            let response = PubkeyResponse {
+                pubkey: keypair.pubkey().to_string(),
+            };
+            respond(stream, 200, &serde_json::json!(response));
+        }
+        (Some("POST"), Some("/sign")) => {
+            let result = serde_json::from_slice::<SignRequest>(&body)
+                .map_err(|e| (400, e.to_string()))
+                .and_then(|req| {
+                    let bytes = base64::engine::general_purpose::STANDARD
+                        .decode(&req.message)
+                        .map_err(|e| (400, e.to_string()))?;
+                    let message: Message = bincode::deserialize(&bytes).map_err(|e| (400, e.to_string()))?;
+                    policy
+                        .check(&keypair.pubkey(), &message, &req.instructions)
+                        .map_err(|e| (403, e))?;
+                    Ok((keypair.sign_message(&bytes), req.instructions))
+                });
+            match result {
+                Ok((signature, instructions)) => {
+                    let names: Vec<&str> = instructions.iter().map(|i| i.name.as_str()).collect();
+                    log::info!("signed {:?} sig={}", names, signature);
+                    let response = SignResponse {
+                        signature: signature.to_string(),
+                    };
+                    respond(stream, 200, &serde_json::json!(response));
+                }
+                Err((status, error)) => {
+                    log::warn!("refused to sign: {}", error);
+                    respond(stream, status, &serde_json::json!({ "error": error }));
+                }
+            }
+        }
+        (Some("POST"), Some("/sign-verdict")) => {
+            let result = serde_json::from_slice::<VerdictSignRequest>(&body)
+                .map_err(|e| (400, e.to_string()))
+                .and_then(|req| {
+                    let terms = req.terms().map_err(|e| (400, e))?;
+                    policy.check_verdict(&terms).map_err(|e| (403, e))?;
+                    Ok((keypair.sign_message(&terms.message()), req))
+                });
+            match result {
+                Ok((signature, req)) => {
+                    log::info!(
+                        "signed verdict game_escrow={} winner={} expiry={} sig={}",
+                        req.game_escrow,
+                        req.winner,
+                        req.expiry,
+                        signature
+                    );
+                    let response = SignResponse {
+                        signature: signature.to_string(),
+                    };
+                    respond(stream, 200, &serde_json::json!(response));
+                }
+                Err((status, error)) => {
+                    log::warn!("refused to sign verdict: {}", error);
+                    respond(stream, status, &serde_json::json!({ "error": error }));
+                }
+            }
+        }
+        _ => respond(stream, 404, &serde_json::json!({ "error": "not found" })),
+    }
+}
+
+fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let keypair_path = std::env::var("SIGNER_KEYPAIR_PATH").expect("SIGNER_KEYPAIR_PATH must be set");
+    let keypair = load_keypair(keypair_path.as_ref()).expect("failed to load SIGNER_KEYPAIR_PATH");
+    let policy = Policy::from_env();
+    let listen = std::env::var("SIGNER_LISTEN").unwrap_or_else(|_| "127.0.0.1:7070".to_string());
+    log::info!(
+        "resolve-signer {} on {} (allowed: {})",
+        keypair.pubkey(),
+        listen,
+        policy.allowed.join(",")
+    );
+
+    if let Some(path) = listen.strip_prefix("unix:") {
+        let _ = std::fs::remove_file(path);
+        let listener = UnixListener::bind(path).expect("bind unix socket");
+        for mut stream in listener.incoming().flatten() {
+            handle(&mut stream, &keypair, &policy);
+        }
+    } else {
+        let listener = TcpListener::bind(&listen).expect("bind");
+        for mut stream in listener.incoming().flatten() {
+            handle(&mut stream, &keypair, &policy);
+        }
+    }
+}
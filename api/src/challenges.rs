@@ -0,0 +1,268 @@
+//! Direct challenges: a game only one named wallet can join.
+//!
+//! The creator funds the escrow with the program's `create_challenge` instruction, which pins the
+//! opponent on-chain so `join_game` rejects anyone else. Challenge games are not joinable by PIN:
+//! the opponent finds them in `GET /players/me/challenges` and accepts (after the on-chain
+//! `join_game`) or declines. Accepting checks that the escrow's joiner on-chain is the opponent
+//! before the game changes. A declined challenge stays `waiting` so the creator can cancel it
+//! and recover the deposit as usual.
+
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use mongodb::bson::doc;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::games::{
+    insert_new_game, upsert_user, AppState, CreateGameRequest, CreateGameResponse, Game,
+    GameIdPath, JoinGameResponse,
+};
+
+#[derive(Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengeStatus {
+    #[default]
+    Pending,
+    Accepted,
+    Declined,
+}
+
+/// Challenge details stored on the game.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Challenge {
+    pub opponent_pubkey: String,
+    pub status: ChallengeStatus,
+}
+
+/// Request body for `POST /games/challenge`. Creator identity from SIWS auth.
+#[derive(Deserialize)]
+pub struct ChallengeRequest {
+    pub opponent_pubkey: String,
+    pub game_id: Option<String>,
+    #[serde(default)]
+    pub amount_per_player: i64,
+}
+
+/// Response for `GET /players/me/challenges`: pending challenges still waiting for a joiner.
+#[derive(Serialize)]
+pub struct ChallengeInboxResponse {
+    /// Challenges naming the caller as opponent.
+    pub received: Vec<Game>,
+    /// Challenges the caller created.
+    pub sent: Vec<Game>,
+}
+
+async fn create_challenge(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<ChallengeRequest>,
+) -> Result<Json<CreateGameResponse>, ApiError> {
+    let creator_pubkey = auth.pubkey.trim();
+    let opponent_pubkey = body.opponent_pubkey.trim();
+    if opponent_pubkey.parse::<solana_sdk::pubkey::Pubkey>().is_err() {
+        return Err(ApiError::bad_request("invalid opponent_pubkey (not a valid base58 pubkey)"));
+    }
+    if opponent_pubkey == creator_pubkey {
+        return Err(ApiError::bad_request("You cannot challenge yourself"));
+    }
+
+    let request = CreateGameRequest {
+        game_id: body.game_id,
+        amount_per_player: body.amount_per_player,
+        matchmaking: false,
+    };
+    let challenge = Challenge {
+        opponent_pubkey: opponent_pubkey.to_string(),
+        status: ChallengeStatus::Pending,
+    };
+    let game = insert_new_game(&state, creator_pubkey, &request, Some(challenge)).await?;
+    log::info!(
+        "Challenge created game_id={} creator_pubkey={} opponent_pubkey={}",
+        game.id, creator_pubkey, opponent_pubkey
+    );
+    Ok(Json(CreateGameResponse {
+        game_id: game.id,
+        pin: game.pin,
+    }))
+}
+
+/// Filter for a pending challenge addressed to `opponent_pubkey`.
+fn pending_for(game_id: &str, opponent_pubkey: &str) -> mongodb::bson::Document {
+    doc! {
+        "_id": game_id,
+        "status": "waiting",
+        "joiner_pubkey": null,
+        "challenge.opponent_pubkey": opponent_pubkey,
+        "challenge.status": "pending",
+    }
+}
+
+/// Filter for a challenge `opponent_pubkey` can accept: still pending, or already joined by them
+/// as recorded by the indexer.
+fn acceptable_for(game_id: &str, opponent_pubkey: &str) -> mongodb::bson::Document {
+    doc! {
+        "_id": game_id,
+        "challenge.opponent_pubkey": opponent_pubkey,
+        "challenge.status": "pending",
+        "$or": [
+            { "status": "waiting", "joiner_pubkey": null },
+            { "status": "active", "joiner_pubkey": opponent_pubkey },
+        ],
+    }
+}
+
+async fn accept_challenge(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(path): Path<GameIdPath>,
+) -> Result<Json<JoinGameResponse>, ApiError> {
+    let joiner_pubkey = auth.pubkey.trim();
+    state.pause.ensure_open()?;
+    let solana = state
+        .solana
+        .clone()
+        .ok_or_else(|| ApiError::internal("Solana client not configured"))?;
+
+    let games = state.db.collection::<Game>("games");
+    let pending = games
+        .find_one(acceptable_for(&path.game_id, joiner_pubkey), None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to find challenge: {}", e);
+            ApiError::internal(e.to_string())
+        })?
+        .ok_or_else(|| ApiError::not_found("No pending challenge for this wallet"))?;
+    let escrow: Pubkey = pending
+        .game_escrow_pubkey
+        .as_deref()
+        .and_then(|k| k.parse().ok())
+        .ok_or_else(|| ApiError::internal("Challenge has no game escrow"))?;
+    let escrow = tokio::task::spawn_blocking(move || solana.fetch_game_escrow(&escrow))
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?
+        .map_err(|e| {
+            log::error!("Failed to fetch game escrow game_id={}: {}", pending.id, e);
+            ApiError::service_unavailable("Cannot read the game escrow on-chain")
+        })?;
+    let joined = escrow
+        .and_then(|e| e.joiner)
+        .is_some_and(|joiner| joiner.to_string() == joiner_pubkey);
+    if !joined {
+        return Err(ApiError::bad_request(
+            "join_game has not landed on-chain for this wallet yet",
+        ));
+    }
+    upsert_user(&state.db, joiner_pubkey).await?;
+
+    let update = doc! {
+        "$set": {
+            "joiner_pubkey": joiner_pubkey,
+            "status": "active",
+            "challenge.status": "accepted"
+        }
+    };
+    let updated = games
+        .find_one_and_update(acceptable_for(&path.game_id, joiner_pubkey), update, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to accept challenge: {}", e);
+            ApiError::internal(e.to_string())
+        })?;
+
+    match updated {
+        Some(game) => {
+            log::info!("Challenge accepted game_id={} joiner_pubkey={}", game.id, joiner_pubkey);
+            Ok(Json(JoinGameResponse { game_id: game.id }))
+        }
+        None => Err(ApiError::not_found("No pending challenge for this wallet")),
+    }
+}
+
+async fn decline_challenge(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(path): Path<GameIdPath>,
+) -> Result<Json<Game>, ApiError> {
+    let opponent_pubkey = auth.pubkey.trim();
+    let games = state.db.collection::<Game>("games");
+    let opts = mongodb::options::FindOneAndUpdateOptions::builder()
+        .return_document(mongodb::options::ReturnDocument::After)
+        .build();
+    let updated = games
+        .find_one_and_update(
+            pending_for(&path.game_id, opponent_pubkey),
+            doc! { "$set": { "challenge.status": "declined" } },
+            opts,
+        )
+        .await
+        .map_err(|e| {
+            log::error!("Failed to decline challenge: {}", e);
+            ApiError::internal(e.to_string())
+        })?;
+
+    match updated {
+        Some(game) => {
+            log::info!("Challenge declined game_id={} opponent_pubkey={}", game.id, opponent_pubkey);
+            Ok(Json(game))
+        }
+        None => Err(ApiError::not_found("No pending challenge for this wallet")),
+    }
+}
+
+async fn find_pending(
+    state: &AppState,
+    filter: mongodb::bson::Document,
+) -> Result<Vec<Game>, ApiError> {
+    let opts = mongodb::options::FindOptions::builder()
+        .sort(doc! { "created_at": -1 })
+        .build();
+    let mut cursor = state
+        .db
+        .collection::<Game>("games")
+        .find(filter, opts)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to list challenges: {}", e);
+            ApiError::internal(e.to_string())
+        })?;
+    let mut games = Vec::new();
+    while cursor.advance().await.map_err(|e| ApiError::internal(e.to_string()))? {
+        games.push(cursor.deserialize_current().map_err(|e| ApiError::internal(e.to_string()))?);
+    }
+    Ok(games)
+}
+
+async fn challenge_inbox(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<ChallengeInboxResponse>, ApiError> {
+    let pubkey = auth.pubkey.trim();
+    let pending = doc! {
+        "status": "waiting",
+        "joiner_pubkey": null,
+        "challenge.status": "pending",
+    };
+    let mut received_filter = pending.clone();
+    received_filter.insert("challenge.opponent_pubkey", pubkey);
+    let mut sent_filter = pending;
+    sent_filter.insert("creator_pubkey", pubkey);
+
+    Ok(Json(ChallengeInboxResponse {
+        received: find_pending(&state, received_filter).await?,
+        sent: find_pending(&state, sent_filter).await?,
+    }))
+}
+
+pub fn challenge_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/games/challenge", post(create_challenge))
+        .route("/games/:game_id/accept", post(accept_challenge))
+        .route("/games/:game_id/decline", post(decline_challenge))
+        .route("/players/me/challenges", get(challenge_inbox))
+        .with_state(state)
+}
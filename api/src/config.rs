@@ -1,43 +1,308 @@
 //! Configuration loaded from environment variables.
 //! Single place to validate and document required env vars.
 //!
-//! **Solana (mainnet):**
+//! **Solana:**
+//! - `SOLANA_CLUSTER` — cluster profile: `localnet`, `devnet` or `mainnet` (optional, default `mainnet`)
 //! - `SOLANA_RPC_URL` — RPC endpoint (required)
+//! - `SOLANA_RPC_FALLBACK_URLS` — comma-separated RPC endpoints used when the primary fails (optional)
+//! - `SOLANA_WS_URL` — websocket endpoint for the indexer's log subscription (optional, default derived from `SOLANA_RPC_URL`)
 //! - `RPS_ESCROW_PROGRAM_ID` — RPS escrow program id (required)
-//! - `RESOLVE_AUTHORITY_KEYPAIR_PATH` — path to JSON keypair file for resolve authority (required)
+//! - `ALLOW_MISSING_IDL` — `true` lets the API start when the program has no published IDL to check against the bundled one (optional, default `false`)
+//! - `TREASURY_PUBKEY` — treasury receiving the 3% fee (optional, default the program's `TREASURY_PUBKEY`)
+//! - `RESOLVE_SIGNER` — resolve authority signer backend: `file`, `env` or `remote` (optional, default `file`)
+//! - `RESOLVE_AUTHORITY_KEYPAIR_PATH` — path to JSON keypair file for resolve authority (required for `file`)
+//! - `RESOLVE_AUTHORITY_KEYPAIR` — resolve authority keypair, JSON byte array or base58 (required for `env`)
+//! - `RESOLVE_SIGNER_URL` — remote signer, `http://host:port` or `unix:/path.sock` (required for `remote`)
+//! - `RESOLVE_COSIGNER_URLS` — comma-separated remote signers of other resolve authorities, co-signing games at or above the program's multisig stake (optional)
+//! - `FEE_PAYER_KEYPAIR_PATH` — path to JSON keypair file paying the fees of API transactions (optional, default the resolve authority pays)
+//! - `PRIORITY_FEE_PERCENTILE` — percentile of recent prioritization fees to pay (optional, default 75)
+//! - `PRIORITY_FEE_MAX_MICRO_LAMPORTS` — cap on the priority fee per compute unit (optional, default 1_000_000)
+//! - `RESOLVE_BATCH_SIZE` — max games resolved by one `resolve_batch` transaction; 1 disables batching (optional, default 8)
+//! - `RESOLVE_BATCH_WINDOW_MS` — how long a finished game waits for others to batch with (optional, default 200)
+//! - `VERDICT_TTL_SECS` — lifetime of the signed verdicts winners claim on-chain themselves; 0 makes the API resolve every game (optional, default 0)
+//!
+//! **Balances** (checked every `BALANCE_CHECK_SECS`, optional, default 60; `/ready` fails below a minimum):
+//! - `FEE_PAYER_WARN_LAMPORTS` / `FEE_PAYER_MIN_LAMPORTS` — thresholds for the account paying fees (optional, default 100_000_000 / 10_000_000)
+//! - `RESOLVE_AUTHORITY_WARN_LAMPORTS` / `RESOLVE_AUTHORITY_MIN_LAMPORTS` — thresholds for a resolve authority that does not pay fees (optional, default 0 / 0)
+//!
+//! **Pause:**
+//! - `PAUSE_CHECK_SECS` — interval between reads of the program's pause flag (optional, default 30)
+//!
+//! **Indexer:**
+//! - `INDEXER_POLL_SECS` — interval between indexer polls when no log notification arrives (optional, default 15)
+//! - `INDEXER_BACKFILL_LIMIT` — most recent transactions indexed on a first start (optional, default: the whole history)
+//!
+//! **Admin:**
+//! - `ADMIN_API_KEY` — key expected in `X-Admin-Key` on `/admin/*` routes (optional; admin routes are disabled without it)
+//! - `RECONCILIATION_INTERVAL_SECS` — interval between DB/on-chain reconciliation runs (optional, default 3600)
+//!
+//! **Leaderboard:**
+//! - `LEADERBOARD_REFRESH_SECS` — interval between leaderboard snapshot refreshes (optional, default 60)
+//!
+//! **Links:**
+//! - `PUBLIC_BASE_URL` — public origin of the API in Solana Action and Pay links, e.g. `https://api.example.com` (optional, default `http://localhost:3000`)
+
+use std::str::FromStr;
+
+/// Solana cluster the API runs against. Used to check the RPC endpoint at startup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClusterProfile {
+    Localnet,
+    Devnet,
+    Mainnet,
+}
+
+impl ClusterProfile {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ClusterProfile::Localnet => "localnet",
+            ClusterProfile::Devnet => "devnet",
+            ClusterProfile::Mainnet => "mainnet",
+        }
+    }
+
+    /// Genesis hash the RPC must report, or None for localnet (any local validator).
+    pub fn genesis_hash(self) -> Option<&'static str> {
+        match self {
+            ClusterProfile::Localnet => None,
+            ClusterProfile::Devnet => Some("EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG"),
+            ClusterProfile::Mainnet => Some("5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d"),
+        }
+    }
+}
+
+impl FromStr for ClusterProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "localnet" | "localhost" => Ok(ClusterProfile::Localnet),
+            "devnet" => Ok(ClusterProfile::Devnet),
+            "mainnet" | "mainnet-beta" => Ok(ClusterProfile::Mainnet),
+            other => Err(format!("unknown cluster profile {:?}", other)),
+        }
+    }
+}
+
+/// Where the resolve authority key lives (see [`crate::signer`]).
+pub enum ResolveSignerConfig {
+    /// JSON keypair file.
+    File(std::path::PathBuf),
+    /// Keypair injected through the environment.
+    Env(String),
+    /// Remote signer URL.
+    Remote(String),
+}
 
 pub struct Config {
     pub mongodb_uri: String,
     pub db_name: String,
+    /// Cluster profile (from env SOLANA_CLUSTER).
+    pub solana_cluster: ClusterProfile,
     /// Solana RPC URL (from env SOLANA_RPC_URL).
     pub solana_rpc_url: String,
-    /// RPS escrow program id (mainnet).
+    /// Fallback RPC URLs, in order (from env SOLANA_RPC_FALLBACK_URLS).
+    pub solana_rpc_fallback_urls: Vec<String>,
+    /// Websocket URL (from env SOLANA_WS_URL, else derived from the RPC URL).
+    pub solana_ws_url: String,
+    /// RPS escrow program id. Used for every instruction, PDA and account check.
     pub rps_escrow_program_id: solana_sdk::pubkey::Pubkey,
-    /// Path to resolve authority keypair JSON file.
-    pub resolve_authority_keypair_path: std::path::PathBuf,
+    /// Start even if the program has no published IDL (from env ALLOW_MISSING_IDL).
+    pub allow_missing_idl: bool,
+    /// Treasury passed to `resolve`; must match the deployed program's constant.
+    pub treasury_pubkey: solana_sdk::pubkey::Pubkey,
+    /// Resolve authority signer backend.
+    pub resolve_signer: ResolveSignerConfig,
+    /// Remote signers of the other resolve authorities (from env RESOLVE_COSIGNER_URLS).
+    pub resolve_cosigner_urls: Vec<String>,
+    /// Fee payer keypair file; None means the resolve authority pays.
+    pub fee_payer_keypair_path: Option<std::path::PathBuf>,
+    /// Percentile (0-100) of recent prioritization fees paid by API transactions.
+    pub priority_fee_percentile: u8,
+    /// Cap on the priority fee, in micro-lamports per compute unit.
+    pub priority_fee_max_micro_lamports: u64,
+    /// Max games per `resolve_batch` transaction (at least 1).
+    pub resolve_batch_size: usize,
+    /// Milliseconds a finished game waits for others to batch with.
+    pub resolve_batch_window_ms: u64,
+    /// Seconds a verdict stays valid; 0 disables verdicts.
+    pub verdict_ttl_secs: u64,
+    /// Seconds between balance checks.
+    pub balance_check_secs: u64,
+    /// Balance thresholds (lamports) of the account paying fees.
+    pub fee_payer_warn_lamports: u64,
+    pub fee_payer_min_lamports: u64,
+    /// Balance thresholds (lamports) of a resolve authority that does not pay fees.
+    pub resolve_authority_warn_lamports: u64,
+    pub resolve_authority_min_lamports: u64,
+    /// Seconds between reads of the program's pause flag.
+    pub pause_check_secs: u64,
+    /// Seconds between indexer polls.
+    pub indexer_poll_secs: u64,
+    /// Most transactions the indexer backfills on a first start; None = the whole history.
+    pub indexer_backfill_limit: Option<usize>,
+    /// Key for `/admin/*` routes; None disables them.
+    pub admin_api_key: Option<String>,
+    /// Seconds between reconciliation runs.
+    pub reconciliation_interval_secs: u64,
+    /// Seconds between leaderboard snapshot refreshes.
+    pub leaderboard_refresh_secs: u64,
+    /// Public origin of the API, without trailing slash.
+    pub public_base_url: String,
 }
 
 impl Config {
     /// Load config from environment. Panics if required vars are missing.
     pub fn from_env() -> Self {
+        let solana_cluster = optional_env("SOLANA_CLUSTER")
+            .map(|v| v.parse().expect("SOLANA_CLUSTER must be localnet, devnet or mainnet"))
+            .unwrap_or(ClusterProfile::Mainnet);
         let solana_rpc_url = std::env::var("SOLANA_RPC_URL")
             .expect("SOLANA_RPC_URL must be set");
+        let solana_rpc_fallback_urls = optional_env("SOLANA_RPC_FALLBACK_URLS")
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|url| !url.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let resolve_cosigner_urls = optional_env("RESOLVE_COSIGNER_URLS")
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|url| !url.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let solana_ws_url =
+            optional_env("SOLANA_WS_URL").unwrap_or_else(|| ws_url_from_rpc(&solana_rpc_url));
         let program_id_str = std::env::var("RPS_ESCROW_PROGRAM_ID")
             .expect("RPS_ESCROW_PROGRAM_ID must be set");
         let program_id = program_id_str
             .parse()
             .expect("RPS_ESCROW_PROGRAM_ID must be a valid base58 pubkey");
-        let resolve_authority_keypair_path = std::env::var("RESOLVE_AUTHORITY_KEYPAIR_PATH")
-            .expect("RESOLVE_AUTHORITY_KEYPAIR_PATH must be set")
-            .into();
+        let allow_missing_idl = optional_env("ALLOW_MISSING_IDL")
+            .map(|v| v.parse().expect("ALLOW_MISSING_IDL must be true or false"))
+            .unwrap_or(false);
+        // Default: the treasury the program itself enforces.
+        let treasury_pubkey = optional_env("TREASURY_PUBKEY")
+            .map(|v| v.parse().expect("TREASURY_PUBKEY must be a valid base58 pubkey"))
+            .unwrap_or(rps_escrow_client::TREASURY_PUBKEY);
+        let resolve_signer = match optional_env("RESOLVE_SIGNER").as_deref().unwrap_or("file") {
+            "file" => ResolveSignerConfig::File(
+                std::env::var("RESOLVE_AUTHORITY_KEYPAIR_PATH")
+                    .expect("RESOLVE_AUTHORITY_KEYPAIR_PATH must be set")
+                    .into(),
+            ),
+            "env" => ResolveSignerConfig::Env(
+                optional_env("RESOLVE_AUTHORITY_KEYPAIR")
+                    .expect("RESOLVE_AUTHORITY_KEYPAIR must be set when RESOLVE_SIGNER=env"),
+            ),
+            "remote" => ResolveSignerConfig::Remote(
+                optional_env("RESOLVE_SIGNER_URL")
+                    .expect("RESOLVE_SIGNER_URL must be set when RESOLVE_SIGNER=remote"),
+            ),
+            other => panic!("RESOLVE_SIGNER must be file, env or remote (got {:?})", other),
+        };
+        let priority_fee_percentile = optional_env("PRIORITY_FEE_PERCENTILE")
+            .map(|v| {
+                v.parse()
+                    .ok()
+                    .filter(|p| *p <= 100)
+                    .expect("PRIORITY_FEE_PERCENTILE must be between 0 and 100")
+            })
+            .unwrap_or(75);
+        let priority_fee_max_micro_lamports = optional_env("PRIORITY_FEE_MAX_MICRO_LAMPORTS")
+            .map(|v| v.parse().expect("PRIORITY_FEE_MAX_MICRO_LAMPORTS must be a number"))
+            .unwrap_or(1_000_000);
+        let resolve_batch_size = optional_env("RESOLVE_BATCH_SIZE")
+            .map(|v| {
+                v.parse()
+                    .ok()
+                    .filter(|n| *n >= 1)
+                    .expect("RESOLVE_BATCH_SIZE must be a number of games, at least 1")
+            })
+            .unwrap_or(8);
+        let resolve_batch_window_ms = optional_env("RESOLVE_BATCH_WINDOW_MS")
+            .map(|v| v.parse().expect("RESOLVE_BATCH_WINDOW_MS must be a number of milliseconds"))
+            .unwrap_or(200);
+        let verdict_ttl_secs = optional_env("VERDICT_TTL_SECS")
+            .map(|v| v.parse().expect("VERDICT_TTL_SECS must be a number of seconds"))
+            .unwrap_or(0);
+        let lamports = |name: &str, default: u64| {
+            optional_env(name)
+                .map(|v| v.parse().unwrap_or_else(|_| panic!("{} must be a number of lamports", name)))
+                .unwrap_or(default)
+        };
+        let balance_check_secs = optional_env("BALANCE_CHECK_SECS")
+            .map(|v| v.parse().expect("BALANCE_CHECK_SECS must be a number of seconds"))
+            .unwrap_or(60);
+        let pause_check_secs = optional_env("PAUSE_CHECK_SECS")
+            .map(|v| v.parse().expect("PAUSE_CHECK_SECS must be a number of seconds"))
+            .unwrap_or(30);
+        let indexer_poll_secs = optional_env("INDEXER_POLL_SECS")
+            .map(|v| v.parse().expect("INDEXER_POLL_SECS must be a number of seconds"))
+            .unwrap_or(15);
+        let reconciliation_interval_secs = optional_env("RECONCILIATION_INTERVAL_SECS")
+            .map(|v| v.parse().expect("RECONCILIATION_INTERVAL_SECS must be a number of seconds"))
+            .unwrap_or(3600);
+        let leaderboard_refresh_secs = optional_env("LEADERBOARD_REFRESH_SECS")
+            .map(|v| v.parse().expect("LEADERBOARD_REFRESH_SECS must be a number of seconds"))
+            .unwrap_or(60);
 
         Self {
             mongodb_uri: std::env::var("MONGODB_URI").expect("MONGODB_URI must be set"),
             db_name: std::env::var("MONGODB_DB_NAME").expect("MONGODB_DB_NAME must be set"),
+            solana_cluster,
             solana_rpc_url,
+            solana_rpc_fallback_urls,
+            solana_ws_url,
             rps_escrow_program_id: program_id,
-            resolve_authority_keypair_path,
+            allow_missing_idl,
+            treasury_pubkey,
+            resolve_signer,
+            resolve_cosigner_urls,
+            fee_payer_keypair_path: optional_env("FEE_PAYER_KEYPAIR_PATH").map(Into::into),
+            priority_fee_percentile,
+            priority_fee_max_micro_lamports,
+            resolve_batch_size,
+            resolve_batch_window_ms,
+            verdict_ttl_secs,
+            balance_check_secs,
+            fee_payer_warn_lamports: lamports("FEE_PAYER_WARN_LAMPORTS", 100_000_000),
+            fee_payer_min_lamports: lamports("FEE_PAYER_MIN_LAMPORTS", 10_000_000),
+            resolve_authority_warn_lamports: lamports("RESOLVE_AUTHORITY_WARN_LAMPORTS", 0),
+            resolve_authority_min_lamports: lamports("RESOLVE_AUTHORITY_MIN_LAMPORTS", 0),
+            pause_check_secs,
+            indexer_poll_secs,
+            indexer_backfill_limit: optional_env("INDEXER_BACKFILL_LIMIT")
+                .map(|v| v.parse().expect("INDEXER_BACKFILL_LIMIT must be a number of transactions")),
+            admin_api_key: optional_env("ADMIN_API_KEY"),
+            reconciliation_interval_secs,
+            leaderboard_refresh_secs,
+            public_base_url: optional_env("PUBLIC_BASE_URL")
+                .map(|v| v.trim_end_matches('/').to_string())
+                .unwrap_or_else(|| "http://localhost:3000".to_string()),
         }
     }
 }
 
+/// Reads an optional env var; unset or empty both mean "not set".
+fn optional_env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+}
+
+/// Websocket URL for an RPC URL: same host, `ws(s)` scheme; a local validator's 8899 becomes 8900.
+fn ws_url_from_rpc(rpc_url: &str) -> String {
+    let url = if let Some(rest) = rpc_url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        rpc_url.to_string()
+    };
+    url.replace(":8899", ":8900")
+}
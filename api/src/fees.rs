@@ -0,0 +1,145 @@
+//! Priority fees and compute budget for escrow transactions sent by the API (resolve, refund).
+//!
+//! The price per compute unit is a percentile of recent prioritization fees paid on the accounts
+//! the transaction writes (escrow, vault), capped by config. The compute-unit limit comes from
+//! simulating the transaction, plus a margin.
+
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+
+use crate::config::Config;
+use crate::tx_error::TxError;
+
+/// Compute-unit limit used for the simulation that measures the real usage (runtime maximum).
+const SIMULATION_CU_LIMIT: u32 = 1_400_000;
+
+/// Headroom over simulated usage, in percent.
+const CU_MARGIN_PERCENT: u64 = 20;
+
+/// Limit used when simulation does not report units consumed.
+const FALLBACK_CU_LIMIT: u32 = 200_000;
+
+/// How the API prices its transactions.
+#[derive(Clone, Copy, Debug)]
+pub struct FeePolicy {
+    /// Percentile (0-100) of recent prioritization fees to pay.
+    pub percentile: u8,
+    /// Upper bound on the price, in micro-lamports per compute unit.
+    pub max_micro_lamports: u64,
+}
+
+impl FeePolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            percentile: config.priority_fee_percentile,
+            max_micro_lamports: config.priority_fee_max_micro_lamports,
+        }
+    }
+
+    /// Price per compute unit from recent fees paid on `writable_accounts`, capped.
+    /// RPC errors are logged and price at zero rather than blocking the transaction.
+    pub fn estimate_price(&self, rpc: &RpcClient, writable_accounts: &[Pubkey]) -> u64 {
+        match rpc.get_recent_prioritization_fees(writable_accounts) {
+            Ok(fees) => {
+                let mut values: Vec<u64> = fees.into_iter().map(|f| f.prioritization_fee).collect();
+                percentile(&mut values, self.percentile).min(self.max_micro_lamports)
+            }
+            Err(e) => {
+                log::warn!("getRecentPrioritizationFees failed, sending without priority fee: {}", e);
+                0
+            }
+        }
+    }
+}
+
+/// `pct`-th percentile (nearest rank) of `values`; 0 when empty.
+fn percentile(values: &mut [u64], pct: u8) -> u64 {
+    if values.is_empty() {
+        return 0;
+    }
+    values.sort_unstable();
+    let rank = (values.len() * usize::from(pct.min(100))).div_ceil(100);
+    values[rank.saturating_sub(1)]
+}
+
+/// Priority fee and compute-unit limit chosen for a transaction. Stored on the game.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PriorityFee {
+    pub micro_lamports_per_cu: i64,
+    pub compute_unit_limit: i64,
+}
+
+fn with_budget(instructions: &[Instruction], cu_limit: u32, price: u64) -> Vec<Instruction> {
+    let mut all = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(cu_limit),
+        ComputeBudgetInstruction::set_compute_unit_price(price),
+    ];
+    all.extend_from_slice(instructions);
+    all
+}
+
+fn unsigned(instructions: &[Instruction], payer: &Pubkey, recent_blockhash: Hash) -> Transaction {
+    Transaction::new_unsigned(Message::new_with_blockhash(instructions, Some(payer), &recent_blockhash))
+}
+
+/// Serialized size of `instructions` once budgeted and signed, to compare with
+/// `PACKET_DATA_SIZE` before building.
+pub fn budgeted_transaction_size(instructions: &[Instruction], payer: &Pubkey) -> usize {
+    let tx = unsigned(&with_budget(instructions, SIMULATION_CU_LIMIT, 0), payer, Hash::default());
+    bincode::serialized_size(&tx).map_or(usize::MAX, |size| size as usize)
+}
+
+/// Builds `instructions` with compute-budget instructions prepended: price from `policy`, limit
+/// from a simulation of the same transaction. A failed simulation is returned as a decoded
+/// [`TxError`]. The transaction is returned unsigned; `payer` signs it afterwards.
+pub fn build_budgeted_transaction(
+    rpc: &RpcClient,
+    policy: &FeePolicy,
+    instructions: &[Instruction],
+    writable_accounts: &[Pubkey],
+    payer: &Pubkey,
+    recent_blockhash: Hash,
+) -> Result<(Transaction, PriorityFee), TxError> {
+    let price = policy.estimate_price(rpc, writable_accounts);
+
+    // Simulated without signature verification, so the probe is never signed.
+    let probe = unsigned(&with_budget(instructions, SIMULATION_CU_LIMIT, price), payer, recent_blockhash);
+    let simulation = rpc
+        .simulate_transaction_with_config(
+            &probe,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(rpc.commitment()),
+                ..Default::default()
+            },
+        )
+        .map_err(|e| TxError::from_client_error(&e))?
+        .value;
+    if let Some(err) = &simulation.err {
+        return Err(TxError::from_simulation(err, &simulation));
+    }
+    let cu_limit = simulation
+        .units_consumed
+        .map(|units| units + units * CU_MARGIN_PERCENT / 100)
+        .map(|units| units.min(u64::from(SIMULATION_CU_LIMIT)) as u32)
+        .unwrap_or(FALLBACK_CU_LIMIT);
+
+    let tx = unsigned(&with_budget(instructions, cu_limit, price), payer, recent_blockhash);
+    Ok((
+        tx,
+        PriorityFee {
+            micro_lamports_per_cu: price as i64,
+            compute_unit_limit: i64::from(cu_limit),
+        },
+    ))
+}
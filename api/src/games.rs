@@ -1,8 +1,8 @@
 //! Games and users with MongoDB persistence.
 //!
 //! **Collections:**
-//! - **users**: { pubkey } — one doc per wallet
-//! - **games**: { _id, pin, creator_pubkey, joiner_pubkey, status, created_at } — exactly 2 players per game
+//! - **users**: { pubkey, rating, rating_history } — one doc per wallet
+//! - **games**: { _id, pin, creator_pubkey, joiner_pubkey, status, created_at, rounds } — exactly 2 players per game
 
 use axum::{
     extract::{Path, State},
@@ -10,7 +10,7 @@ use axum::{
     Json, Router,
 };
 
-use crate::auth::AuthUser;
+use crate::auth::{AuthUser, MaybeAuthUser};
 use mongodb::{
     bson::doc,
     options::IndexOptions,
@@ -18,11 +18,23 @@ use mongodb::{
     Database,
     IndexModel,
 };
+use rps_escrow_client::instructions::BatchGame;
+use rps_escrow_client::pda;
 use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
 use uuid::Uuid;
 
+use crate::challenges::Challenge;
 use crate::error::ApiError;
+use crate::fees::PriorityFee;
+use crate::pause::PauseMonitor;
+use crate::players;
+use crate::rating;
+use crate::resolver::Resolver;
+use crate::sender::SendAttempt;
 use crate::solana::{self, SolanaAppClient};
+use crate::tx_error::{self, TxError};
+use crate::verdict::{self, Verdict};
 
 /// Path parameter for game ID.
 #[derive(Deserialize)]
@@ -78,21 +90,141 @@ pub struct Game {
     /// Stake amount per player in lamports.
     #[serde(default)]
     pub amount_per_player: i64,
+    /// Rules of the game; absent on games created before formats were stored (best of one).
+    #[serde(default)]
+    pub format: GameFormat,
     /// Game escrow PDA (base58).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub game_escrow_pubkey: Option<String>,
     /// Vault PDA (base58).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub vault_pubkey: Option<String>,
+    /// `GameResult` receipt PDA (base58), written on-chain by resolve or refund.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_result_pubkey: Option<String>,
+    /// On-chain `create_game` / `create_challenge` signature (set by the indexer).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub create_tx: Option<String>,
+    /// On-chain `join_game` signature (set by the indexer).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub join_tx: Option<String>,
+    /// On-chain `cancel` signature (set by the indexer).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel_tx: Option<String>,
+    /// On-chain `refund` signature (set by the indexer).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refund_tx: Option<String>,
+    /// Signed verdict the winner can claim on-chain (see [`crate::verdict`]); the game stays
+    /// `resolving` until it is claimed or the API resolves it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<Verdict>,
     /// On-chain resolve transaction signature (set on success).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub resolve_tx: Option<String>,
-    /// Error message when on-chain resolve failed (status = resolve_failed).
+    /// Priority fee and compute-unit limit of the resolve transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolve_priority_fee: Option<PriorityFee>,
+    /// Every signed resolve transaction and its outcome, in send order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resolve_attempts: Vec<SendAttempt>,
+    /// Why on-chain resolve failed (status = resolve_failed): decoded program error and logs.
+    #[serde(
+        default,
+        deserialize_with = "tx_error::deserialize_legacy",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub resolve_error: Option<TxError>,
+    /// Completed rounds in play order (draws included, the decisive round last).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rounds: Vec<Round>,
+    /// Listed in matchmaking (creator opted in at creation).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matchmaking: Option<bool>,
+    /// Direct challenge: only `challenge.opponent_pubkey` may join (see [`crate::challenges`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub challenge: Option<Challenge>,
+    /// False when the creator disabled spectators; absent means spectating is allowed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spectatable: Option<bool>,
+    /// Set when the game reaches `finished` (same format as `created_at`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    /// True once this game has been counted in `player_stats` (see [`crate::players`]).
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub resolve_error: Option<String>,
+    pub stats_recorded: Option<bool>,
+}
+
+impl Game {
+    /// True while exactly one player has chosen in the current round.
+    pub fn round_pending(&self) -> bool {
+        self.creator_choice.is_some() != self.joiner_choice.is_some()
+    }
+
+    /// Hides a choice in the current round from everyone but the player who made it.
+    /// Once both have chosen the round is settled and both choices are shown.
+    pub fn redact_pending_choice(&mut self, viewer: Option<&str>) {
+        if !self.round_pending() {
+            return;
+        }
+        if viewer != Some(self.creator_pubkey.as_str()) {
+            self.creator_choice = None;
+        }
+        if viewer.is_none() || viewer != self.joiner_pubkey.as_deref() {
+            self.joiner_choice = None;
+        }
+    }
+}
+
+/// Response for `GET /games/:game_id`: the game with pending choices redacted for the viewer.
+#[derive(Serialize)]
+pub struct GameView {
+    #[serde(flatten)]
+    pub game: Game,
+    pub creator_has_chosen: bool,
+    pub joiner_has_chosen: bool,
 }
 
-#[derive(Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+impl GameView {
+    pub fn for_viewer(mut game: Game, viewer: Option<&str>) -> Self {
+        let creator_has_chosen = game.creator_choice.is_some();
+        let joiner_has_chosen = game.joiner_choice.is_some();
+        game.redact_pending_choice(viewer);
+        Self {
+            game,
+            creator_has_chosen,
+            joiner_has_chosen,
+        }
+    }
+}
+
+/// One completed round: both choices and the round winner (None for a draw).
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct Round {
+    pub creator_choice: String,
+    pub joiner_choice: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub winner_pubkey: Option<String>,
+}
+
+/// How a game is played.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GameFormat {
+    /// A single decisive round: draws are replayed until someone wins.
+    #[default]
+    BestOfOne,
+}
+
+impl GameFormat {
+    /// Rules as shown to players, e.g. in a shared game link.
+    pub fn description(self) -> &'static str {
+        match self {
+            GameFormat::BestOfOne => "Best of one: draws are replayed",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum GameStatus {
     #[default]
@@ -104,11 +236,19 @@ pub enum GameStatus {
     /// On-chain resolve failed; needs manual retry.
     ResolveFailed,
     Cancelled,
+    /// Both deposits returned by the authority's on-chain `refund` (seen by the indexer).
+    Refunded,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct User {
     pub pubkey: String,
+    /// Elo rating, absent until the first finished game (see [`crate::rating`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating: Option<i64>,
+    /// Rating changes, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rating_history: Vec<crate::rating::RatingChange>,
 }
 
 /// Request body for creating a game. Creator identity from SIWS auth.
@@ -117,6 +257,36 @@ pub struct CreateGameRequest {
     pub game_id: Option<String>,
     #[serde(default)]
     pub amount_per_player: i64,
+    /// List the game in `GET /games/matchmaking` so rated opponents can find it without the PIN being shared.
+    #[serde(default)]
+    pub matchmaking: bool,
+}
+
+/// Response for `GET /games/lookup/:pin`: the game plus the creator's current rating.
+#[derive(Serialize)]
+pub struct LookupGameResponse {
+    #[serde(flatten)]
+    pub game: Game,
+    pub creator_rating: i64,
+}
+
+/// A waiting game offered by matchmaking.
+#[derive(Serialize)]
+pub struct MatchmakingCandidate {
+    pub game_id: String,
+    pub pin: String,
+    pub creator_pubkey: String,
+    pub creator_rating: i64,
+    pub amount_per_player: i64,
+    /// True if the creator is within [`MATCHMAKING_RATING_BAND`] of the caller.
+    pub within_band: bool,
+}
+
+/// Response for `GET /games/matchmaking`.
+#[derive(Serialize)]
+pub struct MatchmakingResponse {
+    pub rating: i64,
+    pub candidates: Vec<MatchmakingCandidate>,
 }
 
 #[derive(Serialize)]
@@ -125,11 +295,19 @@ pub struct CreateGameResponse {
     pub pin: String,
 }
 
-/// Shared state for routes: MongoDB database and optional Solana client for resolve.
+/// Shared state for routes: MongoDB database, optional Solana client for resolve, and the admin
+/// key (None disables `/admin/*`).
 #[derive(Clone)]
 pub struct AppState {
     pub db: Database,
     pub solana: Option<SolanaAppClient>,
+    /// Batches on-chain resolves; None when no resolve signer is configured.
+    pub resolver: Option<Resolver>,
+    pub admin_api_key: Option<String>,
+    /// Last known pause flag of the escrow program.
+    pub pause: PauseMonitor,
+    /// Public origin of the API, for absolute links handed to wallets (`PUBLIC_BASE_URL`).
+    pub public_base_url: String,
 }
 
 /// Max attempts when reserving a PIN to avoid collision with an existing waiting game.
@@ -193,13 +371,35 @@ async fn reserve_pin(db: &Database) -> Result<String, ApiError> {
     ))
 }
 
-/// Minimum bet per player in lamports (0.001 SOL). Must match program MIN_BET_LAMPORTS.
-const MIN_BET_LAMPORTS: i64 = 1_000_000;
+/// Current time in the format stored in `created_at` / `finished_at` (sorts lexicographically).
+pub fn now_utc_string() -> String {
+    utc_string(chrono::Utc::now())
+}
+
+/// `time` in the format of [`now_utc_string`], e.g. to compare with stored timestamps.
+pub fn utc_string(time: chrono::DateTime<chrono::Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+/// Attempts at an on-chain resolve; only transient failures are retried.
+const RESOLVE_MAX_ATTEMPTS: u32 = 3;
+/// Delay before the second resolve attempt, doubled for each later one.
+const RESOLVE_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Minimum bet per player in lamports (0.001 SOL), as enforced by the program.
+const MIN_BET_LAMPORTS: i64 = rps_escrow_client::fees::MIN_BET_LAMPORTS as i64;
 
 const VALID_CHOICES: [&str; 3] = ["rock", "paper", "scissors"];
 
+/// Matchmaking prefers creators whose rating is within this many points of the caller's.
+pub const MATCHMAKING_RATING_BAND: i64 = 200;
+
+/// Max waiting games scanned and returned by matchmaking.
+const MATCHMAKING_SCAN_LIMIT: i64 = 200;
+const MATCHMAKING_MAX_CANDIDATES: usize = 10;
+
 /// Shortens a pubkey for log display (first 6 chars + "..." + last 6 chars).
-fn short_pk(pk: &str) -> String {
+pub(crate) fn short_pk(pk: &str) -> String {
     let chars: Vec<char> = pk.chars().collect();
     let n = chars.len();
     if n <= 12 {
@@ -241,19 +441,34 @@ async fn create_game(
     auth: AuthUser,
     Json(body): Json<CreateGameRequest>,
 ) -> Result<Json<CreateGameResponse>, ApiError> {
-    let creator_pubkey = auth.pubkey.trim();
+    let game = insert_new_game(&state, auth.pubkey.trim(), &body, None).await?;
+    Ok(Json(CreateGameResponse {
+        game_id: game.id,
+        pin: game.pin,
+    }))
+}
+
+/// Validates the stake, reserves a PIN, derives the escrow PDAs and inserts a `waiting` game.
+/// Shared by open games and direct challenges (`challenge` set).
+pub(crate) async fn insert_new_game(
+    state: &AppState,
+    creator_pubkey: &str,
+    body: &CreateGameRequest,
+    challenge: Option<Challenge>,
+) -> Result<Game, ApiError> {
     if body.amount_per_player < MIN_BET_LAMPORTS {
         log::warn!("Create game rejected: amount {} below minimum {}", body.amount_per_player, MIN_BET_LAMPORTS);
         return Err(ApiError::bad_request("amount_per_player must be at least 0.001 SOL (1_000_000 lamports)"));
     }
+    state.pause.ensure_open()?;
 
     let game_id = body.game_id.clone()
         .filter(|id| Uuid::parse_str(id).is_ok())
         .unwrap_or_else(|| Uuid::new_v4().to_string());
     let pin = reserve_pin(&state.db).await?;
-    let created_at = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string();
+    let created_at = now_utc_string();
 
-    let (game_escrow_pubkey, vault_pubkey) = {
+    let (game_escrow_pubkey, vault_pubkey, game_result_pubkey) = {
         let program_id = state.solana.as_ref()
             .map(|s| s.program_id)
             .unwrap_or_else(solana::program_id);
@@ -261,14 +476,14 @@ async fn create_game(
             .map_err(|_| ApiError::bad_request("invalid creator_pubkey (not a valid base58 pubkey)"))?;
         let uuid = Uuid::parse_str(&game_id).unwrap();
         let game_id_bytes: [u8; 16] = *uuid.as_bytes();
-        let escrow = solana::game_escrow_pda(&program_id, &creator_pk, &game_id_bytes);
-        let vault = solana::vault_pda(&program_id, &escrow);
-        (escrow.to_string(), vault.to_string())
+        let (escrow, vault) = pda::game_addresses(&program_id, &creator_pk, &game_id_bytes);
+        let result = pda::game_result_pda(&program_id, &escrow).0;
+        (escrow.to_string(), vault.to_string(), result.to_string())
     };
 
     let game = Game {
         id: game_id.clone(),
-        pin: pin.clone(),
+        pin,
         creator_pubkey: creator_pubkey.to_string(),
         joiner_pubkey: None,
         status: GameStatus::Waiting,
@@ -278,20 +493,48 @@ async fn create_game(
         winner_pubkey: None,
         round_cleared_for_draw: None,
         amount_per_player: body.amount_per_player,
+        format: GameFormat::BestOfOne,
         game_escrow_pubkey: Some(game_escrow_pubkey),
         vault_pubkey: Some(vault_pubkey),
+        game_result_pubkey: Some(game_result_pubkey),
+        create_tx: None,
+        join_tx: None,
+        cancel_tx: None,
+        refund_tx: None,
+        verdict: None,
         resolve_tx: None,
+        resolve_priority_fee: None,
+        resolve_attempts: Vec::new(),
         resolve_error: None,
+        rounds: Vec::new(),
+        matchmaking: (body.matchmaking && challenge.is_none()).then_some(true),
+        challenge,
+        spectatable: None,
+        finished_at: None,
+        stats_recorded: None,
     };
 
-    let users = state.db.collection::<User>("users");
     let games = state.db.collection::<Game>("games");
+    upsert_user(&state.db, creator_pubkey).await?;
+
+    games
+        .insert_one(&game, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to insert game: {}", e);
+            ApiError::internal(e.to_string())
+        })?;
 
-    // Ensure creator exists in users (upsert by pubkey)
-    users
+    log::info!("Game created game_id={} creator_pubkey={}", game_id, creator_pubkey);
+    Ok(game)
+}
+
+/// Ensures the wallet exists in `users` (upsert by pubkey).
+pub(crate) async fn upsert_user(db: &Database, pubkey: &str) -> Result<(), ApiError> {
+    db.collection::<User>("users")
         .update_one(
-            doc! { "pubkey": creator_pubkey },
-            doc! { "$setOnInsert": { "pubkey": creator_pubkey } },
+            doc! { "pubkey": pubkey },
+            doc! { "$setOnInsert": { "pubkey": pubkey } },
             mongodb::options::UpdateOptions::builder()
                 .upsert(true)
                 .build(),
@@ -301,23 +544,14 @@ async fn create_game(
             log::error!("Failed to upsert user: {}", e);
             ApiError::internal(e.to_string())
         })?;
-
-    games
-        .insert_one(&game, None)
-        .await
-        .map_err(|e| {
-            log::error!("Failed to insert game: {}", e);
-            ApiError::internal(e.to_string())
-        })?;
-
-    log::info!("Game created game_id={} creator_pubkey={}", game_id, creator_pubkey);
-    Ok(Json(CreateGameResponse { game_id, pin }))
+    Ok(())
 }
 
 async fn get_game(
     State(state): State<AppState>,
+    MaybeAuthUser(auth): MaybeAuthUser,
     Path(path): Path<GameIdPath>,
-) -> Result<Json<Game>, ApiError> {
+) -> Result<Json<GameView>, ApiError> {
     let games = state.db.collection::<Game>("games");
     let filter = doc! { "_id": &path.game_id };
     let game = games
@@ -327,10 +561,17 @@ async fn get_game(
             log::error!("Failed to find game: {}", e);
             ApiError::internal(e.to_string())
         })?;
-    match game {
-        Some(g) => Ok(Json(g)),
-        None => Err(ApiError::not_found("Game not found")),
+    let Some(game) = game else {
+        return Err(ApiError::not_found("Game not found"));
+    };
+    let viewer = auth.as_ref().map(|a| a.pubkey.as_str());
+    let is_player =
+        viewer.is_some_and(|v| v == game.creator_pubkey || game.joiner_pubkey.as_deref() == Some(v));
+    // Spectators only see games that allow them.
+    if game.spectatable == Some(false) && !is_player {
+        return Err(ApiError::forbidden("Spectating is disabled for this game"));
     }
+    Ok(Json(GameView::for_viewer(game, viewer)))
 }
 
 async fn submit_choice(
@@ -392,12 +633,10 @@ async fn submit_choice(
                 }
             }
         }
+    } else if is_creator {
+        doc! { "$set": { "creator_choice": choice.as_str() } }
     } else {
-        if is_creator {
-            doc! { "$set": { "creator_choice": choice.as_str() } }
-        } else {
-            doc! { "$set": { "joiner_choice": choice.as_str() } }
-        }
+        doc! { "$set": { "joiner_choice": choice.as_str() } }
     };
 
     games
@@ -454,7 +693,15 @@ async fn submit_choice(
                             "winner_pubkey": winner.clone(),
                             "status": "resolving",
                             "resolve_error": null,
-                            "resolve_tx": null
+                            "resolve_tx": null,
+                            "verdict": null
+                        },
+                        "$push": {
+                            "rounds": {
+                                "creator_choice": cc.as_str(),
+                                "joiner_choice": jc.as_str(),
+                                "winner_pubkey": winner.clone()
+                            }
                         }
                     },
                     None,
@@ -464,10 +711,19 @@ async fn submit_choice(
                     log::error!("Failed to set winner/resolving: {}", e);
                     ApiError::internal(e.to_string())
                 })?;
+            game.rounds.push(Round {
+                creator_choice: cc.clone(),
+                joiner_choice: jc.clone(),
+                winner_pubkey: Some(winner.clone()),
+            });
             game.winner_pubkey = Some(winner.clone());
             game.status = GameStatus::Resolving;
 
-            // Step 2: attempt on-chain resolve
+            // Step 2: a verdict for the winner to claim, else attempt on-chain resolve
+            if let Some(verdict) = verdict::issue(&state, &game, winner).await {
+                game.verdict = Some(verdict);
+                return Ok(Json(game));
+            }
             let resolve_result = try_resolve_on_chain(
                 &state, &games, &path.game_id, &game.creator_pubkey, winner,
             ).await;
@@ -476,9 +732,9 @@ async fn submit_choice(
                     game.status = GameStatus::Finished;
                     game.resolve_tx = Some(sig);
                 }
-                Err(err_msg) => {
+                Err(err) => {
                     game.status = GameStatus::ResolveFailed;
-                    game.resolve_error = Some(err_msg);
+                    game.resolve_error = Some(err);
                 }
             }
         } else {
@@ -499,6 +755,12 @@ async fn submit_choice(
                             "creator_choice": null,
                             "joiner_choice": null,
                             "round_cleared_for_draw": true
+                        },
+                        "$push": {
+                            "rounds": {
+                                "creator_choice": cc.as_str(),
+                                "joiner_choice": jc.as_str()
+                            }
                         }
                     },
                     None,
@@ -508,6 +770,11 @@ async fn submit_choice(
                     log::error!("Failed to clear choices for draw: {}", e);
                     ApiError::internal(e.to_string())
                 })?;
+            game.rounds.push(Round {
+                creator_choice: cc.clone(),
+                joiner_choice: jc.clone(),
+                winner_pubkey: None,
+            });
             game.creator_choice = None;
             game.joiner_choice = None;
             game.winner_pubkey = None;
@@ -562,7 +829,7 @@ async fn cancel_game(
 async fn lookup_game_by_pin(
     State(state): State<AppState>,
     Path(pin): Path<String>,
-) -> Result<Json<Game>, ApiError> {
+) -> Result<Json<LookupGameResponse>, ApiError> {
     let pin = pin.trim();
     if pin.len() != 4 || !pin.chars().all(|c| c.is_ascii_digit()) {
         return Err(ApiError::bad_request("pin must be 4 digits"));
@@ -571,7 +838,8 @@ async fn lookup_game_by_pin(
     let filter = doc! {
         "pin": pin,
         "joiner_pubkey": null,
-        "status": "waiting"
+        "status": "waiting",
+        "challenge": null
     };
     let game = games
         .find_one(filter, None)
@@ -580,10 +848,91 @@ async fn lookup_game_by_pin(
             log::error!("Failed to lookup game by pin: {}", e);
             ApiError::internal(e.to_string())
         })?;
-    match game {
-        Some(g) => Ok(Json(g)),
-        None => Err(ApiError::not_found("No waiting game found for this PIN")),
+    let game = match game {
+        Some(g) => g,
+        None => return Err(ApiError::not_found("No waiting game found for this PIN")),
+    };
+    let creator_rating = rating::get_rating(&state.db, &game.creator_pubkey)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to get creator rating: {}", e);
+            ApiError::internal(e.to_string())
+        })?;
+    Ok(Json(LookupGameResponse { game, creator_rating }))
+}
+
+/// Waiting matchmaking games for the caller: creators within the rating band first, then the
+/// rest, each group ordered by rating distance.
+async fn matchmaking(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<MatchmakingResponse>, ApiError> {
+    let pubkey = auth.pubkey.trim();
+    let my_rating = rating::get_rating(&state.db, pubkey).await.map_err(|e| {
+        log::error!("Failed to get player rating: {}", e);
+        ApiError::internal(e.to_string())
+    })?;
+
+    let games = state.db.collection::<Game>("games");
+    let filter = doc! {
+        "status": "waiting",
+        "joiner_pubkey": null,
+        "matchmaking": true,
+        "creator_pubkey": { "$ne": pubkey },
+    };
+    let opts = mongodb::options::FindOptions::builder()
+        .sort(doc! { "created_at": 1 })
+        .limit(MATCHMAKING_SCAN_LIMIT)
+        .build();
+    let mut cursor = games.find(filter, opts).await.map_err(|e| {
+        log::error!("Failed to list matchmaking games: {}", e);
+        ApiError::internal(e.to_string())
+    })?;
+    let mut waiting = Vec::new();
+    while cursor.advance().await.map_err(|e| ApiError::internal(e.to_string()))? {
+        waiting.push(cursor.deserialize_current().map_err(|e| ApiError::internal(e.to_string()))?);
     }
+
+    let creators: Vec<&str> = waiting.iter().map(|g| g.creator_pubkey.as_str()).collect();
+    let mut ratings = std::collections::HashMap::new();
+    let mut users = state
+        .db
+        .collection::<User>("users")
+        .find(doc! { "pubkey": { "$in": creators } }, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to load creator ratings: {}", e);
+            ApiError::internal(e.to_string())
+        })?;
+    while users.advance().await.map_err(|e| ApiError::internal(e.to_string()))? {
+        let user = users.deserialize_current().map_err(|e| ApiError::internal(e.to_string()))?;
+        ratings.insert(user.pubkey, user.rating.unwrap_or(rating::INITIAL_RATING));
+    }
+
+    let mut candidates: Vec<MatchmakingCandidate> = waiting
+        .into_iter()
+        .map(|g| {
+            let creator_rating = ratings
+                .get(&g.creator_pubkey)
+                .copied()
+                .unwrap_or(rating::INITIAL_RATING);
+            MatchmakingCandidate {
+                within_band: (creator_rating - my_rating).abs() <= MATCHMAKING_RATING_BAND,
+                game_id: g.id,
+                pin: g.pin,
+                creator_pubkey: g.creator_pubkey,
+                creator_rating,
+                amount_per_player: g.amount_per_player,
+            }
+        })
+        .collect();
+    candidates.sort_by_key(|c| (!c.within_band, (c.creator_rating - my_rating).abs()));
+    candidates.truncate(MATCHMAKING_MAX_CANDIDATES);
+
+    Ok(Json(MatchmakingResponse {
+        rating: my_rating,
+        candidates,
+    }))
 }
 
 async fn join_game(
@@ -597,29 +946,17 @@ async fn join_game(
         log::warn!("Join game rejected: invalid pin (must be 4 digits)");
         return Err(ApiError::bad_request("pin must be 4 digits"));
     }
+    state.pause.ensure_open()?;
 
     let games = state.db.collection::<Game>("games");
-    let users = state.db.collection::<User>("users");
-
-    // Ensure joiner exists in users
-    users
-        .update_one(
-            doc! { "pubkey": joiner_pubkey },
-            doc! { "$setOnInsert": { "pubkey": joiner_pubkey } },
-            mongodb::options::UpdateOptions::builder()
-                .upsert(true)
-                .build(),
-        )
-        .await
-        .map_err(|e| {
-            log::error!("Failed to upsert joiner user: {}", e);
-            ApiError::internal(e.to_string())
-        })?;
+    upsert_user(&state.db, joiner_pubkey).await?;
 
+    // Direct challenges are joined through `POST /games/:game_id/accept`, not by PIN.
     let filter = doc! {
         "pin": pin,
         "joiner_pubkey": null,
-        "status": "waiting"
+        "status": "waiting",
+        "challenge": null
     };
     let update = doc! {
         "$set": {
@@ -650,96 +987,147 @@ async fn join_game(
     }
 }
 
-/// Attempts on-chain resolve. On success updates DB to `finished` + stores tx sig.
-/// On failure updates DB to `resolve_failed` + stores error.
-/// Returns Ok(signature) or Err(error_message).
-async fn try_resolve_on_chain(
+/// Attempts on-chain resolve, retrying transient failures. On success updates DB to `finished`
+/// and stores the tx sig. On failure updates DB to `resolve_failed` and stores the decoded error.
+/// Returns Ok(signature) or Err(error).
+pub(crate) async fn try_resolve_on_chain(
     state: &AppState,
     games: &Collection<Game>,
     game_id: &str,
     creator_pubkey: &str,
     winner_pubkey: &str,
-) -> Result<String, String> {
-    let solana = match &state.solana {
-        Some(s) if s.can_resolve() => s,
-        _ => {
-            let msg = "Solana resolve not configured".to_string();
-            log::error!("try_resolve_on_chain: {}", msg);
-            games
-                .update_one(
-                    doc! { "_id": game_id },
-                    doc! { "$set": { "status": "resolve_failed", "resolve_error": &msg } },
-                    None,
-                )
-                .await
-                .ok();
-            return Err(msg);
+) -> Result<String, TxError> {
+    let resolver = match &state.resolver {
+        Some(r) => r,
+        None => {
+            let err = TxError::other("NotConfigured", "Solana resolve not configured", true);
+            log::error!("try_resolve_on_chain: {}", err);
+            mark_resolve_failed(games, game_id, &err).await;
+            return Err(err);
         }
     };
 
     let game_id_bytes = match uuid::Uuid::parse_str(game_id) {
         Ok(u) => *u.as_bytes(),
         Err(e) => {
-            let msg = format!("invalid game_id UUID: {}", e);
-            log::error!("try_resolve_on_chain: {}", msg);
-            games
-                .update_one(
-                    doc! { "_id": game_id },
-                    doc! { "$set": { "status": "resolve_failed", "resolve_error": &msg } },
-                    None,
-                )
-                .await
-                .ok();
-            return Err(msg);
+            let err = TxError::other("InvalidGameId", format!("invalid game_id UUID: {}", e), true);
+            log::error!("try_resolve_on_chain: {}", err);
+            mark_resolve_failed(games, game_id, &err).await;
+            return Err(err);
+        }
+    };
+
+    let (creator, winner) = match (creator_pubkey.parse::<Pubkey>(), winner_pubkey.parse::<Pubkey>()) {
+        (Ok(creator), Ok(winner)) => (creator, winner),
+        (Err(e), _) | (_, Err(e)) => {
+            let err = TxError::other("InvalidPubkey", e.to_string(), true);
+            log::error!("try_resolve_on_chain: {}", err);
+            mark_resolve_failed(games, game_id, &err).await;
+            return Err(err);
+        }
+    };
+    let batch_game = BatchGame {
+        creator,
+        game_id: game_id_bytes,
+        winner,
+    };
+
+    let mut attempt = 1;
+    let result = loop {
+        let (result, attempts) = resolver.resolve(batch_game).await;
+        record_resolve_attempts(games, game_id, &attempts).await;
+        match result {
+            Err(e) if !e.permanent && attempt < RESOLVE_MAX_ATTEMPTS => {
+                log::warn!(
+                    "On-chain resolve attempt {} failed game_id={} (retrying): {}",
+                    attempt, game_id, e
+                );
+                tokio::time::sleep(RESOLVE_RETRY_DELAY * 2u32.pow(attempt - 1)).await;
+                attempt += 1;
+            }
+            result => break result,
         }
     };
 
-    match solana.resolve(game_id_bytes, creator_pubkey, winner_pubkey) {
+    match result {
         Ok(res) => {
             log::info!(
                 "Game resolved on-chain game_id={} winner={} sig={}",
                 game_id, winner_pubkey, res.signature
             );
+            let priority_fee = mongodb::bson::to_bson(&res.priority_fee)
+                .map_err(|e| TxError::other("Internal", e.to_string(), true))?;
             games
                 .update_one(
                     doc! { "_id": game_id },
                     doc! { "$set": {
                         "status": "finished",
                         "resolve_tx": &res.signature,
-                        "resolve_error": null
+                        "resolve_priority_fee": priority_fee,
+                        "resolve_error": null,
+                        "finished_at": now_utc_string()
                     }},
                     None,
                 )
                 .await
                 .map_err(|e| {
                     log::error!("Failed to update game to finished: {}", e);
-                    e.to_string()
+                    TxError::other("Internal", e.to_string(), false)
                 })?;
+            players::record_finished_game(&state.db, game_id).await;
             Ok(res.signature)
         }
         Err(e) => {
             log::error!(
-                "On-chain resolve failed game_id={} winner={}: {}",
-                game_id, winner_pubkey, e
+                "On-chain resolve failed game_id={} winner={} attempts={}: {}",
+                game_id, winner_pubkey, attempt, e
             );
-            games
-                .update_one(
-                    doc! { "_id": game_id },
-                    doc! { "$set": { "status": "resolve_failed", "resolve_error": &e } },
-                    None,
-                )
-                .await
-                .ok();
+            mark_resolve_failed(games, game_id, &e).await;
             Err(e)
         }
     }
 }
 
+/// Appends sent transactions to `resolve_attempts`.
+async fn record_resolve_attempts(games: &Collection<Game>, game_id: &str, attempts: &[SendAttempt]) {
+    if attempts.is_empty() {
+        return;
+    }
+    let Ok(attempts) = mongodb::bson::to_bson(attempts) else {
+        return;
+    };
+    if let Err(e) = games
+        .update_one(
+            doc! { "_id": game_id },
+            doc! { "$push": { "resolve_attempts": { "$each": attempts } } },
+            None,
+        )
+        .await
+    {
+        log::error!("Failed to record resolve attempts game_id={}: {}", game_id, e);
+    }
+}
+
+async fn mark_resolve_failed(games: &Collection<Game>, game_id: &str, err: &TxError) {
+    let Ok(err_doc) = mongodb::bson::to_bson(err) else {
+        return;
+    };
+    games
+        .update_one(
+            doc! { "_id": game_id },
+            doc! { "$set": { "status": "resolve_failed", "resolve_error": err_doc } },
+            None,
+        )
+        .await
+        .ok();
+}
+
 pub fn games_routes(state: AppState) -> Router {
     Router::new()
         .route("/games/create", post(create_game))
         .route("/games/join", post(join_game))
         .route("/games/lookup/:pin", get(lookup_game_by_pin))
+        .route("/games/matchmaking", get(matchmaking))
         .route("/games/:game_id", get(get_game))
         .route("/games/:game_id/choice", post(submit_choice))
         .route("/games/:game_id/cancel", post(cancel_game))
@@ -1,28 +1,61 @@
-//! Health check and root routes (no app state).
+//! Health check and root routes (the escrow's pause flag only), and the `/ready` readiness probe.
 
-use axum::{routing::get, Json, Router};
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
 use serde::Serialize;
 
+use crate::balances::{AccountBalance, BalanceMonitor};
+use crate::pause::{PauseMonitor, PauseStatus};
+
 #[derive(Serialize)]
 pub struct Health {
     pub status: &'static str,
     pub service: &'static str,
+    /// Whether new games and joins are paused on-chain.
+    pub escrow: PauseStatus,
+}
+
+#[derive(Serialize)]
+pub struct Readiness {
+    pub status: &'static str,
+    pub accounts: Vec<AccountBalance>,
 }
 
 async fn root() -> &'static str {
     "seeker-rps-api"
 }
 
-async fn health() -> Json<Health> {
+async fn health(State(pause): State<PauseMonitor>) -> Json<Health> {
     Json(Health {
         status: "ok",
         service: "seeker-rps-api",
+        escrow: pause.status(),
     })
 }
 
 /// Routes for / and /health.
-pub fn routes() -> Router {
+pub fn routes(pause: PauseMonitor) -> Router {
     Router::new()
         .route("/", get(root))
         .route("/health", get(health))
+        .with_state(pause)
+}
+
+/// 503 while a monitored account is below its minimum balance.
+async fn ready(State(monitor): State<BalanceMonitor>) -> (StatusCode, Json<Readiness>) {
+    let ready = monitor.is_ready();
+    let readiness = Readiness {
+        status: if ready { "ready" } else { "not_ready" },
+        accounts: monitor.snapshot(),
+    };
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
+/// Route for /ready.
+pub fn readiness_routes(monitor: BalanceMonitor) -> Router {
+    Router::new().route("/ready", get(ready)).with_state(monitor)
 }
@@ -0,0 +1,425 @@
+//! Indexer keeping `games` in sync with the rps-escrow program on-chain.
+//!
+//! **Collections:**
+//! - **indexer_state**: { _id: "rps_escrow", last_signature, last_slot, updated_at } — cursor
+//!
+//! The source of truth is `getSignaturesForAddress(program)`: every program transaction newer than
+//! the cursor is fetched oldest first, its rps-escrow instructions decoded by discriminator and
+//! applied to the game whose `game_escrow_pubkey` matches. The cursor advances after each
+//! transaction, so a restart resumes where the indexer stopped. A `logsSubscribe` websocket on the
+//! program only wakes the indexer early; polling every `INDEXER_POLL_SECS` covers dropped
+//! connections and missed notifications.
+//!
+//! Updates are conditional on the game's status, so replaying a transaction (or indexing one the
+//! API already recorded, e.g. its own resolve) changes nothing. Confirmed creates and joins
+//! overwrite a signature linked by `POST /games/:game_id/tx/submit` that never landed. Instruction
+//! accounts include addresses loaded from lookup tables (v0 transactions); a transaction whose
+//! accounts cannot be resolved fails the catch-up instead of being skipped.
+//!
+//! Without a cursor (first start), the whole program history is indexed, back to its first
+//! transaction, unless `INDEXER_BACKFILL_LIMIT` caps it.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use mongodb::{bson::doc, options::UpdateOptions, Database};
+use rps_escrow_client::instructions;
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::{
+    RpcTransactionConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status_client_types::{UiLoadedAddresses, UiTransactionEncoding};
+use tokio::sync::Notify;
+
+use crate::games::{now_utc_string, Game};
+use crate::players;
+
+const CURSOR_ID: &str = "rps_escrow";
+
+/// Page size of `getSignaturesForAddress` (RPC maximum).
+const SIGNATURE_PAGE: usize = 1000;
+
+/// Statuses a cancel, refund or resolve may end: the escrow is still open. Anything else is
+/// already final, so replays cannot move a game between final statuses.
+const OPEN_STATUSES: [&str; 4] = ["waiting", "active", "resolving", "resolve_failed"];
+
+/// Delay before reconnecting the log subscription.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Where the indexer stopped.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IndexerCursor {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub last_signature: String,
+    pub last_slot: i64,
+    pub updated_at: String,
+}
+
+/// An rps-escrow instruction the indexer understands, for one game. `game_escrow` is account 1 in
+/// all of them but `resolve_with_attestation` (account 0), which is a `Resolve`; a `resolve_batch`
+/// is one `Resolve` per game.
+#[derive(Debug, PartialEq)]
+pub enum EscrowInstruction {
+    Create,
+    Join { joiner: Pubkey },
+    Cancel,
+    Refund,
+    Resolve { winner: Pubkey },
+}
+
+/// Decodes one single-game instruction given its data and account keys. None for anything else:
+/// `resolve_batch` (see [`decode_game_instructions`]), the resolve config instructions and `set_paused`.
+pub(crate) fn decode_instruction(data: &[u8], accounts: &[Pubkey]) -> Option<(Pubkey, EscrowInstruction)> {
+    let decoded = instructions::EscrowInstruction::decode(data)?;
+    if let instructions::EscrowInstruction::ResolveWithAttestation { winner, .. } = decoded {
+        return Some((*accounts.first()?, EscrowInstruction::Resolve { winner }));
+    }
+    let game_escrow = *accounts.get(1)?;
+    let ix = match decoded {
+        instructions::EscrowInstruction::CreateGame { .. }
+        | instructions::EscrowInstruction::CreateChallenge { .. } => EscrowInstruction::Create,
+        instructions::EscrowInstruction::JoinGame => EscrowInstruction::Join {
+            joiner: *accounts.first()?,
+        },
+        instructions::EscrowInstruction::Cancel => EscrowInstruction::Cancel,
+        instructions::EscrowInstruction::Refund => EscrowInstruction::Refund,
+        instructions::EscrowInstruction::Resolve { winner } => EscrowInstruction::Resolve { winner },
+        instructions::EscrowInstruction::ResolveBatch { .. }
+        | instructions::EscrowInstruction::ResolveWithAttestation { .. }
+        | instructions::EscrowInstruction::InitResolveConfig { .. }
+        | instructions::EscrowInstruction::UpdateResolveConfig { .. }
+        | instructions::EscrowInstruction::SetPaused { .. } => return None,
+    };
+    Some((game_escrow, ix))
+}
+
+/// Decodes one instruction into an entry per game it touches: a `Resolve` per game of a
+/// `resolve_batch`, else as [`decode_instruction`].
+pub(crate) fn decode_game_instructions(data: &[u8], accounts: &[Pubkey]) -> Vec<(Pubkey, EscrowInstruction)> {
+    let decoded = instructions::EscrowInstruction::decode(data);
+    let Some(instructions::EscrowInstruction::ResolveBatch { winners }) = decoded else {
+        return decode_instruction(data, accounts).into_iter().collect();
+    };
+    instructions::resolve_batch_escrows(accounts)
+        .zip(winners)
+        .map(|(game_escrow, winner)| (game_escrow, EscrowInstruction::Resolve { winner }))
+        .collect()
+}
+
+/// Applies a decoded instruction to the matching game.
+async fn apply(
+    db: &Database,
+    game_escrow: &Pubkey,
+    ix: &EscrowInstruction,
+    signature: &str,
+) -> Result<(), mongodb::error::Error> {
+    let games = db.collection::<Game>("games");
+    let escrow = game_escrow.to_string();
+    let (filter, update) = match ix {
+        EscrowInstruction::Create => (
+            doc! { "game_escrow_pubkey": &escrow, "create_tx": { "$ne": signature } },
+            doc! { "$set": { "create_tx": signature } },
+        ),
+        EscrowInstruction::Join { .. } => (
+            doc! { "game_escrow_pubkey": &escrow, "join_tx": { "$ne": signature } },
+            doc! { "$set": { "join_tx": signature } },
+        ),
+        EscrowInstruction::Cancel => (
+            doc! { "game_escrow_pubkey": &escrow, "status": { "$in": OPEN_STATUSES.as_slice() } },
+            doc! { "$set": { "status": "cancelled", "cancel_tx": signature } },
+        ),
+        EscrowInstruction::Refund => (
+            doc! { "game_escrow_pubkey": &escrow, "status": { "$in": OPEN_STATUSES.as_slice() } },
+            doc! { "$set": {
+                "status": "refunded",
+                "refund_tx": signature,
+                "finished_at": now_utc_string()
+            }},
+        ),
+        EscrowInstruction::Resolve { winner } => (
+            doc! { "game_escrow_pubkey": &escrow, "status": { "$in": OPEN_STATUSES.as_slice() } },
+            doc! { "$set": {
+                "status": "finished",
+                "winner_pubkey": winner.to_string(),
+                "resolve_tx": signature,
+                "resolve_error": null,
+                "finished_at": now_utc_string()
+            }},
+        ),
+    };
+    let result = games.update_one(filter, update, None).await?;
+
+    // The joiner is only filled in if the API has not seen the join yet (PIN join or accept).
+    if let EscrowInstruction::Join { joiner } = ix {
+        let joined = games
+            .update_one(
+                doc! { "game_escrow_pubkey": &escrow, "status": "waiting", "joiner_pubkey": null },
+                doc! { "$set": { "joiner_pubkey": joiner.to_string(), "status": "active" } },
+                None,
+            )
+            .await?;
+        if joined.modified_count > 0 {
+            log::info!("Indexer: join recorded game_escrow={} joiner={}", escrow, joiner);
+        }
+    }
+    if result.modified_count > 0 {
+        log::info!("Indexer: {:?} game_escrow={} sig={}", ix, escrow, signature);
+        if let EscrowInstruction::Resolve { .. } = ix {
+            if let Some(game) = games.find_one(doc! { "game_escrow_pubkey": &escrow }, None).await? {
+                players::record_finished_game(db, &game.id).await;
+            }
+        }
+    } else if result.matched_count == 0 {
+        let known = games.count_documents(doc! { "game_escrow_pubkey": &escrow }, None).await?;
+        if known == 0 {
+            log::warn!("Indexer: no game for game_escrow={} sig={}", escrow, signature);
+        }
+    }
+    Ok(())
+}
+
+pub struct Indexer {
+    db: Database,
+    rpc: RpcClient,
+    program_id: Pubkey,
+    /// Most transactions indexed on a first start; None = the whole history.
+    backfill_limit: Option<usize>,
+}
+
+impl Indexer {
+    pub fn new(db: Database, rpc_url: String, program_id: Pubkey) -> Self {
+        Self {
+            db,
+            rpc: RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed()),
+            program_id,
+            backfill_limit: None,
+        }
+    }
+
+    /// Caps the first start to the `limit` most recent transactions.
+    pub fn with_backfill_limit(mut self, limit: Option<usize>) -> Self {
+        self.backfill_limit = limit;
+        self
+    }
+
+    async fn load_cursor(&self) -> Result<Option<IndexerCursor>, mongodb::error::Error> {
+        self.db
+            .collection::<IndexerCursor>("indexer_state")
+            .find_one(doc! { "_id": CURSOR_ID }, None)
+            .await
+    }
+
+    async fn save_cursor(&self, signature: &str, slot: u64) -> Result<(), mongodb::error::Error> {
+        self.db
+            .collection::<IndexerCursor>("indexer_state")
+            .update_one(
+                doc! { "_id": CURSOR_ID },
+                doc! { "$set": {
+                    "last_signature": signature,
+                    "last_slot": slot as i64,
+                    "updated_at": now_utc_string()
+                }},
+                UpdateOptions::builder().upsert(true).build(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Signatures newer than `until`, oldest first.
+    /// Each entry is (signature, slot, failed).
+    async fn pending_signatures(
+        &self,
+        until: Option<Signature>,
+    ) -> Result<Vec<(Signature, u64, bool)>, String> {
+        let mut newest_first = Vec::new();
+        let mut before = None;
+        loop {
+            let page = self
+                .rpc
+                .get_signatures_for_address_with_config(
+                    &self.program_id,
+                    GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until,
+                        limit: Some(SIGNATURE_PAGE),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                    },
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+            let done = page.len() < SIGNATURE_PAGE;
+            for entry in page {
+                let signature: Signature = entry.signature.parse().map_err(|e| format!("{:?}", e))?;
+                before = Some(signature);
+                newest_first.push((signature, entry.slot, entry.err.is_some()));
+            }
+            if done {
+                break;
+            }
+            if let (None, Some(limit)) = (until, self.backfill_limit) {
+                if newest_first.len() >= limit {
+                    newest_first.truncate(limit);
+                    log::warn!(
+                        "Indexer: backfill stopped at INDEXER_BACKFILL_LIMIT={}; older transactions skipped",
+                        limit
+                    );
+                    break;
+                }
+            }
+        }
+        newest_first.reverse();
+        Ok(newest_first)
+    }
+
+    /// rps-escrow instructions of a confirmed transaction, in order.
+    async fn decode_transaction(
+        &self,
+        signature: &Signature,
+    ) -> Result<Vec<(Pubkey, EscrowInstruction)>, String> {
+        let confirmed = self
+            .rpc
+            .get_transaction_with_config(
+                signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        // Failed transactions changed nothing on-chain.
+        if confirmed.transaction.meta.as_ref().is_some_and(|m| m.err.is_some()) {
+            return Ok(Vec::new());
+        }
+        let tx = confirmed
+            .transaction
+            .transaction
+            .decode()
+            .ok_or_else(|| format!("cannot decode transaction {}", signature))?;
+
+        // Account keys as instructions index them: static keys, then the writable and readonly
+        // addresses loaded from lookup tables.
+        let mut keys = tx.message.static_account_keys().to_vec();
+        if tx.message.address_table_lookups().is_some_and(|l| !l.is_empty()) {
+            let loaded = confirmed
+                .transaction
+                .meta
+                .as_ref()
+                .and_then(|m| Option::<&UiLoadedAddresses>::from(m.loaded_addresses.as_ref()))
+                .ok_or_else(|| format!("no loaded addresses for v0 transaction {}", signature))?;
+            for address in loaded.writable.iter().chain(&loaded.readonly) {
+                keys.push(address.parse().map_err(|_| format!("bad loaded address in {}", signature))?);
+            }
+        }
+        let key = |i: u8| {
+            keys.get(usize::from(i))
+                .copied()
+                .ok_or_else(|| format!("account index {} out of range in {}", i, signature))
+        };
+
+        let mut decoded = Vec::new();
+        for ix in tx.message.instructions() {
+            if key(ix.program_id_index)? != self.program_id {
+                continue;
+            }
+            let accounts = ix.accounts.iter().map(|i| key(*i)).collect::<Result<Vec<_>, _>>()?;
+            decoded.extend(decode_game_instructions(&ix.data, &accounts));
+        }
+        Ok(decoded)
+    }
+
+    /// Applies one confirmed transaction reported by a client, ahead of the cursor. Returns its
+    /// rps-escrow instructions (none if it failed). Safe to repeat: `catch_up` applies it again.
+    pub async fn index_signature(
+        &self,
+        signature: &Signature,
+    ) -> Result<Vec<(Pubkey, EscrowInstruction)>, String> {
+        let instructions = self.decode_transaction(signature).await?;
+        for (game_escrow, ix) in &instructions {
+            apply(&self.db, game_escrow, ix, &signature.to_string())
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(instructions)
+    }
+
+    /// Indexes every program transaction since the cursor.
+    pub async fn catch_up(&self) -> Result<usize, String> {
+        let cursor = self.load_cursor().await.map_err(|e| e.to_string())?;
+        let until = match &cursor {
+            Some(c) => Some(c.last_signature.parse().map_err(|e| format!("bad cursor: {:?}", e))?),
+            None => None,
+        };
+        let pending = self.pending_signatures(until).await?;
+        let count = pending.len();
+        for (signature, slot, failed) in pending {
+            // Failed transactions changed nothing on-chain.
+            if !failed {
+                for (game_escrow, ix) in self.decode_transaction(&signature).await? {
+                    apply(&self.db, &game_escrow, &ix, &signature.to_string())
+                        .await
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+            self.save_cursor(&signature.to_string(), slot).await.map_err(|e| e.to_string())?;
+        }
+        Ok(count)
+    }
+}
+
+/// Wakes `notify` on every program log notification, reconnecting forever.
+async fn subscribe_logs(ws_url: String, program_id: Pubkey, notify: Arc<Notify>) {
+    loop {
+        match PubsubClient::new(&ws_url).await {
+            Ok(client) => {
+                let subscription = client
+                    .logs_subscribe(
+                        RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]),
+                        RpcTransactionLogsConfig {
+                            commitment: Some(CommitmentConfig::confirmed()),
+                        },
+                    )
+                    .await;
+                match subscription {
+                    Ok((mut logs, _unsubscribe)) => {
+                        log::info!("Indexer: subscribed to program logs on {}", ws_url);
+                        while logs.next().await.is_some() {
+                            notify.notify_one();
+                        }
+                        log::warn!("Indexer: log subscription closed");
+                    }
+                    Err(e) => log::warn!("Indexer: logsSubscribe failed: {}", e),
+                }
+            }
+            Err(e) => log::warn!("Indexer: websocket {} unavailable: {}", ws_url, e),
+        }
+        tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+    }
+}
+
+/// Runs the indexer for the lifetime of the process: catches up now, then on every log
+/// notification and at least every `poll_interval`.
+pub fn spawn(indexer: Indexer, ws_url: String, poll_interval: Duration) {
+    let notify = Arc::new(Notify::new());
+    tokio::spawn(subscribe_logs(ws_url, indexer.program_id, notify.clone()));
+    tokio::spawn(async move {
+        loop {
+            match indexer.catch_up().await {
+                Ok(0) => {}
+                Ok(n) => log::info!("Indexer: processed {} transaction(s)", n),
+                Err(e) => log::error!("Indexer catch-up failed: {}", e),
+            }
+            tokio::select! {
+                _ = notify.notified() => {}
+                _ = tokio::time::sleep(poll_interval) => {}
+            }
+        }
+    });
+}
@@ -0,0 +1,460 @@
+//! Leaderboards computed from finished games.
+//!
+//! **Collections:**
+//! - **leaderboard_snapshots**: { _id: "window:metric", window, metric, generation, refreshed_at, entries } — top 100
+//! - **leaderboard_ranks**: { board: "window:metric", generation, refreshed_at, entry } — every ranked player of a rolling window
+//!
+//! Snapshots are rebuilt by [`spawn_refresh_job`]; requests only read them, and get 503 until the
+//! first refresh has written the board. Each refresh tags what it writes with a fresh ObjectId
+//! `generation`: it only replaces snapshots and deletes ranks of older generations, so refreshes
+//! running concurrently (several API instances) never mix their rows. Rolling windows
+//! (`daily` = last 24 h, `weekly` = last 7 days) total the games whose `finished_at` falls in
+//! them. All-time boards come from `player_stats` (see [`crate::players`]), so no refresh scans
+//! the whole game history; a caller's all-time rank is counted on the `player_stats` metric index.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use mongodb::{
+    bson::doc,
+    bson::oid::ObjectId,
+    error::{ErrorKind, WriteFailure},
+    options::{FindOptions, ReplaceOptions},
+    Database, IndexModel,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::MaybeAuthUser;
+use crate::error::ApiError;
+use crate::games::{AppState, Game};
+use crate::players::{game_outcomes, PlayerStatsRecord};
+
+/// Default and maximum number of entries returned by `GET /leaderboard`; snapshots keep the maximum.
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 100;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Window {
+    Daily,
+    Weekly,
+    #[default]
+    All,
+}
+
+impl Window {
+    const ALL: [Window; 3] = [Window::Daily, Window::Weekly, Window::All];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Window::Daily => "daily",
+            Window::Weekly => "weekly",
+            Window::All => "all",
+        }
+    }
+
+    /// Oldest `finished_at` included, in the stored string format. None for all-time.
+    fn cutoff(self) -> Option<String> {
+        let span = match self {
+            Window::Daily => chrono::Duration::days(1),
+            Window::Weekly => chrono::Duration::days(7),
+            Window::All => return None,
+        };
+        Some(crate::games::utc_string(chrono::Utc::now() - span))
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    #[default]
+    NetWinnings,
+    Wins,
+    Volume,
+}
+
+impl Metric {
+    const ALL: [Metric; 3] = [Metric::NetWinnings, Metric::Wins, Metric::Volume];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Metric::NetWinnings => "net_winnings",
+            Metric::Wins => "wins",
+            Metric::Volume => "volume",
+        }
+    }
+
+    /// Field of the metric in `player_stats`.
+    fn stats_field(self) -> &'static str {
+        match self {
+            Metric::NetWinnings => "net_lamports",
+            Metric::Wins => "wins",
+            Metric::Volume => "total_wagered",
+        }
+    }
+
+    fn stats_value(self, record: &PlayerStatsRecord) -> i64 {
+        match self {
+            Metric::NetWinnings => record.net_lamports,
+            Metric::Wins => record.wins,
+            Metric::Volume => record.total_wagered,
+        }
+    }
+}
+
+fn snapshot_id(window: Window, metric: Metric) -> String {
+    format!("{}:{}", window.as_str(), metric.as_str())
+}
+
+/// One ranked player. `value` is the ranking metric; the other totals are for display.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub rank: i64,
+    pub pubkey: String,
+    pub value: i64,
+    pub games_played: i64,
+    pub wins: i64,
+    pub net_lamports: i64,
+    pub volume: i64,
+}
+
+/// A player's place on one board (`leaderboard_ranks`).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RankRecord {
+    /// Snapshot id, "window:metric".
+    pub board: String,
+    /// Refresh that wrote the record.
+    pub generation: ObjectId,
+    pub refreshed_at: String,
+    pub entry: LeaderboardEntry,
+}
+
+/// Stored top of the ranking for one window and metric.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LeaderboardSnapshot {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub window: Window,
+    pub metric: Metric,
+    /// Refresh that wrote the snapshot; its `leaderboard_ranks` carry the same generation.
+    /// None on snapshots written before generations existed.
+    #[serde(default)]
+    pub generation: Option<ObjectId>,
+    pub refreshed_at: String,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(Deserialize)]
+pub struct LeaderboardQuery {
+    #[serde(default)]
+    pub window: Window,
+    #[serde(default)]
+    pub metric: Metric,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct LeaderboardResponse {
+    pub window: Window,
+    pub metric: Metric,
+    pub refreshed_at: String,
+    pub entries: Vec<LeaderboardEntry>,
+    /// The calling player's entry (SIWS), whether or not it is in `entries`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub me: Option<LeaderboardEntry>,
+}
+
+#[derive(Default)]
+struct Totals {
+    games_played: i64,
+    wins: i64,
+    net_lamports: i64,
+    volume: i64,
+}
+
+/// Per-player totals over finished games in a rolling `window` (not [`Window::All`]).
+async fn window_totals(db: &Database, window: Window) -> Result<HashMap<String, Totals>, mongodb::error::Error> {
+    let mut filter = doc! { "status": "finished" };
+    if let Some(cutoff) = window.cutoff() {
+        filter.insert("finished_at", doc! { "$gte": cutoff });
+    }
+    let mut cursor = db.collection::<Game>("games").find(filter, None).await?;
+    let mut totals: HashMap<String, Totals> = HashMap::new();
+    while cursor.advance().await? {
+        let game = cursor.deserialize_current()?;
+        for o in game_outcomes(&game).into_iter().flatten() {
+            let t = totals.entry(o.pubkey).or_default();
+            t.games_played += 1;
+            t.wins += i64::from(o.won);
+            t.net_lamports += o.net_lamports;
+            t.volume += o.wagered;
+        }
+    }
+    Ok(totals)
+}
+
+fn rank(totals: &HashMap<String, Totals>, metric: Metric) -> Vec<LeaderboardEntry> {
+    let mut entries: Vec<LeaderboardEntry> = totals
+        .iter()
+        .map(|(pubkey, t)| LeaderboardEntry {
+            rank: 0,
+            pubkey: pubkey.clone(),
+            value: match metric {
+                Metric::NetWinnings => t.net_lamports,
+                Metric::Wins => t.wins,
+                Metric::Volume => t.volume,
+            },
+            games_played: t.games_played,
+            wins: t.wins,
+            net_lamports: t.net_lamports,
+            volume: t.volume,
+        })
+        .collect();
+    entries.sort_by(|a, b| b.value.cmp(&a.value).then_with(|| a.pubkey.cmp(&b.pubkey)));
+    for (i, e) in entries.iter_mut().enumerate() {
+        e.rank = i as i64 + 1;
+    }
+    entries
+}
+
+/// All-time entry of a `player_stats` record.
+fn stats_entry(record: PlayerStatsRecord, metric: Metric, rank: i64) -> LeaderboardEntry {
+    LeaderboardEntry {
+        rank,
+        value: metric.stats_value(&record),
+        pubkey: record.pubkey,
+        games_played: record.games_played,
+        wins: record.wins,
+        net_lamports: record.net_lamports,
+        volume: record.total_wagered,
+    }
+}
+
+/// All-time top [`MAX_LIMIT`] for `metric`, read from `player_stats` through its metric index.
+async fn all_time_top(db: &Database, metric: Metric) -> Result<Vec<LeaderboardEntry>, mongodb::error::Error> {
+    let options = FindOptions::builder()
+        .sort(doc! { metric.stats_field(): -1, "_id": 1 })
+        .limit(MAX_LIMIT as i64)
+        .build();
+    let mut cursor = db
+        .collection::<PlayerStatsRecord>("player_stats")
+        .find(doc! { "games_played": { "$gt": 0_i64 } }, options)
+        .await?;
+    let mut entries = Vec::new();
+    while cursor.advance().await? {
+        let rank = entries.len() as i64 + 1;
+        entries.push(stats_entry(cursor.deserialize_current()?, metric, rank));
+    }
+    Ok(entries)
+}
+
+/// All-time entry of `pubkey`: its rank is one plus the players ahead of it (higher value, or the
+/// same value and a smaller pubkey), counted on the metric index.
+async fn all_time_entry(
+    db: &Database,
+    metric: Metric,
+    pubkey: &str,
+) -> Result<Option<LeaderboardEntry>, mongodb::error::Error> {
+    let stats = db.collection::<PlayerStatsRecord>("player_stats");
+    let Some(record) = stats.find_one(doc! { "_id": pubkey, "games_played": { "$gt": 0_i64 } }, None).await?
+    else {
+        return Ok(None);
+    };
+    let field = metric.stats_field();
+    let value = metric.stats_value(&record);
+    let ahead = stats
+        .count_documents(
+            doc! { "games_played": { "$gt": 0_i64 }, "$or": [
+                { field: { "$gt": value } },
+                { field: value, "_id": { "$lt": pubkey } },
+            ] },
+            None,
+        )
+        .await?;
+    Ok(Some(stats_entry(record, metric, ahead as i64 + 1)))
+}
+
+/// Filter matching documents of `board` written by a refresh older than `generation`.
+fn older_than(board_field: &str, board: &str, generation: ObjectId) -> mongodb::bson::Document {
+    doc! {
+        board_field: board,
+        "$or": [
+            { "generation": { "$lt": generation } },
+            { "generation": { "$exists": false } },
+        ],
+    }
+}
+
+/// Rebuilds every window/metric snapshot: the top [`MAX_LIMIT`] of each, plus every ranked
+/// player of the rolling windows in `leaderboard_ranks`.
+pub async fn refresh_snapshots(db: &Database) -> Result<(), mongodb::error::Error> {
+    let snapshots = db.collection::<LeaderboardSnapshot>("leaderboard_snapshots");
+    let ranks = db.collection::<RankRecord>("leaderboard_ranks");
+    let generation = ObjectId::new();
+    for window in Window::ALL {
+        let totals = match window {
+            Window::All => None,
+            _ => Some(window_totals(db, window).await?),
+        };
+        let refreshed_at = crate::games::now_utc_string();
+        for metric in Metric::ALL {
+            let id = snapshot_id(window, metric);
+            let mut entries = match &totals {
+                Some(totals) => rank(totals, metric),
+                None => all_time_top(db, metric).await?,
+            };
+            if totals.is_some() {
+                let records: Vec<RankRecord> = entries
+                    .iter()
+                    .map(|entry| RankRecord {
+                        board: id.clone(),
+                        generation,
+                        refreshed_at: refreshed_at.clone(),
+                        entry: entry.clone(),
+                    })
+                    .collect();
+                if !records.is_empty() {
+                    ranks.insert_many(records, None).await?;
+                }
+                ranks.delete_many(older_than("board", &id, generation), None).await?;
+            }
+            entries.truncate(MAX_LIMIT);
+            let snapshot = LeaderboardSnapshot {
+                id,
+                window,
+                metric,
+                generation: Some(generation),
+                refreshed_at: refreshed_at.clone(),
+                entries,
+            };
+            let replaced = snapshots
+                .replace_one(
+                    older_than("_id", &snapshot.id, generation),
+                    &snapshot,
+                    ReplaceOptions::builder().upsert(true).build(),
+                )
+                .await;
+            match replaced {
+                Ok(_) => {}
+                // The upsert collides with a snapshot a newer refresh already wrote; keep that one.
+                Err(e) if is_duplicate_key(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_duplicate_key(e: &mongodb::error::Error) -> bool {
+    matches!(&*e.kind, ErrorKind::Write(WriteFailure::WriteError(w)) if w.code == 11000)
+}
+
+/// `pubkey`'s entry on `snapshot`'s board: computed on `player_stats` for all-time, else from the
+/// `leaderboard_ranks` of the snapshot's generation.
+async fn find_entry(
+    db: &Database,
+    snapshot: &LeaderboardSnapshot,
+    pubkey: &str,
+) -> Result<Option<LeaderboardEntry>, mongodb::error::Error> {
+    if snapshot.window == Window::All {
+        return all_time_entry(db, snapshot.metric, pubkey).await;
+    }
+    let Some(generation) = snapshot.generation else {
+        return Ok(None);
+    };
+    let record = db
+        .collection::<RankRecord>("leaderboard_ranks")
+        .find_one(
+            doc! { "board": &snapshot.id, "generation": generation, "entry.pubkey": pubkey },
+            None,
+        )
+        .await?;
+    Ok(record.map(|r| r.entry))
+}
+
+/// Indexes behind the leaderboards: rolling windows scan recent finished games, all-time ranks
+/// walk `player_stats` by metric, and a player's rank is looked up by board and pubkey.
+pub async fn ensure_leaderboard_indexes(db: &Database) -> Result<(), mongodb::error::Error> {
+    let index = |keys| IndexModel::builder().keys(keys).build();
+    db.collection::<mongodb::bson::Document>("games")
+        .create_index(index(doc! { "status": 1, "finished_at": 1 }), None)
+        .await?;
+    let stats = db.collection::<mongodb::bson::Document>("player_stats");
+    for metric in Metric::ALL {
+        stats
+            .create_index(index(doc! { metric.stats_field(): -1, "_id": 1 }), None)
+            .await?;
+    }
+    db.collection::<mongodb::bson::Document>("leaderboard_ranks")
+        .create_index(index(doc! { "board": 1, "generation": 1, "entry.pubkey": 1 }), None)
+        .await?;
+    log::info!("Leaderboard indexes ensured");
+    Ok(())
+}
+
+/// Refreshes snapshots now and then every `interval`, for the lifetime of the process.
+pub fn spawn_refresh_job(db: Database, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match refresh_snapshots(&db).await {
+                Ok(()) => log::debug!("Leaderboard snapshots refreshed"),
+                Err(e) => log::error!("Failed to refresh leaderboard snapshots: {}", e),
+            }
+        }
+    });
+}
+
+async fn get_leaderboard(
+    State(state): State<AppState>,
+    MaybeAuthUser(auth): MaybeAuthUser,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<LeaderboardResponse>, ApiError> {
+    let snapshots = state.db.collection::<LeaderboardSnapshot>("leaderboard_snapshots");
+    let id = snapshot_id(query.window, query.metric);
+    let snapshot = snapshots
+        .find_one(doc! { "_id": &id }, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to find leaderboard snapshot: {}", e);
+            ApiError::internal(e.to_string())
+        })?
+        // Requests before the refresh job's first run.
+        .ok_or_else(|| ApiError::service_unavailable("Leaderboard is warming up, retry shortly"))?;
+
+    let me = match auth {
+        Some(user) => match snapshot.entries.iter().find(|e| e.pubkey == user.pubkey) {
+            Some(entry) => Some(entry.clone()),
+            None => find_entry(&state.db, &snapshot, &user.pubkey)
+                .await
+                .map_err(|e| {
+                    log::error!("Failed to find leaderboard entry: {}", e);
+                    ApiError::internal(e.to_string())
+                })?,
+        },
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let mut entries = snapshot.entries;
+    entries.truncate(limit);
+
+    Ok(Json(LeaderboardResponse {
+        window: snapshot.window,
+        metric: snapshot.metric,
+        refreshed_at: snapshot.refreshed_at,
+        entries,
+        me,
+    }))
+}
+
+pub fn leaderboard_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/leaderboard", get(get_leaderboard))
+        .with_state(state)
+}
@@ -1,6 +1,26 @@
+pub mod actions;
+pub mod admin;
 pub mod auth;
+pub mod balances;
+pub mod challenges;
 pub mod config;
 pub mod error;
+pub mod fees;
 pub mod games;
 pub mod health;
-pub mod solana;
\ No newline at end of file
+pub mod indexer;
+pub mod leaderboard;
+pub mod onchain;
+pub mod pause;
+pub mod pay;
+pub mod players;
+pub mod rating;
+pub mod reconcile;
+pub mod resolver;
+pub mod sender;
+pub mod signer;
+pub mod solana;
+pub mod spectate;
+pub mod transactions;
+pub mod tx_error;
+pub mod verdict;
\ No newline at end of file
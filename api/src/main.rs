@@ -1,8 +1,24 @@
+use seeker_rps_api::actions::action_routes;
+use seeker_rps_api::admin::admin_routes;
+use seeker_rps_api::balances::{self, BalanceMonitor};
+use seeker_rps_api::challenges::challenge_routes;
 use seeker_rps_api::config::Config;
 use seeker_rps_api::games::{games_routes, AppState};
 use seeker_rps_api::health;
+use seeker_rps_api::indexer::{self, Indexer};
+use seeker_rps_api::leaderboard::{self, leaderboard_routes};
+use seeker_rps_api::onchain::onchain_routes;
+use seeker_rps_api::pause::{self, PauseMonitor};
+use seeker_rps_api::pay::pay_routes;
+use seeker_rps_api::players::players_routes;
+use seeker_rps_api::reconcile;
+use seeker_rps_api::resolver::Resolver;
 use seeker_rps_api::solana::SolanaAppClient;
+use seeker_rps_api::spectate::{self, spectate_routes};
+use seeker_rps_api::transactions::transaction_routes;
+use seeker_rps_api::verdict;
 use std::net::SocketAddr;
+use std::time::Duration;
 use tower_http::cors::{Any, CorsLayer};
 
 #[tokio::main]
@@ -18,13 +34,72 @@ async fn main() {
     seeker_rps_api::games::ensure_games_pin_index(&db)
         .await
         .expect("Failed to ensure games PIN index");
-    let solana = Some(SolanaAppClient::from_config(&config));
-    if solana.as_ref().map(|s| s.can_resolve()).unwrap_or(false) {
-        log::info!("Solana mainnet: resolve enabled (program {})", config.rps_escrow_program_id);
+    leaderboard::ensure_leaderboard_indexes(&db)
+        .await
+        .expect("Failed to ensure leaderboard indexes");
+    spectate::ensure_spectator_indexes(&db)
+        .await
+        .expect("Failed to ensure spectator indexes");
+    let solana_client = SolanaAppClient::from_config(&config);
+    if let Err(e) = solana_client.verify_program(config.allow_missing_idl) {
+        panic!("Solana program check failed on {}: {}", config.solana_cluster.as_str(), e);
+    }
+    log::info!(
+        "Solana {}: program {} verified",
+        config.solana_cluster.as_str(),
+        config.rps_escrow_program_id
+    );
+    if solana_client.can_resolve() {
+        log::info!("Solana {}: resolve enabled", config.solana_cluster.as_str());
     } else {
-        log::info!("Solana mainnet: resolve disabled (set RESOLVE_AUTHORITY_KEYPAIR_PATH to enable)");
+        log::info!(
+            "Solana {}: resolve disabled (configure RESOLVE_SIGNER to enable)",
+            config.solana_cluster.as_str()
+        );
+    }
+    indexer::spawn(
+        Indexer::new(db.clone(), config.solana_rpc_url.clone(), config.rps_escrow_program_id)
+            .with_backfill_limit(config.indexer_backfill_limit),
+        config.solana_ws_url.clone(),
+        Duration::from_secs(config.indexer_poll_secs),
+    );
+    reconcile::spawn_reconciliation_job(
+        db.clone(),
+        solana_client.clone(),
+        Duration::from_secs(config.reconciliation_interval_secs),
+    );
+    let balance_monitor = BalanceMonitor::new(&solana_client, &config);
+    balances::spawn_balance_job(
+        balance_monitor.clone(),
+        solana_client.clone(),
+        Duration::from_secs(config.balance_check_secs),
+    );
+    let pause_monitor = PauseMonitor::default();
+    pause::spawn_pause_job(
+        pause_monitor.clone(),
+        solana_client.clone(),
+        Duration::from_secs(config.pause_check_secs),
+    );
+    let resolver = solana_client.can_resolve().then(|| {
+        Resolver::spawn(
+            solana_client.clone(),
+            config.resolve_batch_size,
+            Duration::from_millis(config.resolve_batch_window_ms),
+        )
+    });
+    let solana = Some(solana_client);
+    leaderboard::spawn_refresh_job(db.clone(), Duration::from_secs(config.leaderboard_refresh_secs));
+    let state = AppState {
+        db,
+        solana,
+        resolver,
+        admin_api_key: config.admin_api_key.clone(),
+        pause: pause_monitor.clone(),
+        public_base_url: config.public_base_url.clone(),
+    };
+    if config.verdict_ttl_secs > 0 {
+        verdict::spawn_fallback_job(state.clone(), verdict::FALLBACK_CHECK_INTERVAL);
     }
-    let state = AppState { db, solana };
     log::info!("MongoDB connected");
 
     let cors = CorsLayer::new()
@@ -33,8 +108,18 @@ async fn main() {
         .allow_headers(Any);
 
     let app = axum::Router::new()
-        .merge(health::routes())
-        .merge(games_routes(state))
+        .merge(health::routes(pause_monitor))
+        .merge(health::readiness_routes(balance_monitor))
+        .merge(games_routes(state.clone()))
+        .merge(challenge_routes(state.clone()))
+        .merge(spectate_routes(state.clone()))
+        .merge(onchain_routes(state.clone()))
+        .merge(transaction_routes(state.clone()))
+        .merge(action_routes(state.clone()))
+        .merge(pay_routes(state.clone()))
+        .merge(players_routes(state.clone()))
+        .merge(leaderboard_routes(state.clone()))
+        .merge(admin_routes(state))
         .layer(cors);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
@@ -0,0 +1,275 @@
+//! On-chain view of a game's escrow, for support.
+//!
+//! `GET /games/:game_id/onchain` reads the `GameEscrow` account (the program's own type) and the vault
+//! balance at the game's PDAs. While the escrow is open it also reports the pending resolve or
+//! refund transaction, if any; once closed, the transaction that closed it and the `GameResult`
+//! receipt it left behind (none after a cancel). Transaction statuses come from
+//! `getSignatureStatuses` (with history).
+
+use std::str::FromStr;
+
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+use mongodb::bson::doc;
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use rps_escrow_client::pda;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+
+use crate::error::ApiError;
+use crate::games::{AppState, Game, GameIdPath};
+use crate::solana::{GameEscrowAccount, GameResultAccount, SolanaAppClient};
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowState {
+    Open,
+    /// Account closed by resolve, refund or cancel.
+    Closed,
+    /// No account and no transaction ever touched the address.
+    NotFound,
+}
+
+/// Decoded `GameEscrow` account.
+#[derive(Serialize)]
+pub struct EscrowView {
+    pub creator: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub joiner: Option<String>,
+    /// Invited player of a direct challenge.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opponent: Option<String>,
+    pub amount_per_player: u64,
+    pub resolved: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub winner: Option<String>,
+}
+
+impl EscrowView {
+    fn new(escrow: &GameEscrowAccount, opponent: Option<Pubkey>) -> Self {
+        Self {
+            creator: escrow.creator.to_string(),
+            joiner: escrow.joiner.map(|k| k.to_string()),
+            opponent: opponent.map(|k| k.to_string()),
+            amount_per_player: escrow.amount_per_player,
+            resolved: escrow.resolved,
+            winner: escrow.winner.map(|k| k.to_string()),
+        }
+    }
+}
+
+/// Decoded `GameResult` receipt.
+#[derive(Serialize)]
+pub struct ResultView {
+    pub creator: String,
+    pub joiner: String,
+    /// None for a refund.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub winner: Option<String>,
+    pub amount_per_player: u64,
+    pub fee: u64,
+    /// Slot the game was settled in.
+    pub slot: u64,
+}
+
+impl From<&GameResultAccount> for ResultView {
+    fn from(result: &GameResultAccount) -> Self {
+        Self {
+            creator: result.creator.to_string(),
+            joiner: result.joiner.to_string(),
+            winner: result.winner.map(|k| k.to_string()),
+            amount_per_player: result.amount_per_player,
+            fee: result.fee,
+            slot: result.slot,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TxKind {
+    Resolve,
+    Refund,
+    Cancel,
+    /// Closing transaction found on-chain but not recorded on the game.
+    Unknown,
+}
+
+#[derive(Serialize)]
+pub struct TxView {
+    pub kind: TxKind,
+    pub signature: String,
+    /// `processed`, `confirmed`, `finalized`, `failed` or `not_found`.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct OnchainView {
+    pub game_id: String,
+    pub program_id: String,
+    pub game_escrow_pubkey: String,
+    pub vault_pubkey: String,
+    pub game_result_pubkey: String,
+    pub state: EscrowState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub escrow: Option<EscrowView>,
+    pub vault_lamports: u64,
+    /// Pending resolve/refund while open; the closing transaction once closed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<TxView>,
+    /// Receipt written when the escrow was resolved or refunded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<ResultView>,
+}
+
+/// The game's resolve, refund or cancel signature, else its latest resolve attempt.
+fn recorded_tx(game: &Game) -> Option<(TxKind, String)> {
+    game.resolve_tx
+        .clone()
+        .map(|s| (TxKind::Resolve, s))
+        .or_else(|| game.refund_tx.clone().map(|s| (TxKind::Refund, s)))
+        .or_else(|| game.cancel_tx.clone().map(|s| (TxKind::Cancel, s)))
+        .or_else(|| game.resolve_attempts.last().map(|a| (TxKind::Resolve, a.signature.clone())))
+}
+
+fn tx_view(rpc: &RpcClient, kind: TxKind, signature: &str) -> Result<TxView, String> {
+    let sig = Signature::from_str(signature).map_err(|e| e.to_string())?;
+    let status = rpc
+        .get_signature_statuses_with_history(&[sig])
+        .map_err(|e| e.to_string())?
+        .value
+        .into_iter()
+        .next()
+        .flatten();
+    let (status, error) = match status {
+        None => ("not_found".to_string(), None),
+        Some(s) if s.err.is_some() => ("failed".to_string(), s.err.map(|e| e.to_string())),
+        Some(s) => (
+            s.confirmation_status
+                .map(|c| format!("{:?}", c).to_lowercase())
+                .unwrap_or_else(|| "finalized".to_string()),
+            None,
+        ),
+    };
+    Ok(TxView {
+        kind,
+        signature: signature.to_string(),
+        status,
+        error,
+    })
+}
+
+/// Reads the escrow, vault, receipt and transaction. Blocking.
+fn inspect(
+    solana: &SolanaAppClient,
+    game_id: String,
+    escrow_address: Pubkey,
+    vault: Pubkey,
+    game_result: Pubkey,
+    recorded: Option<(TxKind, String)>,
+) -> Result<OnchainView, String> {
+    let rpc = RpcClient::new_with_commitment(solana.rpc_url.clone(), CommitmentConfig::confirmed());
+    let escrow = solana.fetch_game_escrow_with_opponent(&escrow_address)?;
+    let vault_lamports = solana.get_balances(&[vault])?.first().copied().unwrap_or_default();
+    let result = solana.fetch_game_result(&game_result)?;
+
+    let (state, transaction) = match &escrow {
+        Some(_) => (EscrowState::Open, recorded),
+        None => {
+            // The newest transaction touching a closed escrow is the one that closed it.
+            let closing = rpc
+                .get_signatures_for_address(&escrow_address)
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .find(|s| s.err.is_none())
+                .map(|s| s.signature);
+            match closing {
+                Some(sig) => {
+                    let kind = recorded
+                        .filter(|(_, s)| *s == sig)
+                        .map_or(TxKind::Unknown, |(kind, _)| kind);
+                    (EscrowState::Closed, Some((kind, sig)))
+                }
+                None => (EscrowState::NotFound, recorded),
+            }
+        }
+    };
+    let transaction = transaction
+        .map(|(kind, sig)| tx_view(&rpc, kind, &sig))
+        .transpose()?;
+
+    Ok(OnchainView {
+        game_id,
+        program_id: solana.program_id.to_string(),
+        game_escrow_pubkey: escrow_address.to_string(),
+        vault_pubkey: vault.to_string(),
+        game_result_pubkey: game_result.to_string(),
+        state,
+        escrow: escrow.as_ref().map(|(escrow, opponent)| EscrowView::new(escrow, *opponent)),
+        vault_lamports,
+        transaction,
+        result: result.as_ref().map(ResultView::from),
+    })
+}
+
+async fn get_onchain(
+    State(state): State<AppState>,
+    Path(path): Path<GameIdPath>,
+) -> Result<Json<OnchainView>, ApiError> {
+    let solana = state
+        .solana
+        .clone()
+        .ok_or_else(|| ApiError::internal("Solana client not configured"))?;
+    let game = state
+        .db
+        .collection::<Game>("games")
+        .find_one(doc! { "_id": &path.game_id }, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to find game: {}", e);
+            ApiError::internal(e.to_string())
+        })?
+        .ok_or_else(|| ApiError::not_found("Game not found"))?;
+
+    let escrow_address = match &game.game_escrow_pubkey {
+        Some(pk) => Pubkey::from_str(pk).map_err(|e| ApiError::internal(e.to_string()))?,
+        None => {
+            let creator = Pubkey::from_str(&game.creator_pubkey)
+                .map_err(|e| ApiError::internal(e.to_string()))?;
+            let game_id = uuid::Uuid::parse_str(&game.id)
+                .map_err(|e| ApiError::internal(e.to_string()))?;
+            pda::game_escrow_pda(&solana.program_id, &creator, game_id.as_bytes()).0
+        }
+    };
+    let vault = match &game.vault_pubkey {
+        Some(pk) => Pubkey::from_str(pk).map_err(|e| ApiError::internal(e.to_string()))?,
+        None => pda::vault_pda(&solana.program_id, &escrow_address).0,
+    };
+    let game_result = match &game.game_result_pubkey {
+        Some(pk) => Pubkey::from_str(pk).map_err(|e| ApiError::internal(e.to_string()))?,
+        None => pda::game_result_pda(&solana.program_id, &escrow_address).0,
+    };
+
+    let recorded = recorded_tx(&game);
+    let view = tokio::task::spawn_blocking(move || {
+        inspect(&solana, game.id, escrow_address, vault, game_result, recorded)
+    })
+    .await
+    .map_err(|e| ApiError::internal(e.to_string()))?
+    .map_err(|e| {
+        log::error!("On-chain inspection failed game_id={}: {}", path.game_id, e);
+        ApiError::internal(e)
+    })?;
+    Ok(Json(view))
+}
+
+pub fn onchain_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/games/:game_id/onchain", get(get_onchain))
+        .with_state(state)
+}
@@ -0,0 +1,86 @@
+//! Emergency pause of the escrow program.
+//!
+//! Polls the program's pause flag every `PAUSE_CHECK_SECS`. While it is set, the program rejects
+//! `create_game`, `create_challenge` and `join_game`, so the API refuses to create, join or accept
+//! games up front (503) instead of handing out transactions that would fail. Cancel and refund
+//! keep working. `/health` reports the last known flag.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::error::ApiError;
+use crate::games::now_utc_string;
+use crate::solana::SolanaAppClient;
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PauseStatus {
+    pub paused: bool,
+    /// None until the first successful check.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checked_at: Option<String>,
+}
+
+/// Last known pause flag, shared with the game routes and `/health`.
+#[derive(Clone, Default)]
+pub struct PauseMonitor {
+    status: Arc<RwLock<PauseStatus>>,
+}
+
+impl PauseMonitor {
+    pub fn status(&self) -> PauseStatus {
+        self.status.read().expect("pause lock poisoned").clone()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.status().paused
+    }
+
+    /// 503 while the escrow is paused.
+    pub fn ensure_open(&self) -> Result<(), ApiError> {
+        if self.is_paused() {
+            return Err(ApiError::service_unavailable(
+                "Escrow is paused: new games and joins are disabled",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reads the flag and logs when it changes. A failed read keeps the previous flag.
+    async fn check(&self, solana: &SolanaAppClient) {
+        let solana = solana.clone();
+        let fetched = tokio::task::spawn_blocking(move || solana.fetch_paused())
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r);
+        let paused = match fetched {
+            Ok(paused) => paused,
+            Err(e) => {
+                log::warn!("Pause check failed: {}", e);
+                return;
+            }
+        };
+        let mut status = self.status.write().expect("pause lock poisoned");
+        if paused != status.paused {
+            if paused {
+                log::warn!("Escrow paused: refusing new games and joins");
+            } else {
+                log::info!("Escrow unpaused: accepting new games and joins");
+            }
+        }
+        status.paused = paused;
+        status.checked_at = Some(now_utc_string());
+    }
+}
+
+/// Checks the pause flag every `interval` (the first check runs immediately).
+pub fn spawn_pause_job(monitor: PauseMonitor, solana: SolanaAppClient, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            monitor.check(&solana).await;
+        }
+    });
+}
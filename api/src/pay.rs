@@ -0,0 +1,388 @@
+//! [Solana Pay](https://docs.solanapay.com/spec) transaction requests for creating and joining
+//! games from a QR code, e.g. a second device joining by scanning the creator's screen.
+//!
+//! **Collections:**
+//! - **pay_references**: { _id: reference, game_id, action, created_at, account?, signature?, confirmed_at? }
+//!
+//! The creator asks for a payload (`POST /games/:game_id/pay`): a `solana:` URL pointing at
+//! `/pay/games/:game_id/join` (or `/create`) with a fresh `reference` key. Wallets `GET` it for a
+//! label and icon and `POST { "account" }` for the transaction: the usual `join_game` /
+//! `create_game` for that account, with `reference` added as a read-only account so the deposit
+//! can be found with `getSignaturesForAddress(reference)`. `GET /games/:game_id/pay/:reference`
+//! looks it up, indexes it (see [`crate::indexer`]) and reports `pending` or `confirmed`.
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Json, Router,
+};
+use base64::Engine;
+use mongodb::bson::doc;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig, instruction::AccountMeta, pubkey::Pubkey,
+    signature::Signature,
+};
+
+use crate::actions::{self, ActionError};
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::games::{now_utc_string, AppState, Game, GameIdPath};
+use crate::indexer::{EscrowInstruction, Indexer};
+use crate::transactions::{self, PlayerAction};
+
+/// A reference key handed out in a QR payload.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PayReference {
+    #[serde(rename = "_id")]
+    pub reference: String,
+    pub game_id: String,
+    pub action: PlayerAction,
+    pub created_at: String,
+    /// Wallet that requested the transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    /// Confirmed transaction carrying the reference.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmed_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PayPayloadRequest {
+    /// `join` (default) or `create`.
+    pub action: Option<PlayerAction>,
+}
+
+#[derive(Serialize)]
+pub struct PayPayloadResponse {
+    /// `solana:` transaction-request URL; encode it as the QR code.
+    pub url: String,
+    pub reference: String,
+    pub action: PlayerAction,
+}
+
+#[derive(Deserialize)]
+pub struct PayPath {
+    pub game_id: String,
+    pub action: String,
+}
+
+#[derive(Deserialize)]
+pub struct ReferenceQuery {
+    pub reference: String,
+}
+
+#[derive(Deserialize)]
+pub struct ReferencePath {
+    pub game_id: String,
+    pub reference: String,
+}
+
+#[derive(Serialize)]
+pub struct PayLabel {
+    pub label: String,
+    pub icon: String,
+}
+
+#[derive(Deserialize)]
+pub struct PayTransactionRequest {
+    pub account: String,
+}
+
+#[derive(Serialize)]
+pub struct PayTransactionResponse {
+    /// Base64 bincode transaction, unsigned.
+    pub transaction: String,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PayStatus {
+    Pending,
+    Confirmed,
+}
+
+#[derive(Serialize)]
+pub struct PayStatusResponse {
+    pub reference: String,
+    pub action: PlayerAction,
+    pub status: PayStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// Percent-encodes everything but RFC 3986 unreserved characters.
+fn url_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn parse_action(action: &str) -> Result<PlayerAction, ApiError> {
+    match action {
+        "create" => Ok(PlayerAction::Create),
+        "join" => Ok(PlayerAction::Join),
+        _ => Err(ApiError::not_found("Unknown pay action")),
+    }
+}
+
+async fn find_game(state: &AppState, game_id: &str) -> Result<Game, ApiError> {
+    state
+        .db
+        .collection::<Game>("games")
+        .find_one(doc! { "_id": game_id }, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to find game: {}", e);
+            ApiError::internal(e.to_string())
+        })?
+        .ok_or_else(|| ApiError::not_found("Game not found"))
+}
+
+/// The reference, if it was issued for this game and action.
+async fn find_reference(
+    state: &AppState,
+    game_id: &str,
+    reference: &str,
+) -> Result<PayReference, ApiError> {
+    state
+        .db
+        .collection::<PayReference>("pay_references")
+        .find_one(doc! { "_id": reference, "game_id": game_id }, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to find pay reference: {}", e);
+            ApiError::internal(e.to_string())
+        })?
+        .ok_or_else(|| ApiError::not_found("Unknown reference for this game"))
+}
+
+async fn create_payload(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(path): Path<GameIdPath>,
+    Json(body): Json<PayPayloadRequest>,
+) -> Result<Json<PayPayloadResponse>, ApiError> {
+    let action = body.action.unwrap_or(PlayerAction::Join);
+    if !matches!(action, PlayerAction::Create | PlayerAction::Join) {
+        return Err(ApiError::bad_request("action must be create or join"));
+    }
+    let game = find_game(&state, &path.game_id).await?;
+    if game.creator_pubkey != auth.pubkey.trim() {
+        return Err(ApiError::forbidden("Only the creator can share a payment QR code"));
+    }
+
+    let reference = Pubkey::new_from_array(rand::random()).to_string();
+    let pay_reference = PayReference {
+        reference: reference.clone(),
+        game_id: game.id.clone(),
+        action,
+        created_at: now_utc_string(),
+        account: None,
+        signature: None,
+        confirmed_at: None,
+    };
+    state
+        .db
+        .collection::<PayReference>("pay_references")
+        .insert_one(&pay_reference, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to insert pay reference: {}", e);
+            ApiError::internal(e.to_string())
+        })?;
+
+    let action_path = if action == PlayerAction::Create { "create" } else { "join" };
+    let link = format!(
+        "{}/pay/games/{}/{}?reference={}",
+        state.public_base_url,
+        game.id,
+        action_path,
+        reference
+    );
+    log::info!("Pay payload game_id={} action={} reference={}", game.id, action_path, reference);
+    Ok(Json(PayPayloadResponse {
+        url: format!("solana:{}", url_encode(&link)),
+        reference,
+        action,
+    }))
+}
+
+async fn get_pay_label(
+    State(state): State<AppState>,
+    Path(path): Path<PayPath>,
+) -> Result<Json<PayLabel>, ActionError> {
+    let action = parse_action(&path.action)?;
+    let game = find_game(&state, &path.game_id).await?;
+    let stake = actions::sol(game.amount_per_player);
+    let label = match action {
+        PlayerAction::Create => format!("Deposit {} for Ro-Sham-Bo", stake),
+        _ => format!("Join Ro-Sham-Bo for {}", stake),
+    };
+    Ok(Json(PayLabel {
+        label,
+        icon: format!("{}/actions/icon.png", state.public_base_url),
+    }))
+}
+
+async fn post_pay_transaction(
+    State(state): State<AppState>,
+    Path(path): Path<PayPath>,
+    Query(query): Query<ReferenceQuery>,
+    Json(body): Json<PayTransactionRequest>,
+) -> Result<Json<PayTransactionResponse>, ActionError> {
+    let solana = state
+        .solana
+        .clone()
+        .ok_or_else(|| ApiError::internal("Solana client not configured"))?;
+    let action = parse_action(&path.action)?;
+    let pay_reference = find_reference(&state, &path.game_id, &query.reference).await?;
+    if pay_reference.action != action || pay_reference.signature.is_some() {
+        return Err(ActionError::bad_request("Reference already used or issued for another action"));
+    }
+    let reference: Pubkey = pay_reference
+        .reference
+        .parse()
+        .map_err(|_| ApiError::internal("stored reference is not a pubkey"))?;
+    let account = body.account.trim().to_string();
+    let payer: Pubkey = account
+        .parse()
+        .map_err(|_| ActionError::bad_request("account is not a valid base58 pubkey"))?;
+    let game = find_game(&state, &path.game_id).await?;
+    let mut ix = transactions::player_instruction(&solana, &state.pause, &game, action, &account)?;
+    ix.accounts.push(AccountMeta::new_readonly(reference, false));
+
+    let (tx, _, _) = tokio::task::spawn_blocking(move || solana.build_player_transaction(&ix, &payer))
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?
+        .map_err(|e| {
+            log::warn!("Pay {:?} failed game_id={} account={}: {}", action, game.id, account, e);
+            ActionError::bad_request(e.message.clone())
+        })?;
+    let bytes = bincode::serialize(&tx).map_err(|e| ApiError::internal(e.to_string()))?;
+
+    state
+        .db
+        .collection::<PayReference>("pay_references")
+        .update_one(
+            doc! { "_id": &pay_reference.reference },
+            doc! { "$set": { "account": &account } },
+            None,
+        )
+        .await
+        .map_err(|e| {
+            log::error!("Failed to update pay reference: {}", e);
+            ApiError::internal(e.to_string())
+        })?;
+    log::info!(
+        "Pay transaction built game_id={} action={:?} account={} reference={}",
+        game.id, action, account, pay_reference.reference
+    );
+    let stake = actions::sol(game.amount_per_player);
+    Ok(Json(PayTransactionResponse {
+        transaction: base64::engine::general_purpose::STANDARD.encode(bytes),
+        message: match action {
+            PlayerAction::Create => format!("Deposit {} to open the game", stake),
+            _ => format!("Deposit {} to join the game", stake),
+        },
+    }))
+}
+
+async fn get_pay_status(
+    State(state): State<AppState>,
+    Path(path): Path<ReferencePath>,
+) -> Result<Json<PayStatusResponse>, ApiError> {
+    let pay_reference = find_reference(&state, &path.game_id, &path.reference).await?;
+    let response = |status, signature| PayStatusResponse {
+        reference: pay_reference.reference.clone(),
+        action: pay_reference.action,
+        status,
+        signature,
+    };
+    if pay_reference.signature.is_some() {
+        return Ok(Json(response(PayStatus::Confirmed, pay_reference.signature.clone())));
+    }
+    let solana = state
+        .solana
+        .clone()
+        .ok_or_else(|| ApiError::internal("Solana client not configured"))?;
+    let reference: Pubkey = pay_reference
+        .reference
+        .parse()
+        .map_err(|_| ApiError::internal("stored reference is not a pubkey"))?;
+
+    let rpc_url = solana.rpc_url.clone();
+    let found = tokio::task::spawn_blocking(move || {
+        RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed())
+            .get_signatures_for_address(&reference)
+            .map(|sigs| sigs.into_iter().find(|s| s.err.is_none()).map(|s| s.signature))
+    })
+    .await
+    .map_err(|e| ApiError::internal(e.to_string()))?
+    .map_err(|e| {
+        log::error!("getSignaturesForAddress(reference) failed: {}", e);
+        ApiError::internal(e.to_string())
+    })?;
+    let Some(signature) = found else {
+        return Ok(Json(response(PayStatus::Pending, None)));
+    };
+
+    let sig: Signature = signature
+        .parse()
+        .map_err(|_| ApiError::internal("RPC returned an invalid signature"))?;
+    let game = find_game(&state, &path.game_id).await?;
+    let indexer = Indexer::new(state.db.clone(), solana.rpc_url.clone(), solana.program_id);
+    let instructions = indexer.index_signature(&sig).await.map_err(|e| {
+        log::error!("Failed to index pay transaction {}: {}", signature, e);
+        ApiError::internal(e)
+    })?;
+    let expected = instructions.iter().any(|(escrow, ix)| {
+        game.game_escrow_pubkey.as_deref() == Some(escrow.to_string().as_str())
+            && matches!(
+                (pay_reference.action, ix),
+                (PlayerAction::Create, EscrowInstruction::Create)
+                    | (PlayerAction::Join, EscrowInstruction::Join { .. })
+            )
+    });
+    if !expected {
+        log::warn!("Pay reference {} used by unrelated transaction {}", pay_reference.reference, signature);
+        return Ok(Json(response(PayStatus::Pending, None)));
+    }
+
+    state
+        .db
+        .collection::<PayReference>("pay_references")
+        .update_one(
+            doc! { "_id": &pay_reference.reference },
+            doc! { "$set": { "signature": &signature, "confirmed_at": now_utc_string() } },
+            None,
+        )
+        .await
+        .map_err(|e| {
+            log::error!("Failed to update pay reference: {}", e);
+            ApiError::internal(e.to_string())
+        })?;
+    log::info!(
+        "Pay confirmed game_id={} action={:?} sig={}",
+        game.id, pay_reference.action, signature
+    );
+    Ok(Json(response(PayStatus::Confirmed, Some(signature))))
+}
+
+pub fn pay_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/games/:game_id/pay", post(create_payload))
+        .route("/games/:game_id/pay/:reference", get(get_pay_status))
+        .route(
+            "/pay/games/:game_id/:action",
+            get(get_pay_label).post(post_pay_transaction),
+        )
+        .with_state(state)
+}
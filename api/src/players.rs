@@ -0,0 +1,328 @@
+//! Player statistics, maintained incrementally as games finish.
+//!
+//! **Collections:**
+//! - **player_stats**: { _id: pubkey, games_played, wins, losses, draw_rounds, total_wagered, net_lamports, throws }
+//!
+//! A game is counted once, when it reaches `finished`: [`record_finished_game`] claims it by setting
+//! `stats_recorded` on the game document, then `$inc`s both players' records and updates their ratings.
+
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Json, Router,
+};
+use mongodb::{bson::doc, Database};
+use rps_escrow_client::fees;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ApiError;
+use crate::games::{AppState, Game, User};
+use crate::rating::{self, RatingChange};
+
+/// Path parameter for a player's wallet.
+#[derive(Deserialize)]
+pub struct PubkeyPath {
+    pub pubkey: String,
+}
+
+/// Number of times a player threw each choice.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ThrowCounts {
+    #[serde(default)]
+    pub rock: i64,
+    #[serde(default)]
+    pub paper: i64,
+    #[serde(default)]
+    pub scissors: i64,
+}
+
+impl ThrowCounts {
+    fn add(&mut self, choice: &str) {
+        match choice.to_lowercase().as_str() {
+            "rock" => self.rock += 1,
+            "paper" => self.paper += 1,
+            "scissors" => self.scissors += 1,
+            _ => {}
+        }
+    }
+
+    /// Most thrown choice, or None if the player never threw. Ties resolve in rock, paper, scissors order.
+    pub fn favourite(&self) -> Option<&'static str> {
+        let counts = [("rock", self.rock), ("paper", self.paper), ("scissors", self.scissors)];
+        let mut best: Option<(&'static str, i64)> = None;
+        for (name, n) in counts {
+            if n > 0 && best.map(|(_, b)| n > b).unwrap_or(true) {
+                best = Some((name, n));
+            }
+        }
+        best.map(|(name, _)| name)
+    }
+}
+
+/// Stored per-player totals (collection `player_stats`).
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct PlayerStatsRecord {
+    #[serde(rename = "_id")]
+    pub pubkey: String,
+    #[serde(default)]
+    pub games_played: i64,
+    #[serde(default)]
+    pub wins: i64,
+    #[serde(default)]
+    pub losses: i64,
+    /// Drawn rounds across all finished games.
+    #[serde(default)]
+    pub draw_rounds: i64,
+    /// Sum of stakes in lamports.
+    #[serde(default)]
+    pub total_wagered: i64,
+    /// Lamports won minus lamports lost, after the treasury fee.
+    #[serde(default)]
+    pub net_lamports: i64,
+    #[serde(default)]
+    pub throws: ThrowCounts,
+}
+
+/// Response for `GET /players/:pubkey/stats`.
+#[derive(Serialize)]
+pub struct PlayerStatsResponse {
+    pub pubkey: String,
+    pub games_played: i64,
+    pub wins: i64,
+    pub losses: i64,
+    pub draw_rounds: i64,
+    /// wins / games_played, 0 when no games.
+    pub win_rate: f64,
+    pub total_wagered: i64,
+    pub net_lamports: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favourite_throw: Option<&'static str>,
+}
+
+impl From<PlayerStatsRecord> for PlayerStatsResponse {
+    fn from(r: PlayerStatsRecord) -> Self {
+        let win_rate = if r.games_played > 0 {
+            r.wins as f64 / r.games_played as f64
+        } else {
+            0.0
+        };
+        Self {
+            favourite_throw: r.throws.favourite(),
+            pubkey: r.pubkey,
+            games_played: r.games_played,
+            wins: r.wins,
+            losses: r.losses,
+            draw_rounds: r.draw_rounds,
+            win_rate,
+            total_wagered: r.total_wagered,
+            net_lamports: r.net_lamports,
+        }
+    }
+}
+
+/// Treasury fee on a pot of `pot` lamports (the program's own fee math).
+pub fn treasury_fee(pot: i64) -> i64 {
+    u64::try_from(pot)
+        .ok()
+        .and_then(fees::treasury_fee)
+        .map_or(0, |fee| fee as i64)
+}
+
+/// One player's result in a finished game.
+pub struct PlayerOutcome {
+    pub pubkey: String,
+    pub won: bool,
+    pub wagered: i64,
+    /// Lamports gained (winner: pot minus fee minus own stake) or lost (loser: minus stake).
+    pub net_lamports: i64,
+    pub draw_rounds: i64,
+    pub throws: ThrowCounts,
+}
+
+/// Per-player outcomes of a finished game (creator first). None if the game is not decided.
+pub fn game_outcomes(game: &Game) -> Option<[PlayerOutcome; 2]> {
+    let joiner = game.joiner_pubkey.as_deref()?;
+    let winner = game.winner_pubkey.as_deref()?;
+    let stake = game.amount_per_player;
+    let winner_net = stake - treasury_fee(stake * 2);
+
+    // Games finished before round history was kept only have the decisive choices.
+    let rounds: Vec<(&str, &str)> = if game.rounds.is_empty() {
+        match (&game.creator_choice, &game.joiner_choice) {
+            (Some(c), Some(j)) => vec![(c.as_str(), j.as_str())],
+            _ => Vec::new(),
+        }
+    } else {
+        game.rounds
+            .iter()
+            .map(|r| (r.creator_choice.as_str(), r.joiner_choice.as_str()))
+            .collect()
+    };
+    let draw_rounds = rounds
+        .iter()
+        .filter(|(c, j)| c.eq_ignore_ascii_case(j))
+        .count() as i64;
+
+    let outcome = |pubkey: &str, throws: ThrowCounts| {
+        let won = pubkey == winner;
+        PlayerOutcome {
+            pubkey: pubkey.to_string(),
+            won,
+            wagered: stake,
+            net_lamports: if won { winner_net } else { -stake },
+            draw_rounds,
+            throws,
+        }
+    };
+    let mut creator_throws = ThrowCounts::default();
+    let mut joiner_throws = ThrowCounts::default();
+    for (c, j) in &rounds {
+        creator_throws.add(c);
+        joiner_throws.add(j);
+    }
+
+    Some([
+        outcome(&game.creator_pubkey, creator_throws),
+        outcome(joiner, joiner_throws),
+    ])
+}
+
+/// Counts a finished game in both players' stats, at most once per game.
+/// Errors are logged, not returned: stats must never fail a resolve.
+pub async fn record_finished_game(db: &Database, game_id: &str) {
+    let games = db.collection::<Game>("games");
+    let claimed = games
+        .find_one_and_update(
+            doc! { "_id": game_id, "status": "finished", "stats_recorded": { "$ne": true } },
+            doc! { "$set": { "stats_recorded": true } },
+            None,
+        )
+        .await;
+    let game = match claimed {
+        Ok(Some(g)) => g,
+        Ok(None) => return,
+        Err(e) => {
+            log::error!("Failed to claim game for stats game_id={}: {}", game_id, e);
+            return;
+        }
+    };
+    let outcomes = match game_outcomes(&game) {
+        Some(o) => o,
+        None => {
+            log::warn!("Finished game has no joiner or winner; stats skipped game_id={}", game_id);
+            return;
+        }
+    };
+
+    let stats = db.collection::<PlayerStatsRecord>("player_stats");
+    for o in &outcomes {
+        let update = doc! {
+            "$inc": {
+                "games_played": 1_i64,
+                "wins": if o.won { 1_i64 } else { 0 },
+                "losses": if o.won { 0_i64 } else { 1 },
+                "draw_rounds": o.draw_rounds,
+                "total_wagered": o.wagered,
+                "net_lamports": o.net_lamports,
+                "throws.rock": o.throws.rock,
+                "throws.paper": o.throws.paper,
+                "throws.scissors": o.throws.scissors,
+            }
+        };
+        let opts = mongodb::options::UpdateOptions::builder().upsert(true).build();
+        if let Err(e) = stats.update_one(doc! { "_id": &o.pubkey }, update, opts).await {
+            log::error!(
+                "Failed to update player stats game_id={} pubkey={}: {}",
+                game_id, o.pubkey, e
+            );
+        }
+    }
+
+    let (winner, loser) = if outcomes[0].won {
+        (&outcomes[0].pubkey, &outcomes[1].pubkey)
+    } else {
+        (&outcomes[1].pubkey, &outcomes[0].pubkey)
+    };
+    let receipt = game.game_result_pubkey.as_deref();
+    if let Err(e) = rating::record_game_result(db, game_id, receipt, winner, loser).await {
+        log::error!("Failed to update ratings game_id={}: {}", game_id, e);
+    }
+}
+
+/// Response for `GET /players/:pubkey`.
+#[derive(Serialize)]
+pub struct PlayerProfileResponse {
+    pub pubkey: String,
+    pub rating: i64,
+    /// Most recent rating changes, newest first.
+    pub rating_history: Vec<RatingChange>,
+    pub stats: PlayerStatsResponse,
+}
+
+/// Validates a path pubkey and returns it trimmed.
+fn parse_pubkey_path(path: &PubkeyPath) -> Result<&str, ApiError> {
+    let pubkey = path.pubkey.trim();
+    if pubkey.parse::<solana_sdk::pubkey::Pubkey>().is_err() {
+        return Err(ApiError::bad_request("invalid pubkey (not a valid base58 pubkey)"));
+    }
+    Ok(pubkey)
+}
+
+async fn find_stats(db: &Database, pubkey: &str) -> Result<PlayerStatsRecord, ApiError> {
+    let stats = db.collection::<PlayerStatsRecord>("player_stats");
+    let record = stats
+        .find_one(doc! { "_id": pubkey }, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to find player stats: {}", e);
+            ApiError::internal(e.to_string())
+        })?;
+    Ok(record.unwrap_or_else(|| PlayerStatsRecord {
+        pubkey: pubkey.to_string(),
+        ..Default::default()
+    }))
+}
+
+async fn get_player_stats(
+    State(state): State<AppState>,
+    Path(path): Path<PubkeyPath>,
+) -> Result<Json<PlayerStatsResponse>, ApiError> {
+    let pubkey = parse_pubkey_path(&path)?;
+    Ok(Json(find_stats(&state.db, pubkey).await?.into()))
+}
+
+async fn get_player_profile(
+    State(state): State<AppState>,
+    Path(path): Path<PubkeyPath>,
+) -> Result<Json<PlayerProfileResponse>, ApiError> {
+    let pubkey = parse_pubkey_path(&path)?;
+    let user = state
+        .db
+        .collection::<User>("users")
+        .find_one(doc! { "pubkey": pubkey }, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to find user: {}", e);
+            ApiError::internal(e.to_string())
+        })?;
+    let (rating, mut rating_history) = match user {
+        Some(u) => (u.rating.unwrap_or(rating::INITIAL_RATING), u.rating_history),
+        None => (rating::INITIAL_RATING, Vec::new()),
+    };
+    rating_history.reverse();
+    rating_history.truncate(rating::PROFILE_HISTORY_LEN);
+    let stats = find_stats(&state.db, pubkey).await?.into();
+    Ok(Json(PlayerProfileResponse {
+        pubkey: pubkey.to_string(),
+        rating,
+        rating_history,
+        stats,
+    }))
+}
+
+pub fn players_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/players/:pubkey", get(get_player_profile))
+        .route("/players/:pubkey/stats", get(get_player_stats))
+        .with_state(state)
+}
@@ -0,0 +1,170 @@
+//! Elo skill rating per wallet, stored on the `users` document.
+//!
+//! Updated once per decisive game, when it reaches `finished` (called from
+//! [`crate::players::record_finished_game`], which guarantees at-most-once per game). Both players
+//! are updated in one MongoDB transaction (so the deployment must be a replica set), computed from
+//! the ratings read inside it: two games finishing at once for the same wallet conflict, and the
+//! one retried recomputes its update from the other's result.
+
+use mongodb::{
+    bson::doc,
+    error::{TRANSIENT_TRANSACTION_ERROR, UNKNOWN_TRANSACTION_COMMIT_RESULT},
+    options::UpdateOptions,
+    ClientSession, Collection, Database,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::games::User;
+
+/// Rating of a wallet that has not finished a game yet.
+pub const INITIAL_RATING: i64 = 1200;
+
+/// Elo K-factor: maximum rating change per game.
+const K_FACTOR: f64 = 32.0;
+
+/// Number of rating history entries returned on a profile.
+pub const PROFILE_HISTORY_LEN: usize = 50;
+
+/// Rating history entries kept on the `users` document; older ones are dropped.
+const STORED_HISTORY_LEN: i32 = 200;
+
+/// One rating change, appended to `users.rating_history`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RatingChange {
+    pub game_id: String,
+    /// On-chain `GameResult` receipt of the game, for games created with one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_result_pubkey: Option<String>,
+    pub opponent_pubkey: String,
+    /// Rating after the game.
+    pub rating: i64,
+    pub delta: i64,
+    pub at: String,
+}
+
+/// Expected score of a player rated `rating` against `opponent` (0..1).
+fn expected_score(rating: i64, opponent: i64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) as f64 / 400.0))
+}
+
+/// New (winner, loser) ratings after a decisive game.
+pub fn elo_update(winner: i64, loser: i64) -> (i64, i64) {
+    let delta = (K_FACTOR * (1.0 - expected_score(winner, loser))).round() as i64;
+    (winner + delta, loser - delta)
+}
+
+/// Current rating of `pubkey`, [`INITIAL_RATING`] if unknown.
+pub async fn get_rating(db: &Database, pubkey: &str) -> Result<i64, mongodb::error::Error> {
+    let user = db
+        .collection::<User>("users")
+        .find_one(doc! { "pubkey": pubkey }, None)
+        .await?;
+    Ok(user.and_then(|u| u.rating).unwrap_or(INITIAL_RATING))
+}
+
+/// Applies the Elo update for a finished game to both players' `users` documents.
+///
+/// Both ratings are read and written in one transaction, retried from fresh reads when another
+/// game's update conflicts with it.
+pub async fn record_game_result(
+    db: &Database,
+    game_id: &str,
+    game_result_pubkey: Option<&str>,
+    winner_pubkey: &str,
+    loser_pubkey: &str,
+) -> Result<(), mongodb::error::Error> {
+    let users = db.collection::<User>("users");
+    let mut session = users.client().start_session(None).await?;
+    let change = RatingChange {
+        game_id: game_id.to_string(),
+        game_result_pubkey: game_result_pubkey.map(String::from),
+        opponent_pubkey: String::new(),
+        rating: 0,
+        delta: 0,
+        at: crate::games::now_utc_string(),
+    };
+    loop {
+        session.start_transaction(None).await?;
+        let applied = apply_ratings(&users, &mut session, &change, winner_pubkey, loser_pubkey).await;
+        let (winner, loser) = match applied {
+            Ok(ratings) => ratings,
+            Err(e) => {
+                let _ = session.abort_transaction().await;
+                if e.contains_label(TRANSIENT_TRANSACTION_ERROR) {
+                    continue;
+                }
+                return Err(e);
+            }
+        };
+        match commit(&mut session).await {
+            Ok(()) => {
+                log::info!(
+                    "Ratings updated game_id={} winner {}->{} loser {}->{}",
+                    game_id, winner.0, winner.1, loser.0, loser.1
+                );
+                return Ok(());
+            }
+            Err(e) if e.contains_label(TRANSIENT_TRANSACTION_ERROR) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Commits the session's transaction, retrying while its outcome is unknown.
+async fn commit(session: &mut ClientSession) -> Result<(), mongodb::error::Error> {
+    loop {
+        match session.commit_transaction().await {
+            Err(e) if e.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT) => continue,
+            result => return result,
+        }
+    }
+}
+
+/// Within the session's transaction: reads both ratings, computes the Elo update from them and
+/// writes it with a history entry based on `change`. Returns (before, after) for winner and loser.
+async fn apply_ratings(
+    users: &Collection<User>,
+    session: &mut ClientSession,
+    change: &RatingChange,
+    winner_pubkey: &str,
+    loser_pubkey: &str,
+) -> Result<((i64, i64), (i64, i64)), mongodb::error::Error> {
+    let mut before = [INITIAL_RATING; 2];
+    for (pubkey, rating) in [winner_pubkey, loser_pubkey].into_iter().zip(&mut before) {
+        let user = users
+            .find_one_with_session(doc! { "pubkey": pubkey }, None, session)
+            .await?;
+        if let Some(current) = user.and_then(|u| u.rating) {
+            *rating = current;
+        }
+    }
+    let (winner_after, loser_after) = elo_update(before[0], before[1]);
+    for (pubkey, opponent, before, after) in [
+        (winner_pubkey, loser_pubkey, before[0], winner_after),
+        (loser_pubkey, winner_pubkey, before[1], loser_after),
+    ] {
+        let entry = RatingChange {
+            opponent_pubkey: opponent.to_string(),
+            rating: after,
+            delta: after - before,
+            ..change.clone()
+        };
+        let update = doc! {
+            "$setOnInsert": { "pubkey": pubkey },
+            "$set": { "rating": after },
+            "$push": { "rating_history": {
+                "$each": [mongodb::bson::to_bson(&entry)?],
+                "$slice": -STORED_HISTORY_LEN,
+            } },
+        };
+        users
+            .update_one_with_session(
+                doc! { "pubkey": pubkey },
+                update,
+                UpdateOptions::builder().upsert(true).build(),
+                session,
+            )
+            .await?;
+    }
+    Ok(((before[0], winner_after), (before[1], loser_after)))
+}
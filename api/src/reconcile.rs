@@ -0,0 +1,321 @@
+//! Reconciliation between the `games` collection and live `GameEscrow` accounts on-chain.
+//!
+//! **Collections:**
+//! - **reconciliation_reports**: { _id: "latest", generated_at, escrows_scanned, findings } — last run
+//!
+//! Every escrow still open on-chain (`resolve`, `refund` and `cancel` close it) is fetched with
+//! `getProgramAccounts` filtered on the `GameEscrow` discriminator, together with its vault
+//! balance, and compared with the game whose `game_escrow_pubkey` matches. Joined games in the DB
+//! whose escrow is gone are reported too. Each finding carries a suggested remediation; nothing is
+//! changed automatically.
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use mongodb::{bson::doc, options::ReplaceOptions, Database};
+use rps_escrow_client::{pda, state};
+use serde::{Deserialize, Serialize};
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use crate::games::{now_utc_string, Game, GameStatus};
+use crate::solana::{GameEscrowAccount, SolanaAppClient};
+
+pub const LATEST_REPORT_ID: &str = "latest";
+
+/// `getMultipleAccounts` limit.
+const MULTIPLE_ACCOUNTS_CHUNK: usize = 100;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// Escrow on-chain with no game in the DB.
+    Orphan,
+    /// Game is over in the DB but its escrow (and vault) is still open.
+    StuckVault,
+    /// Stake differs between DB and escrow, or the vault holds less than the deposits.
+    AmountMismatch,
+    /// Creator or joiner differs between DB and escrow.
+    PlayerMismatch,
+    /// Game is joined in the DB but has no open escrow and no closing transaction recorded.
+    MissingEscrow,
+}
+
+/// Suggested fix for a finding.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Remediation {
+    /// Authority `resolve` to the recorded winner.
+    Resolve { winner_pubkey: String },
+    /// Authority `refund` to both players.
+    Refund,
+    /// Only the creator can `cancel` an unjoined escrow; contact them.
+    CreatorCancel,
+    /// Needs a human look (data disagrees in a way no instruction fixes).
+    Investigate,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_escrow_pubkey: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub db_status: Option<GameStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vault_lamports: Option<i64>,
+    pub detail: String,
+    pub remediation: Remediation,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub generated_at: String,
+    pub escrows_scanned: i64,
+    pub findings: Vec<Finding>,
+}
+
+/// An open escrow and the balance of its vault.
+struct OpenEscrow {
+    address: Pubkey,
+    escrow: GameEscrowAccount,
+    vault_lamports: u64,
+}
+
+async fn fetch_open_escrows(rpc: &RpcClient, program_id: &Pubkey) -> Result<Vec<OpenEscrow>, String> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            0,
+            state::GAME_ESCROW_DISCRIMINATOR.to_vec(),
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let accounts = rpc
+        .get_program_accounts_with_config(program_id, config)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut escrows = Vec::with_capacity(accounts.len());
+    for (address, account) in accounts {
+        match state::decode_game_escrow(&account.data) {
+            Ok(escrow) => escrows.push((address, escrow)),
+            Err(e) => log::warn!("Reconciliation: cannot decode escrow {}: {}", address, e),
+        }
+    }
+
+    let vaults: Vec<Pubkey> = escrows.iter().map(|(a, _)| pda::vault_pda(program_id, a).0).collect();
+    let mut balances = Vec::with_capacity(vaults.len());
+    for chunk in vaults.chunks(MULTIPLE_ACCOUNTS_CHUNK) {
+        let accounts = rpc.get_multiple_accounts(chunk).await.map_err(|e| e.to_string())?;
+        balances.extend(accounts.into_iter().map(|a| a.map_or(0, |a| a.lamports)));
+    }
+    Ok(escrows
+        .into_iter()
+        .zip(balances)
+        .map(|((address, escrow), vault_lamports)| OpenEscrow {
+            address,
+            escrow,
+            vault_lamports,
+        })
+        .collect())
+}
+
+/// What should happen to an open escrow given who (if anyone) won.
+fn close_remediation(escrow: &GameEscrowAccount, winner: Option<&str>) -> Remediation {
+    match (escrow.joiner, winner) {
+        (Some(_), Some(w)) => Remediation::Resolve {
+            winner_pubkey: w.to_string(),
+        },
+        (Some(_), None) => Remediation::Refund,
+        (None, _) => Remediation::CreatorCancel,
+    }
+}
+
+/// Findings for one open escrow against its game (if any).
+fn check_escrow(open: &OpenEscrow, game: Option<&Game>) -> Vec<Finding> {
+    let escrow = &open.escrow;
+    let finding = |kind, detail: String, remediation| Finding {
+        kind,
+        game_id: game.map(|g| g.id.clone()),
+        game_escrow_pubkey: Some(open.address.to_string()),
+        db_status: game.map(|g| g.status),
+        vault_lamports: Some(open.vault_lamports as i64),
+        detail,
+        remediation,
+    };
+
+    let Some(game) = game else {
+        let game_id = uuid::Uuid::from_bytes(escrow.game_id);
+        return vec![finding(
+            FindingKind::Orphan,
+            format!("escrow for game_id {} by {} has no game in the DB", game_id, escrow.creator),
+            close_remediation(escrow, None),
+        )];
+    };
+
+    let mut findings = Vec::new();
+    if escrow.creator.to_string() != game.creator_pubkey {
+        findings.push(finding(
+            FindingKind::PlayerMismatch,
+            format!("creator is {} on-chain, {} in the DB", escrow.creator, game.creator_pubkey),
+            Remediation::Investigate,
+        ));
+    }
+    match (escrow.joiner.map(|j| j.to_string()), game.joiner_pubkey.as_deref()) {
+        (Some(onchain), Some(db)) if onchain != db => findings.push(finding(
+            FindingKind::PlayerMismatch,
+            format!("joiner is {} on-chain, {} in the DB", onchain, db),
+            Remediation::Investigate,
+        )),
+        (Some(onchain), None) => findings.push(finding(
+            FindingKind::PlayerMismatch,
+            format!("{} joined on-chain but the DB has no joiner", onchain),
+            Remediation::Refund,
+        )),
+        (None, Some(db)) => findings.push(finding(
+            FindingKind::PlayerMismatch,
+            format!("DB joiner {} never joined on-chain", db),
+            Remediation::CreatorCancel,
+        )),
+        _ => {}
+    }
+
+    if escrow.amount_per_player as i64 != game.amount_per_player {
+        findings.push(finding(
+            FindingKind::AmountMismatch,
+            format!(
+                "amount_per_player is {} on-chain, {} in the DB",
+                escrow.amount_per_player, game.amount_per_player
+            ),
+            Remediation::Investigate,
+        ));
+    }
+    let deposits = if escrow.joiner.is_some() { 2 } else { 1 };
+    let expected = escrow.amount_per_player.saturating_mul(deposits);
+    if open.vault_lamports < expected {
+        findings.push(finding(
+            FindingKind::AmountMismatch,
+            format!("vault holds {} lamports, deposits total {}", open.vault_lamports, expected),
+            Remediation::Investigate,
+        ));
+    }
+
+    let over = matches!(
+        game.status,
+        GameStatus::Finished | GameStatus::ResolveFailed | GameStatus::Cancelled | GameStatus::Refunded
+    );
+    if over {
+        findings.push(finding(
+            FindingKind::StuckVault,
+            format!("game is {:?} in the DB but the escrow is still open", game.status),
+            close_remediation(escrow, game.winner_pubkey.as_deref()),
+        ));
+    }
+    findings
+}
+
+/// Builds a report, stores it as the latest one and returns it.
+pub async fn run(db: &Database, solana: &SolanaAppClient) -> Result<ReconciliationReport, String> {
+    let rpc = RpcClient::new_with_commitment(solana.rpc_url.clone(), CommitmentConfig::confirmed());
+    let open = fetch_open_escrows(&rpc, &solana.program_id).await?;
+
+    let games = db.collection::<Game>("games");
+    let addresses: Vec<String> = open.iter().map(|o| o.address.to_string()).collect();
+    let mut by_escrow: HashMap<String, Game> = HashMap::new();
+    for chunk in addresses.chunks(MULTIPLE_ACCOUNTS_CHUNK) {
+        let mut cursor = games
+            .find(doc! { "game_escrow_pubkey": { "$in": chunk } }, None)
+            .await
+            .map_err(|e| e.to_string())?;
+        while cursor.advance().await.map_err(|e| e.to_string())? {
+            let game = cursor.deserialize_current().map_err(|e| e.to_string())?;
+            if let Some(escrow) = game.game_escrow_pubkey.clone() {
+                by_escrow.insert(escrow, game);
+            }
+        }
+    }
+
+    let mut findings: Vec<Finding> = open
+        .iter()
+        .flat_map(|o| check_escrow(o, by_escrow.get(&o.address.to_string())))
+        .collect();
+
+    // Joined games whose escrow is gone without a resolve or refund on record.
+    let open_set: HashSet<&String> = addresses.iter().collect();
+    let mut cursor = games
+        .find(
+            doc! {
+                "status": { "$in": ["active", "resolving"] },
+                "resolve_tx": null,
+                "refund_tx": null,
+            },
+            None,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    while cursor.advance().await.map_err(|e| e.to_string())? {
+        let game: Game = cursor.deserialize_current().map_err(|e| e.to_string())?;
+        let Some(escrow) = &game.game_escrow_pubkey else {
+            continue;
+        };
+        if !open_set.contains(escrow) {
+            findings.push(Finding {
+                kind: FindingKind::MissingEscrow,
+                game_id: Some(game.id.clone()),
+                game_escrow_pubkey: Some(escrow.clone()),
+                db_status: Some(game.status),
+                vault_lamports: None,
+                detail: "game is joined in the DB but its escrow is not open on-chain".to_string(),
+                remediation: Remediation::Investigate,
+            });
+        }
+    }
+
+    let report = ReconciliationReport {
+        id: LATEST_REPORT_ID.to_string(),
+        generated_at: now_utc_string(),
+        escrows_scanned: open.len() as i64,
+        findings,
+    };
+    db.collection::<ReconciliationReport>("reconciliation_reports")
+        .replace_one(
+            doc! { "_id": LATEST_REPORT_ID },
+            &report,
+            ReplaceOptions::builder().upsert(true).build(),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+/// Runs reconciliation now and then every `interval`, for the lifetime of the process.
+pub fn spawn_reconciliation_job(db: Database, solana: SolanaAppClient, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match run(&db, &solana).await {
+                Ok(report) if report.findings.is_empty() => {
+                    log::info!("Reconciliation: {} escrow(s), no findings", report.escrows_scanned)
+                }
+                Ok(report) => log::warn!(
+                    "Reconciliation: {} escrow(s), {} finding(s); see GET /admin/reconciliation",
+                    report.escrows_scanned,
+                    report.findings.len()
+                ),
+                Err(e) => log::error!("Reconciliation failed: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,172 @@
+//! Batched on-chain resolves.
+//!
+//! Finished games are queued and resolved together with the program's `resolve_batch`: after the
+//! first game arrives the resolver waits up to `RESOLVE_BATCH_WINDOW_MS` for more, up to
+//! `RESOLVE_BATCH_SIZE`, then splits them into transactions that fit `PACKET_DATA_SIZE`. The
+//! compute-unit limit of each transaction comes from its simulation (see
+//! [`crate::fees::build_budgeted_transaction`]).
+//!
+//! A batch is all or nothing on-chain. When one fails permanently (a program error in one game,
+//! or more compute than a transaction allows) each of its games is resolved alone, so every game
+//! gets its own outcome; a transient failure is reported to every game, whose caller retries.
+//! With `RESOLVE_BATCH_SIZE=1` every game is resolved alone, without waiting. Games that need
+//! co-signers (see [`SolanaAppClient::multisig_games`]) are always resolved alone.
+
+use std::time::Duration;
+
+use rps_escrow_client::instructions::BatchGame;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::sender::SendAttempt;
+use crate::solana::{ResolveResult, SolanaAppClient};
+use crate::tx_error::TxError;
+
+/// Outcome of resolving one game, and the transactions sent for it.
+pub type ResolveOutcome = (Result<ResolveResult, TxError>, Vec<SendAttempt>);
+
+struct ResolveJob {
+    game: BatchGame,
+    reply: oneshot::Sender<ResolveOutcome>,
+}
+
+/// Handle on the resolve queue; cheap to clone.
+#[derive(Clone)]
+pub struct Resolver {
+    solana: SolanaAppClient,
+    /// None when batching is disabled.
+    queue: Option<mpsc::UnboundedSender<ResolveJob>>,
+}
+
+impl Resolver {
+    /// Starts the batching task, unless `batch_size` is 1.
+    pub fn spawn(solana: SolanaAppClient, batch_size: usize, window: Duration) -> Self {
+        let queue = (batch_size > 1).then(|| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            tokio::spawn(collect_batches(solana.clone(), rx, batch_size, window));
+            tx
+        });
+        Self { solana, queue }
+    }
+
+    /// Resolves `game` on-chain, batched with the games finishing around the same time.
+    pub async fn resolve(&self, game: BatchGame) -> ResolveOutcome {
+        let Some(queue) = &self.queue else {
+            return resolve_alone(self.solana.clone(), game).await;
+        };
+        let (reply, outcome) = oneshot::channel();
+        if queue.send(ResolveJob { game, reply }).is_err() {
+            return resolve_alone(self.solana.clone(), game).await;
+        }
+        outcome.await.unwrap_or_else(|_| {
+            (Err(TxError::other("Internal", "resolver dropped the game", false)), Vec::new())
+        })
+    }
+}
+
+async fn resolve_alone(solana: SolanaAppClient, game: BatchGame) -> ResolveOutcome {
+    tokio::task::spawn_blocking(move || {
+        let mut attempts = Vec::new();
+        let result = solana.resolve(game.game_id, &game.creator, &game.winner, &mut attempts);
+        (result, attempts)
+    })
+    .await
+    .unwrap_or_else(|e| (Err(TxError::other("Internal", e.to_string(), true)), Vec::new()))
+}
+
+/// Collects queued games into batches of up to `batch_size`, waiting at most `window` after the
+/// first one. Each batch is sent in the background so the next one can fill meanwhile.
+async fn collect_batches(
+    solana: SolanaAppClient,
+    mut rx: mpsc::UnboundedReceiver<ResolveJob>,
+    batch_size: usize,
+    window: Duration,
+) {
+    while let Some(first) = rx.recv().await {
+        let deadline = tokio::time::Instant::now() + window;
+        let mut jobs = vec![first];
+        while jobs.len() < batch_size {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(job)) => jobs.push(job),
+                Ok(None) | Err(_) => break,
+            }
+        }
+        let solana = solana.clone();
+        tokio::spawn(async move {
+            let replies = tokio::task::spawn_blocking(move || {
+                let games: Vec<BatchGame> = jobs.iter().map(|j| j.game).collect();
+                let outcomes = resolve_games(&solana, &games);
+                jobs.into_iter().zip(outcomes).for_each(|(job, outcome)| {
+                    let _ = job.reply.send(outcome);
+                });
+            });
+            if let Err(e) = replies.await {
+                log::error!("Resolve batch task failed: {}", e);
+            }
+        });
+    }
+}
+
+/// Splits `games` into transactions that fit, keeping each game at most once per transaction and
+/// the games flagged in `alone` in a transaction of their own.
+fn split_batches(solana: &SolanaAppClient, games: &[BatchGame], alone: &[bool]) -> Vec<Vec<BatchGame>> {
+    let mut batches: Vec<Vec<BatchGame>> = Vec::new();
+    let mut last_alone = false;
+    for (game, &game_alone) in games.iter().zip(alone) {
+        let same_game = |g: &BatchGame| g.creator == game.creator && g.game_id == game.game_id;
+        let open = batches
+            .last_mut()
+            .filter(|b| !game_alone && !last_alone && !b.iter().any(same_game));
+        if let Some(batch) = open {
+            batch.push(*game);
+            if solana.resolve_batch_fits(batch) {
+                continue;
+            }
+            batch.pop();
+        }
+        batches.push(vec![*game]);
+        last_alone = game_alone;
+    }
+    batches
+}
+
+/// Resolves `games`, one outcome per game in order. Blocking.
+fn resolve_games(solana: &SolanaAppClient, games: &[BatchGame]) -> Vec<ResolveOutcome> {
+    let mut outcomes = Vec::with_capacity(games.len());
+    // Without the config, batch anyway: a failed batch falls back to resolving each game alone.
+    let alone = match games {
+        [_] => vec![true],
+        _ => solana.multisig_games(games).map(|(_, multisig)| multisig).unwrap_or_else(|e| {
+            log::warn!("Resolve config check failed, batching anyway: {}", e);
+            vec![false; games.len()]
+        }),
+    };
+    for batch in split_batches(solana, games, &alone) {
+        if let [game] = batch[..] {
+            let mut attempts = Vec::new();
+            let result = solana.resolve(game.game_id, &game.creator, &game.winner, &mut attempts);
+            outcomes.push((result, attempts));
+            continue;
+        }
+
+        let mut attempts = Vec::new();
+        match solana.resolve_batch(&batch, &mut attempts) {
+            Ok(res) => {
+                log::info!("Resolved {} games in one transaction sig={}", batch.len(), res.signature);
+                outcomes.extend(batch.iter().map(|_| (Ok(res.clone()), attempts.clone())));
+            }
+            Err(e) if !e.permanent => {
+                outcomes.extend(batch.iter().map(|_| (Err(e.clone()), attempts.clone())));
+            }
+            Err(e) => {
+                log::warn!("Resolve batch of {} games failed, resolving each alone: {}", batch.len(), e);
+                for game in &batch {
+                    let mut game_attempts = attempts.clone();
+                    let result =
+                        solana.resolve(game.game_id, &game.creator, &game.winner, &mut game_attempts);
+                    outcomes.push((result, game_attempts));
+                }
+            }
+        }
+    }
+    outcomes
+}
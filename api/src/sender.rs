@@ -0,0 +1,289 @@
+//! Sends escrow transactions until they land or provably cannot.
+//!
+//! A signed transaction is valid until the `last_valid_block_height` of its blockhash. The sender
+//! re-broadcasts the same signed transaction (same signature) until it is confirmed or the block
+//! height passes that limit, and only re-signs with a fresh blockhash after a final signature
+//! status check shows the old one never landed. A retry therefore cannot race a transaction that
+//! landed but whose confirmation was slow. RPC failures move to the next configured endpoint.
+//!
+//! Blocking (sync `RpcClient`); call it from `spawn_blocking`. Each signed transaction is recorded
+//! as one [`SendAttempt`].
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use solana_client::client_error::ClientError;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    signature::Signature,
+    transaction::{Transaction, TransactionError},
+};
+
+use crate::games::now_utc_string;
+use crate::tx_error::TxError;
+
+/// Time between re-broadcasts (and status checks) of the same signed transaction.
+const REBROADCAST_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Signed transactions tried before giving up (each one only after the previous expired).
+const MAX_SIGNINGS: u32 = 3;
+
+/// How long one signed transaction may go without a usable answer from any endpoint before the
+/// sender stops and reports it as unconfirmed. A blockhash lives ~60-90s.
+const ATTEMPT_DEADLINE: Duration = Duration::from_secs(150);
+
+/// How a signed transaction ended.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttemptOutcome {
+    Confirmed,
+    /// Landed and failed on-chain.
+    Failed,
+    /// Blockhash expired without the transaction landing; safe to re-sign.
+    Expired,
+    /// The sender could not find out; the transaction may still have landed.
+    Unknown,
+}
+
+/// One signed transaction and what happened to it. Stored on the game.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SendAttempt {
+    pub signature: String,
+    pub last_valid_block_height: i64,
+    /// Endpoints the transaction was broadcast to.
+    pub rpc_urls: Vec<String>,
+    pub broadcasts: i64,
+    pub outcome: AttemptOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<TxError>,
+    pub started_at: String,
+    pub finished_at: String,
+}
+
+/// A signature status reduced to what the sender needs: reached the sender's commitment, and the
+/// on-chain error if it failed.
+type Status = (bool, Option<TransactionError>);
+
+/// Sends through an ordered list of RPC endpoints, failing over on errors.
+pub struct TransactionSender {
+    endpoints: Vec<(String, RpcClient)>,
+    current: usize,
+    commitment: CommitmentConfig,
+}
+
+impl TransactionSender {
+    /// `rpc_urls` in order of preference; must not be empty.
+    pub fn new(rpc_urls: &[String], commitment: CommitmentConfig) -> Self {
+        assert!(!rpc_urls.is_empty(), "TransactionSender needs at least one RPC URL");
+        let endpoints = rpc_urls
+            .iter()
+            .map(|url| (url.clone(), RpcClient::new_with_commitment(url.clone(), commitment)))
+            .collect();
+        Self {
+            endpoints,
+            current: 0,
+            commitment,
+        }
+    }
+
+    /// Client of the endpoint currently in use.
+    pub fn rpc(&self) -> &RpcClient {
+        &self.endpoints[self.current].1
+    }
+
+    fn url(&self) -> &str {
+        &self.endpoints[self.current].0
+    }
+
+    /// Runs `call` on the current endpoint, then each other one in turn, until one succeeds.
+    /// Returns the last endpoint's error if all fail.
+    pub fn with_failover<T>(
+        &mut self,
+        what: &str,
+        mut call: impl FnMut(&RpcClient) -> Result<T, ClientError>,
+    ) -> Result<T, TxError> {
+        let mut last_err = None;
+        for _ in 0..self.endpoints.len() {
+            match call(self.rpc()) {
+                Ok(value) => return Ok(value),
+                Err(e) => {
+                    log::warn!("{} failed on {}: {}", what, self.url(), e);
+                    last_err = Some(TxError::from_client_error(&e));
+                    self.current = (self.current + 1) % self.endpoints.len();
+                }
+            }
+        }
+        Err(last_err.expect("at least one endpoint"))
+    }
+
+    /// Signs with `sign` (given the current endpoint and a fresh blockhash) and sends until
+    /// confirmed. Every signed transaction is appended to `attempts`, including on error.
+    pub fn send(
+        &mut self,
+        attempts: &mut Vec<SendAttempt>,
+        mut sign: impl FnMut(&RpcClient, Hash) -> Result<Transaction, TxError>,
+    ) -> Result<Signature, TxError> {
+        for _ in 0..MAX_SIGNINGS {
+            let commitment = self.commitment;
+            let (blockhash, last_valid_block_height) = self
+                .with_failover("getLatestBlockhash", |rpc| {
+                    rpc.get_latest_blockhash_with_commitment(commitment)
+                })?;
+            let tx = sign(self.rpc(), blockhash)?;
+
+            let mut attempt = SendAttempt {
+                signature: tx.signatures[0].to_string(),
+                last_valid_block_height: last_valid_block_height as i64,
+                rpc_urls: Vec::new(),
+                broadcasts: 0,
+                outcome: AttemptOutcome::Unknown,
+                error: None,
+                started_at: now_utc_string(),
+                finished_at: String::new(),
+            };
+            let result = self.broadcast_until_final(&tx, last_valid_block_height, &mut attempt);
+            attempt.finished_at = now_utc_string();
+            log::info!(
+                "Transaction {} {:?} after {} broadcast(s)",
+                attempt.signature, attempt.outcome, attempt.broadcasts
+            );
+            attempt.error = result.as_ref().err().cloned();
+            let outcome = attempt.outcome;
+            attempts.push(attempt);
+            match outcome {
+                AttemptOutcome::Expired => continue,
+                _ => return result.map(|()| tx.signatures[0]),
+            }
+        }
+        Err(TxError::other(
+            "Expired",
+            format!("transaction expired {} times without landing", MAX_SIGNINGS),
+            false,
+        ))
+    }
+
+    /// Re-broadcasts `tx` until it is confirmed, fails on-chain, or its blockhash expires.
+    /// Sets `attempt.outcome`; Err for `Failed` and `Unknown`.
+    fn broadcast_until_final(
+        &mut self,
+        tx: &Transaction,
+        last_valid_block_height: u64,
+        attempt: &mut SendAttempt,
+    ) -> Result<(), TxError> {
+        let signature = tx.signatures[0];
+        let config = RpcSendTransactionConfig {
+            // Simulated when built; the RPC's own retry queue is replaced by this loop.
+            skip_preflight: true,
+            max_retries: Some(0),
+            ..Default::default()
+        };
+        let mut last_answer = Instant::now();
+        loop {
+            match self.rpc().send_transaction_with_config(tx, config) {
+                Ok(_) => {
+                    attempt.broadcasts += 1;
+                    if !attempt.rpc_urls.iter().any(|u| u == self.url()) {
+                        attempt.rpc_urls.push(self.url().to_string());
+                    }
+                }
+                Err(e) => {
+                    log::warn!("sendTransaction {} failed on {}: {}", signature, self.url(), e);
+                    self.current = (self.current + 1) % self.endpoints.len();
+                }
+            }
+            std::thread::sleep(REBROADCAST_INTERVAL);
+
+            let commitment = self.commitment;
+            let status = self.with_failover("getSignatureStatuses", |rpc| {
+                rpc.get_signature_statuses(&[signature]).map(|r| {
+                    r.value
+                        .into_iter()
+                        .next()
+                        .flatten()
+                        .map(|s| (s.satisfies_commitment(commitment), s.err))
+                })
+            });
+            let block_height = self.with_failover("getBlockHeight", |rpc| rpc.get_block_height());
+            match (status, block_height) {
+                (Ok(Some(status)), _) => {
+                    last_answer = Instant::now();
+                    if let Some(done) = settle(status, attempt) {
+                        return done;
+                    }
+                }
+                (Ok(None), Ok(height)) => {
+                    last_answer = Instant::now();
+                    if height > last_valid_block_height {
+                        return self.check_expired(signature, attempt);
+                    }
+                }
+                _ => {}
+            }
+            if last_answer.elapsed() > ATTEMPT_DEADLINE {
+                attempt.outcome = AttemptOutcome::Unknown;
+                return Err(unconfirmed(&signature));
+            }
+        }
+    }
+
+    /// Blockhash expired: one last status check (with history) before declaring the
+    /// transaction dropped.
+    fn check_expired(&mut self, signature: Signature, attempt: &mut SendAttempt) -> Result<(), TxError> {
+        let commitment = self.commitment;
+        let status = self.with_failover("getSignatureStatuses", |rpc| {
+            rpc.get_signature_statuses_with_history(&[signature]).map(|r| {
+                r.value
+                    .into_iter()
+                    .next()
+                    .flatten()
+                    .map(|s| (s.satisfies_commitment(commitment), s.err))
+            })
+        });
+        match status {
+            Ok(None) => {
+                attempt.outcome = AttemptOutcome::Expired;
+                Err(TxError::other("Expired", format!("transaction {} expired", signature), false))
+            }
+            Ok(Some(status)) => match settle(status, attempt) {
+                Some(done) => done,
+                None => {
+                    attempt.outcome = AttemptOutcome::Unknown;
+                    Err(unconfirmed(&signature))
+                }
+            },
+            Err(_) => {
+                attempt.outcome = AttemptOutcome::Unknown;
+                Err(unconfirmed(&signature))
+            }
+        }
+    }
+}
+
+/// Final answer from a signature status, or None while not yet at the sender's commitment.
+fn settle((reached, err): Status, attempt: &mut SendAttempt) -> Option<Result<(), TxError>> {
+    if !reached {
+        return None;
+    }
+    Some(match err {
+        None => {
+            attempt.outcome = AttemptOutcome::Confirmed;
+            Ok(())
+        }
+        Some(err) => {
+            attempt.outcome = AttemptOutcome::Failed;
+            Err(TxError::from_transaction_error(&err, Vec::new()))
+        }
+    })
+}
+
+/// The transaction may have landed: permanent so that nothing re-signs it blindly.
+fn unconfirmed(signature: &Signature) -> TxError {
+    TxError::other(
+        "Unconfirmed",
+        format!("could not determine whether transaction {} landed; check it before retrying", signature),
+        true,
+    )
+}
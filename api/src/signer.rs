@@ -0,0 +1,418 @@
+//! Signer backends for the resolve authority.
+//!
+//! [`ResolveSigner`] signs the messages of the API's escrow transactions. Backends
+//! (`RESOLVE_SIGNER`):
+//! - **file** (default): JSON keypair file at `RESOLVE_AUTHORITY_KEYPAIR_PATH`
+//! - **env**: keypair injected in `RESOLVE_AUTHORITY_KEYPAIR` (JSON byte array or base58)
+//! - **remote**: signer service at `RESOLVE_SIGNER_URL` (`http://host:port` or `unix:/path.sock`);
+//!   the key never reaches the API host
+//!
+//! **Remote protocol** (HTTP/1.1, JSON bodies, one request per connection):
+//! - `GET /pubkey` → `{ "pubkey": "<base58>" }`
+//! - `POST /sign` `{ "message": "<base64 message bytes>", "instructions": [DecodedInstruction] }`
+//!   → `{ "signature": "<base58>" }`; `403 { "error" }` when the signer's policy refuses.
+//! - `POST /sign-verdict` `{ "game_escrow", "game_id": "<32 hex>", "winner", "expiry", "message":
+//!   "<base64 verdict message>" }` → `{ "signature": "<base58>" }`; same errors. The message is the
+//!   program's `verdict_message` of the other fields, which the winner submits with
+//!   `resolve_with_attestation`.
+//!
+//! `instructions` describes the program instructions in the message (name, named accounts,
+//! args) so the signer can apply its own policy. A signer must check them against the message
+//! it actually signs. `src/bin/resolve-signer.rs` is a local stand-in.
+//!
+//! Games staking at least the program's `multisig_min_amount` also need the other resolve
+//! authorities: their remote signers (`RESOLVE_COSIGNER_URLS`) get the same requests.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rps_escrow_client::instructions;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    bs58,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+
+use crate::config::ResolveSignerConfig;
+use crate::tx_error::TxError;
+
+/// Timeout for each read and write on the remote signer connection.
+const REMOTE_IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One account of a decoded instruction, named as in the IDL.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NamedAccount {
+    pub name: String,
+    pub pubkey: String,
+}
+
+/// A program instruction as the IDL describes it. Sent to remote signers.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct DecodedInstruction {
+    pub program_id: String,
+    pub name: String,
+    /// In instruction order.
+    pub accounts: Vec<NamedAccount>,
+    pub args: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SignRequest {
+    pub message: String,
+    pub instructions: Vec<DecodedInstruction>,
+}
+
+/// A verdict: the resolve authority attests that `winner` won the game at `game_escrow` until the
+/// unix timestamp `expiry`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerdictTerms {
+    pub game_escrow: Pubkey,
+    pub game_id: [u8; 16],
+    pub winner: Pubkey,
+    pub expiry: i64,
+}
+
+impl VerdictTerms {
+    /// Bytes to sign (see `instructions::verdict_message`).
+    pub fn message(&self) -> Vec<u8> {
+        instructions::verdict_message(&self.game_escrow, &self.game_id, &self.winner, self.expiry)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VerdictSignRequest {
+    pub game_escrow: String,
+    /// UUID bytes as 32 hex digits.
+    pub game_id: String,
+    pub winner: String,
+    pub expiry: i64,
+    /// Base64 [`VerdictTerms::message`] of the fields above.
+    pub message: String,
+}
+
+impl VerdictSignRequest {
+    pub fn new(terms: &VerdictTerms) -> Self {
+        use base64::Engine;
+        Self {
+            game_escrow: terms.game_escrow.to_string(),
+            game_id: uuid::Uuid::from_bytes(terms.game_id).simple().to_string(),
+            winner: terms.winner.to_string(),
+            expiry: terms.expiry,
+            message: base64::engine::general_purpose::STANDARD.encode(terms.message()),
+        }
+    }
+
+    /// The terms, if the fields parse and `message` is theirs.
+    pub fn terms(&self) -> Result<VerdictTerms, String> {
+        use base64::Engine;
+        let terms = VerdictTerms {
+            game_escrow: self.game_escrow.parse().map_err(|_| "invalid game_escrow")?,
+            game_id: *uuid::Uuid::parse_str(&self.game_id)
+                .map_err(|e| e.to_string())?
+                .as_bytes(),
+            winner: self.winner.parse().map_err(|_| "invalid winner")?,
+            expiry: self.expiry,
+        };
+        let message = base64::engine::general_purpose::STANDARD
+            .decode(&self.message)
+            .map_err(|e| e.to_string())?;
+        if message != terms.message() {
+            return Err("message does not match the verdict".to_string());
+        }
+        Ok(terms)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SignResponse {
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PubkeyResponse {
+    pub pubkey: String,
+}
+
+#[derive(Debug)]
+pub enum SignerError {
+    /// The signer refused (policy); sending again will not help.
+    Rejected(String),
+    /// The signer could not be reached or failed.
+    Unavailable(String),
+}
+
+impl From<SignerError> for TxError {
+    fn from(e: SignerError) -> Self {
+        match e {
+            SignerError::Rejected(msg) => TxError::other("SignerRejected", msg, true),
+            SignerError::Unavailable(msg) => TxError::other("SignerUnavailable", msg, false),
+        }
+    }
+}
+
+/// Holder of the resolve authority key.
+pub trait ResolveSigner: Send + Sync {
+    fn pubkey(&self) -> Pubkey;
+
+    /// Signs serialized message bytes. `instructions` describes the program instructions in it.
+    fn sign_message(
+        &self,
+        message: &[u8],
+        instructions: &[DecodedInstruction],
+    ) -> Result<Signature, SignerError>;
+
+    /// Signs the message of `verdict`.
+    fn sign_verdict(&self, verdict: &VerdictTerms) -> Result<Signature, SignerError>;
+}
+
+/// Signer holding the keypair in memory (file and env backends).
+pub struct KeypairSigner(Keypair);
+
+impl KeypairSigner {
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        crate::solana::load_keypair(path).map(Self)
+    }
+
+    /// Parses a JSON byte array (as in a keypair file) or a base58 secret key.
+    pub fn from_env_value(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        let bytes: Vec<u8> = if value.starts_with('[') {
+            serde_json::from_str(value).map_err(|e| e.to_string())?
+        } else {
+            bs58::decode(value).into_vec().map_err(|e| e.to_string())?
+        };
+        Keypair::try_from(bytes.as_slice()).map(Self).map_err(|e| e.to_string())
+    }
+}
+
+impl ResolveSigner for KeypairSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.0.pubkey()
+    }
+
+    fn sign_message(&self, message: &[u8], _: &[DecodedInstruction]) -> Result<Signature, SignerError> {
+        Ok(self.0.sign_message(message))
+    }
+
+    fn sign_verdict(&self, verdict: &VerdictTerms) -> Result<Signature, SignerError> {
+        Ok(self.0.sign_message(&verdict.message()))
+    }
+}
+
+enum Endpoint {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+/// Signer service speaking the remote protocol.
+pub struct RemoteSigner {
+    endpoint: Endpoint,
+    pubkey: Pubkey,
+}
+
+impl RemoteSigner {
+    /// Parses `url` and asks the signer for its public key.
+    pub fn connect(url: &str) -> Result<Self, String> {
+        let endpoint = if let Some(path) = url.strip_prefix("unix:") {
+            Endpoint::Unix(PathBuf::from(path))
+        } else if let Some(addr) = url.strip_prefix("http://") {
+            Endpoint::Tcp(addr.trim_end_matches('/').to_string())
+        } else {
+            return Err(format!("unsupported signer URL {:?} (use http://host:port or unix:/path)", url));
+        };
+        let mut signer = Self {
+            endpoint,
+            pubkey: Pubkey::default(),
+        };
+        let (status, body) = signer.request("GET", "/pubkey", None)?;
+        if status != 200 {
+            return Err(format!("GET /pubkey returned {}", status));
+        }
+        let response: PubkeyResponse = serde_json::from_slice(&body).map_err(|e| e.to_string())?;
+        signer.pubkey = response.pubkey.parse().map_err(|e| format!("invalid signer pubkey: {:?}", e))?;
+        Ok(signer)
+    }
+
+    fn request(&self, method: &str, path: &str, body: Option<&[u8]>) -> Result<(u16, Vec<u8>), String> {
+        let mut stream: Box<dyn ReadWrite> = match &self.endpoint {
+            Endpoint::Tcp(addr) => {
+                let s = TcpStream::connect(addr).map_err(|e| format!("connect {}: {}", addr, e))?;
+                s.set_read_timeout(Some(REMOTE_IO_TIMEOUT)).map_err(|e| e.to_string())?;
+                s.set_write_timeout(Some(REMOTE_IO_TIMEOUT)).map_err(|e| e.to_string())?;
+                Box::new(s)
+            }
+            Endpoint::Unix(socket) => {
+                let s = UnixStream::connect(socket)
+                    .map_err(|e| format!("connect {}: {}", socket.display(), e))?;
+                s.set_read_timeout(Some(REMOTE_IO_TIMEOUT)).map_err(|e| e.to_string())?;
+                s.set_write_timeout(Some(REMOTE_IO_TIMEOUT)).map_err(|e| e.to_string())?;
+                Box::new(s)
+            }
+        };
+        let body = body.unwrap_or_default();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: resolve-signer\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            method,
+            path,
+            body.len()
+        )
+        .and_then(|()| stream.write_all(body))
+        .map_err(|e| e.to_string())?;
+        let (status_line, body) = read_http_message(&mut stream).map_err(|e| e.to_string())?;
+        let status = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| format!("bad status line {:?}", status_line))?;
+        Ok((status, body))
+    }
+
+    /// POSTs `request` to `path` and parses the signature of a 200.
+    fn request_signature(&self, path: &str, request: &impl Serialize) -> Result<Signature, SignerError> {
+        let body = serde_json::to_vec(request).map_err(|e| SignerError::Unavailable(e.to_string()))?;
+        let (status, body) = self
+            .request("POST", path, Some(&body))
+            .map_err(SignerError::Unavailable)?;
+        let text = || String::from_utf8_lossy(&body).into_owned();
+        match status {
+            200 => {
+                let response: SignResponse =
+                    serde_json::from_slice(&body).map_err(|e| SignerError::Unavailable(e.to_string()))?;
+                response
+                    .signature
+                    .parse()
+                    .map_err(|e| SignerError::Unavailable(format!("invalid signature: {:?}", e)))
+            }
+            400 | 403 => Err(SignerError::Rejected(text())),
+            _ => Err(SignerError::Unavailable(format!("signer returned {}: {}", status, text()))),
+        }
+    }
+}
+
+trait ReadWrite: Read + Write {}
+impl<T: Read + Write> ReadWrite for T {}
+
+impl ResolveSigner for RemoteSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn sign_message(
+        &self,
+        message: &[u8],
+        instructions: &[DecodedInstruction],
+    ) -> Result<Signature, SignerError> {
+        use base64::Engine;
+        let request = SignRequest {
+            message: base64::engine::general_purpose::STANDARD.encode(message),
+            instructions: instructions.to_vec(),
+        };
+        self.request_signature("/sign", &request)
+    }
+
+    fn sign_verdict(&self, verdict: &VerdictTerms) -> Result<Signature, SignerError> {
+        self.request_signature("/sign-verdict", &VerdictSignRequest::new(verdict))
+    }
+}
+
+/// Builds the configured signer.
+pub fn from_config(config: &ResolveSignerConfig) -> Result<Box<dyn ResolveSigner>, String> {
+    Ok(match config {
+        ResolveSignerConfig::File(path) => Box::new(KeypairSigner::from_file(path)?),
+        ResolveSignerConfig::Env(value) => Box::new(KeypairSigner::from_env_value(value)?),
+        ResolveSignerConfig::Remote(url) => Box::new(RemoteSigner::connect(url)?),
+    })
+}
+
+/// Signs `tx` as `signer`, then as each of `cosigners` and, when fees are paid by a separate
+/// account, as `fee_payer`. These must be exactly the transaction's required signers: fee payer,
+/// resolve authority, co-signers.
+pub fn sign_transaction(
+    signer: &dyn ResolveSigner,
+    cosigners: &[&dyn ResolveSigner],
+    fee_payer: Option<&Keypair>,
+    tx: &mut Transaction,
+    instructions: &[DecodedInstruction],
+) -> Result<(), TxError> {
+    let signers: Vec<&dyn ResolveSigner> = std::iter::once(signer).chain(cosigners.iter().copied()).collect();
+    let expected: Vec<Pubkey> = fee_payer
+        .map(|k| k.pubkey())
+        .into_iter()
+        .chain(signers.iter().map(|s| s.pubkey()))
+        .collect();
+    let required = usize::from(tx.message.header.num_required_signatures);
+    if tx.message.account_keys.get(..required) != Some(expected.as_slice()) {
+        return Err(TxError::other(
+            "SignerError",
+            "transaction signers must be the fee payer, the resolve authority and its co-signers",
+            true,
+        ));
+    }
+    let message = tx.message_data();
+    let mut signatures: Vec<Signature> = fee_payer.map(|k| k.sign_message(&message)).into_iter().collect();
+    for signer in signers {
+        let signature = signer.sign_message(&message, instructions)?;
+        if !signature.verify(signer.pubkey().as_ref(), &message) {
+            return Err(TxError::other(
+                "SignerError",
+                format!("signer {} returned an invalid signature", signer.pubkey()),
+                true,
+            ));
+        }
+        signatures.push(signature);
+    }
+    tx.signatures = signatures;
+    Ok(())
+}
+
+/// Signs `verdict` as `signer` and checks the signature.
+pub fn sign_verdict(signer: &dyn ResolveSigner, verdict: &VerdictTerms) -> Result<Signature, TxError> {
+    let signature = signer.sign_verdict(verdict)?;
+    if !signature.verify(signer.pubkey().as_ref(), &verdict.message()) {
+        return Err(TxError::other(
+            "SignerError",
+            format!("signer {} returned an invalid verdict signature", signer.pubkey()),
+            true,
+        ));
+    }
+    Ok(signature)
+}
+
+/// Reads one HTTP/1.1 message: returns the start line and the body (`Content-Length` bytes;
+/// without the header, empty for requests and up to EOF for responses). Shared with the
+/// stand-in signer.
+pub fn read_http_message(stream: &mut impl Read) -> std::io::Result<(String, Vec<u8>)> {
+    let mut reader = BufReader::new(stream);
+    let mut start_line = String::new();
+    reader.read_line(&mut start_line)?;
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let mut body = Vec::new();
+    match content_length {
+        Some(len) => {
+            body.resize(len, 0);
+            reader.read_exact(&mut body)?;
+        }
+        None if start_line.starts_with("HTTP/") => {
+            reader.read_to_end(&mut body)?;
+        }
+        None => {}
+    }
+    Ok((start_line.trim().to_string(), body))
+}
@@ -1,173 +1,723 @@
-//! Interaction with the rps-escrow program on Solana mainnet.
+//! Interaction with the rps-escrow program on Solana.
 //!
-//! Follows [Anchor Rust client](https://www.anchor-lang.com/docs/clients/rust): IDL-generated
-//! client to build instructions. Send via RpcClient so the handler stays Send (keypair is !Send).
+//! Instructions, PDAs and accounts come from `rps-escrow-client`, built on the program crate
+//! itself. Resolve and refund are signed by the configured [`ResolveSigner`] (and the fee payer,
+//! when separate, which then pays their fees) and sent via RpcClient. Games staking at least the
+//! program's resolve config `multisig_min_amount` are also signed by enough co-signers
+//! (`RESOLVE_COSIGNER_URLS`) to reach its threshold. The
+//! resolve authority also signs the verdicts winners claim themselves (see [`crate::verdict`]).
+//!
+//! The program id comes from config (`RPS_ESCROW_PROGRAM_ID`), never from the IDL's `address`:
+//! the same binary serves localnet, devnet and mainnet deployments. [`SolanaAppClient::verify_program`]
+//! checks at startup that the RPC is on the configured cluster and that the program deployed at
+//! that id matches the bundled IDL.
 
-use anchor_attribute_program::declare_program;
-use anchor_client::Client;
 use anchor_lang::prelude::Pubkey;
+use rps_escrow_client::instructions::{self, BatchGame};
+use rps_escrow_client::{pda, state, ResolveConfig};
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
-    signature::{Keypair, Signer},
+    instruction::Instruction,
+    packet::PACKET_DATA_SIZE,
+    signature::{Keypair, Signature, Signer},
     transaction::Transaction,
 };
-use std::rc::Rc;
-use std::str::FromStr;
+use std::collections::BTreeMap;
+use std::io::Read;
 use std::sync::Arc;
 
-use crate::config::Config;
+use crate::config::{ClusterProfile, Config};
+use crate::fees::{budgeted_transaction_size, build_budgeted_transaction, FeePolicy, PriorityFee};
+use crate::sender::{SendAttempt, TransactionSender};
+use crate::signer::{self, DecodedInstruction, NamedAccount, ResolveSigner, VerdictTerms};
+use crate::tx_error::TxError;
+
+/// Decoded `GameEscrow` account (the program's own type).
+pub type GameEscrowAccount = rps_escrow_client::GameEscrow;
 
-declare_program!(rps_escrow);
+/// Decoded `GameResult` receipt (the program's own type).
+pub type GameResultAccount = rps_escrow_client::GameResult;
 
-use rps_escrow::client::{accounts, args};
+/// IDL bundled with the API, compared with the program's published IDL at startup.
+pub(crate) const BUNDLED_IDL: &str = include_str!("../idls/rps_escrow.json");
 
-/// RPS escrow program id (from IDL). Use for PDA derivation when not using the full client.
+/// Program id declared by the program. Only a fallback for PDA derivation when no Solana client
+/// is configured; everything else uses the configured `RPS_ESCROW_PROGRAM_ID`.
 pub fn program_id() -> Pubkey {
-    rps_escrow::ID
+    rps_escrow_client::ID
 }
 
-/// PDA seeds for game escrow: `["game_escrow", creator, game_id]`.
-pub fn game_escrow_pda(program_id: &Pubkey, creator: &Pubkey, game_id: &[u8; 16]) -> Pubkey {
-    let seeds: &[&[u8]] = &[b"game_escrow", creator.as_ref(), game_id.as_ref()];
-    Pubkey::find_program_address(seeds, program_id).0
+/// Address where `anchor idl init` stores a program's IDL.
+pub fn idl_address(program_id: &Pubkey) -> Pubkey {
+    let base = Pubkey::find_program_address(&[], program_id).0;
+    Pubkey::create_with_seed(&base, "anchor:idl", program_id).expect("static IDL seed is valid")
 }
 
-/// PDA for vault: seeds = `["vault", game_escrow.key()]`.
-pub fn vault_pda(program_id: &Pubkey, game_escrow: &Pubkey) -> Pubkey {
-    let seeds: &[&[u8]] = &[b"vault", game_escrow.as_ref()];
-    Pubkey::find_program_address(seeds, program_id).0
+/// Load keypair from a JSON file (array of 64 bytes).
+pub fn load_keypair(path: &std::path::Path) -> Result<Keypair, String> {
+    let bytes: Vec<u8> = serde_json::from_reader(std::fs::File::open(path).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+    Keypair::try_from(bytes.as_slice()).map_err(|e| e.to_string())
 }
 
-/// System program id (for resolve instruction).
-pub fn system_program_id() -> Pubkey {
-    Pubkey::from_str("11111111111111111111111111111111").unwrap()
+/// The parts of an IDL that must agree for the API to talk to a program: instruction and account
+/// discriminators, and error codes.
+#[derive(Debug, PartialEq)]
+struct IdlSummary {
+    instructions: BTreeMap<String, Vec<u64>>,
+    accounts: BTreeMap<String, Vec<u64>>,
+    errors: BTreeMap<u64, String>,
 }
 
-/// Treasury pubkey for resolve (receives 3% fee). Must match program constant TREASURY_PUBKEY.
-pub fn treasury_pubkey() -> Pubkey {
-    Pubkey::from_str("Ft6kMwkButM1J7iHJBJTb8QFEBuoBPnG1jq83HMRE9mF").unwrap()
+impl IdlSummary {
+    fn from_json(idl: &serde_json::Value) -> Self {
+        let named = |key: &str| -> BTreeMap<String, Vec<u64>> {
+            idl[key]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|item| {
+                    let name = item["name"].as_str().unwrap_or_default().to_string();
+                    let disc = item["discriminator"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|b| b.as_u64())
+                        .collect();
+                    (name, disc)
+                })
+                .collect()
+        };
+        let errors = idl["errors"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|e| Some((e["code"].as_u64()?, e["name"].as_str()?.to_string())))
+            .collect();
+        Self {
+            instructions: named("instructions"),
+            accounts: named("accounts"),
+            errors,
+        }
+    }
+
+    /// Human-readable differences between `self` (bundled) and `other` (on-chain).
+    fn diff(&self, other: &Self) -> Vec<String> {
+        fn diff_maps<K: Ord + std::fmt::Debug, V: PartialEq>(
+            what: &str,
+            bundled: &BTreeMap<K, V>,
+            onchain: &BTreeMap<K, V>,
+            out: &mut Vec<String>,
+        ) {
+            for (k, v) in bundled {
+                match onchain.get(k) {
+                    None => out.push(format!("{} {:?} missing on-chain", what, k)),
+                    Some(o) if o != v => out.push(format!("{} {:?} differs", what, k)),
+                    _ => {}
+                }
+            }
+            for k in onchain.keys().filter(|k| !bundled.contains_key(*k)) {
+                out.push(format!("{} {:?} only on-chain", what, k));
+            }
+        }
+        let mut out = Vec::new();
+        diff_maps("instruction", &self.instructions, &other.instructions, &mut out);
+        diff_maps("account", &self.accounts, &other.accounts, &mut out);
+        diff_maps("error", &self.errors, &other.errors, &mut out);
+        out
+    }
 }
 
-/// Load keypair from a JSON file (array of 64 bytes).
-pub fn load_keypair(path: &std::path::Path) -> Result<Keypair, String> {
-    let bytes: Vec<u8> = serde_json::from_reader(std::fs::File::open(path).map_err(|e| e.to_string())?)
-        .map_err(|e| e.to_string())?;
-    Keypair::try_from(bytes.as_slice()).map_err(|e| e.to_string())
+/// Decodes an Anchor IDL account: 8-byte discriminator, authority, u32 length, zlib-compressed JSON.
+fn decode_idl_account(data: &[u8]) -> Result<serde_json::Value, String> {
+    const HEADER: usize = 8 + 32;
+    let len_bytes: [u8; 4] = data
+        .get(HEADER..HEADER + 4)
+        .and_then(|b| b.try_into().ok())
+        .ok_or("IDL account too short")?;
+    let len = u32::from_le_bytes(len_bytes) as usize;
+    let compressed = data
+        .get(HEADER + 4..HEADER + 4 + len)
+        .ok_or("IDL account data truncated")?;
+    let mut json = String::new();
+    flate2::read::ZlibDecoder::new(compressed)
+        .read_to_string(&mut json)
+        .map_err(|e| format!("IDL decompress failed: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("IDL JSON invalid: {}", e))
 }
 
 /// Result of calling resolve on-chain.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ResolveResult {
     pub signature: String,
+    pub priority_fee: PriorityFee,
 }
 
-/// Calls the rps-escrow **resolve** instruction on mainnet using the IDL-generated client.
-/// Builds the instruction with the client (Anchor doc pattern); sends with RpcClient so the handler stays Send.
-/// `game_id` must be the 16-byte UUID (no hyphens). `creator_pubkey` and `winner_pubkey` are base58.
-pub fn resolve_game(
-    rpc_url: &str,
-    authority_keypair: &Keypair,
-    game_id: [u8; 16],
-    creator_pubkey: &str,
-    winner_pubkey: &str,
-) -> Result<ResolveResult, String> {
-    let creator = Pubkey::from_str(creator_pubkey).map_err(|e| e.to_string())?;
-    let winner = Pubkey::from_str(winner_pubkey).map_err(|e| e.to_string())?;
-
-    let commitment = CommitmentConfig::confirmed();
-    let client = Client::new_with_options(
-        anchor_client::Cluster::Custom(rpc_url.to_string(), rpc_url.to_string()),
-        Rc::new(authority_keypair),
-        commitment,
-    );
-    let program = client.program(rps_escrow::ID).map_err(|e| e.to_string())?;
-
-    let game_escrow = game_escrow_pda(&rps_escrow::ID, &creator, &game_id);
-    let vault = vault_pda(&rps_escrow::ID, &game_escrow);
-
-    let resolve_ix = program
-        .request()
-        .accounts(accounts::Resolve {
-            authority: authority_keypair.pubkey(),
-            game_escrow,
-            vault,
-            winner_destination: winner,
-            creator,
-            treasury: treasury_pubkey(),
-            system_program: system_program_id(),
-        })
-        .args(args::Resolve { winner })
-        .instructions()
-        .map_err(|e| e.to_string())?
+/// Describes `ix` for a remote signer: account names from the bundled IDL instruction `name`.
+fn describe_instruction(name: &str, ix: &Instruction, args: serde_json::Value) -> DecodedInstruction {
+    let idl: serde_json::Value = serde_json::from_str(BUNDLED_IDL).unwrap_or_default();
+    let names: Vec<String> = idl["instructions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|i| i["name"] == name)
+        .and_then(|i| i["accounts"].as_array())
         .into_iter()
-        .next()
-        .ok_or_else(|| "resolve instruction missing".to_string())?;
-
-    let rpc = RpcClient::new_with_commitment(rpc_url.to_string(), commitment);
-    let recent_blockhash = rpc.get_latest_blockhash().map_err(|e| e.to_string())?;
-    let tx = Transaction::new_signed_with_payer(
-        &[resolve_ix],
-        Some(&authority_keypair.pubkey()),
-        &[authority_keypair],
-        recent_blockhash,
-    );
-
-    let sig = rpc.send_and_confirm_transaction(&tx).map_err(|e| e.to_string())?;
-    Ok(ResolveResult {
-        signature: sig.to_string(),
-    })
+        .flatten()
+        .filter_map(|a| a["name"].as_str().map(String::from))
+        .collect();
+    DecodedInstruction {
+        program_id: ix.program_id.to_string(),
+        name: name.to_string(),
+        accounts: ix
+            .accounts
+            .iter()
+            .enumerate()
+            .map(|(i, meta)| NamedAccount {
+                name: names.get(i).cloned().unwrap_or_else(|| format!("account_{}", i)),
+                pubkey: meta.pubkey.to_string(),
+            })
+            .collect(),
+        args,
+    }
+}
+
+/// Names the last `count` accounts of `decoded`: co-signers are `remaining_accounts`, unnamed in
+/// the IDL.
+fn name_cosigners(decoded: &mut DecodedInstruction, count: usize) {
+    let named = decoded.accounts.len() - count;
+    for (i, account) in decoded.accounts.iter_mut().skip(named).enumerate() {
+        account.name = format!("cosigner_{}", i);
+    }
 }
 
-/// Shared Solana client and config for the app. Resolve is only available if keypair is configured.
+/// Shared Solana client and config for the app. Resolve is only available if a signer is configured.
 #[derive(Clone)]
 pub struct SolanaAppClient {
+    pub cluster: ClusterProfile,
     pub rpc_url: String,
+    /// Endpoints the transaction sender fails over to, in order.
+    pub fallback_rpc_urls: Vec<String>,
     pub program_id: Pubkey,
-    pub resolve_authority: Option<Arc<Keypair>>,
+    pub treasury: Pubkey,
+    pub fee_policy: FeePolicy,
+    pub resolve_signer: Option<Arc<dyn ResolveSigner>>,
+    /// Signers of the other resolve authorities, for games that need several signatures.
+    pub cosigners: Vec<Arc<dyn ResolveSigner>>,
+    /// Pays the fees of API transactions; None means the resolve authority pays.
+    pub fee_payer: Option<Arc<Keypair>>,
+    /// Seconds a verdict stays valid; 0 means the API resolves every game itself.
+    pub verdict_ttl_secs: u64,
 }
 
 impl SolanaAppClient {
     pub fn from_config(config: &Config) -> Self {
-        let resolve_authority = load_keypair(&config.resolve_authority_keypair_path)
-            .ok()
-            .map(Arc::new);
-
-        if resolve_authority.is_none() {
-            log::warn!(
-                "RESOLVE_AUTHORITY_KEYPAIR_PATH set but keypair failed to load; on-chain resolve disabled"
-            );
-        }
+        let resolve_signer = match signer::from_config(&config.resolve_signer) {
+            Ok(s) => {
+                log::info!("Resolve authority {} loaded", s.pubkey());
+                Some(Arc::from(s))
+            }
+            Err(e) => {
+                log::warn!("Resolve signer failed to load ({}); on-chain resolve disabled", e);
+                None
+            }
+        };
+        let cosigners = config
+            .resolve_cosigner_urls
+            .iter()
+            .filter_map(|url| match signer::RemoteSigner::connect(url) {
+                Ok(s) => {
+                    log::info!("Resolve co-signer {} at {}", s.pubkey(), url);
+                    Some(Arc::new(s) as Arc<dyn ResolveSigner>)
+                }
+                Err(e) => {
+                    log::warn!("Resolve co-signer {} unavailable ({}); skipped", url, e);
+                    None
+                }
+            })
+            .collect();
+        let fee_payer = config.fee_payer_keypair_path.as_ref().map(|path| {
+            let keypair = load_keypair(path)
+                .unwrap_or_else(|e| panic!("FEE_PAYER_KEYPAIR_PATH {}: {}", path.display(), e));
+            log::info!("Fee payer {} loaded", keypair.pubkey());
+            Arc::new(keypair)
+        });
 
         Self {
+            cluster: config.solana_cluster,
             rpc_url: config.solana_rpc_url.clone(),
+            fallback_rpc_urls: config.solana_rpc_fallback_urls.clone(),
             program_id: config.rps_escrow_program_id,
-            resolve_authority,
+            treasury: config.treasury_pubkey,
+            fee_policy: FeePolicy::from_config(config),
+            resolve_signer,
+            cosigners,
+            fee_payer,
+            verdict_ttl_secs: config.verdict_ttl_secs,
         }
     }
 
+    fn rpc(&self) -> RpcClient {
+        RpcClient::new_with_commitment(self.rpc_url.clone(), CommitmentConfig::confirmed())
+    }
+
+    /// Sender over the primary RPC endpoint and the fallbacks.
+    fn sender(&self) -> TransactionSender {
+        let mut urls = vec![self.rpc_url.clone()];
+        urls.extend(self.fallback_rpc_urls.iter().cloned());
+        TransactionSender::new(&urls, CommitmentConfig::confirmed())
+    }
+
+    /// Resolve authority's public key, if a signer is configured.
+    pub fn resolve_authority(&self) -> Option<Pubkey> {
+        self.resolve_signer.as_ref().map(|s| s.pubkey())
+    }
+
+    /// Account paying the fees of API transactions: the fee payer, else the resolve authority.
+    pub fn fee_payer_pubkey(&self) -> Option<Pubkey> {
+        self.fee_payer.as_ref().map(|k| k.pubkey()).or_else(|| self.resolve_authority())
+    }
+
     /// Returns true if the API can call resolve on the program.
     pub fn can_resolve(&self) -> bool {
-        self.resolve_authority.is_some()
+        self.resolve_signer.is_some()
     }
 
-    /// Resolve the game on-chain. Fails if keypair not configured or RPC/transaction fails.
+    /// Startup check: the RPC serves the configured cluster, the configured program id is an
+    /// executable program, and its published IDL matches the bundled one. A program without a
+    /// published IDL only passes with `allow_missing_idl`.
+    pub fn verify_program(&self, allow_missing_idl: bool) -> Result<(), String> {
+        let rpc = self.rpc();
+
+        if let Some(expected) = self.cluster.genesis_hash() {
+            let genesis = rpc.get_genesis_hash().map_err(|e| e.to_string())?;
+            if genesis.to_string() != expected {
+                return Err(format!(
+                    "SOLANA_RPC_URL is not on {} (genesis hash {})",
+                    self.cluster.as_str(),
+                    genesis
+                ));
+            }
+        }
+
+        let program = rpc
+            .get_account(&self.program_id)
+            .map_err(|e| format!("program {} not found: {}", self.program_id, e))?;
+        if !program.executable {
+            return Err(format!("account {} is not an executable program", self.program_id));
+        }
+
+        let idl_address = idl_address(&self.program_id);
+        let idl_account = rpc
+            .get_account_with_commitment(&idl_address, rpc.commitment())
+            .map_err(|e| e.to_string())?
+            .value;
+        let Some(idl_account) = idl_account else {
+            if allow_missing_idl {
+                log::warn!(
+                    "No IDL published for program {} (expected at {}); skipping IDL check (ALLOW_MISSING_IDL)",
+                    self.program_id, idl_address
+                );
+                return Ok(());
+            }
+            return Err(format!(
+                "no IDL published for program {} (expected at {}); publish it with `anchor idl init` \
+                 or set ALLOW_MISSING_IDL=true",
+                self.program_id, idl_address
+            ));
+        };
+        let onchain = IdlSummary::from_json(&decode_idl_account(&idl_account.data)?);
+        let bundled_json: serde_json::Value =
+            serde_json::from_str(BUNDLED_IDL).map_err(|e| format!("bundled IDL invalid: {}", e))?;
+        let differences = IdlSummary::from_json(&bundled_json).diff(&onchain);
+        if !differences.is_empty() {
+            return Err(format!(
+                "program {} does not match the bundled IDL: {}",
+                self.program_id,
+                differences.join("; ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Lamports of each account, in order (0 for accounts that do not exist).
+    pub fn get_balances(&self, pubkeys: &[Pubkey]) -> Result<Vec<u64>, String> {
+        let accounts = self.rpc().get_multiple_accounts(pubkeys).map_err(|e| e.to_string())?;
+        Ok(accounts.iter().map(|a| a.as_ref().map_or(0, |a| a.lamports)).collect())
+    }
+
+    /// Fetches and decodes a `GameEscrow` owned by the configured program. None if the account
+    /// does not exist (never created, or closed).
+    pub fn fetch_game_escrow(&self, address: &Pubkey) -> Result<Option<GameEscrowAccount>, String> {
+        Ok(self.fetch_game_escrow_with_opponent(address)?.map(|(escrow, _)| escrow))
+    }
+
+    /// [`Self::fetch_game_escrow`] with the challenged wallet, stored after the escrow's fields.
+    pub fn fetch_game_escrow_with_opponent(
+        &self,
+        address: &Pubkey,
+    ) -> Result<Option<(GameEscrowAccount, Option<Pubkey>)>, String> {
+        let rpc = self.rpc();
+        let account = rpc
+            .get_account_with_commitment(address, rpc.commitment())
+            .map_err(|e| e.to_string())?
+            .value;
+        let Some(account) = account else {
+            return Ok(None);
+        };
+        if account.owner != self.program_id {
+            return Err(format!("account {} is not owned by program {}", address, self.program_id));
+        }
+        let escrow = state::decode_game_escrow(&account.data).map_err(|e| e.to_string())?;
+        let opponent = state::decode_game_escrow_opponent(&account.data).map_err(|e| e.to_string())?;
+        Ok(Some((escrow, opponent)))
+    }
+
+    /// Fetches and decodes a `GameResult` receipt owned by the configured program. None until the
+    /// game's escrow is resolved or refunded.
+    pub fn fetch_game_result(&self, address: &Pubkey) -> Result<Option<GameResultAccount>, String> {
+        let rpc = self.rpc();
+        let account = rpc
+            .get_account_with_commitment(address, rpc.commitment())
+            .map_err(|e| e.to_string())?
+            .value;
+        let Some(account) = account else {
+            return Ok(None);
+        };
+        if account.owner != self.program_id {
+            return Err(format!("account {} is not owned by program {}", address, self.program_id));
+        }
+        state::decode_game_result(&account.data)
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Whether the escrow is paused; false while the pause flag was never set. Blocking.
+    pub fn fetch_paused(&self) -> Result<bool, String> {
+        let rpc = self.rpc();
+        let address = pda::pause_pda(&self.program_id).0;
+        let account = rpc
+            .get_account_with_commitment(&address, rpc.commitment())
+            .map_err(|e| e.to_string())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => state::decode_pause_state(&account.data)
+                .map(|p| p.paused)
+                .map_err(|e| e.to_string()),
+            _ => Ok(false),
+        }
+    }
+
+    /// For each of `games`, whether resolving it needs co-signers: the resolve config exists and the
+    /// game's stake reaches its `multisig_min_amount`. Also returns the config. Blocking.
+    pub fn multisig_games(&self, games: &[BatchGame]) -> Result<(Option<ResolveConfig>, Vec<bool>), String> {
+        let mut addresses = vec![pda::resolve_config_pda(&self.program_id).0];
+        addresses.extend(
+            games
+                .iter()
+                .map(|g| pda::game_escrow_pda(&self.program_id, &g.creator, &g.game_id).0),
+        );
+        let accounts = self.rpc().get_multiple_accounts(&addresses).map_err(|e| e.to_string())?;
+        let config = match &accounts[0] {
+            Some(account) if account.owner == self.program_id => {
+                Some(state::decode_resolve_config(&account.data).map_err(|e| e.to_string())?)
+            }
+            _ => None,
+        };
+        let multisig = accounts[1..]
+            .iter()
+            .map(|account| {
+                let escrow = account
+                    .as_ref()
+                    .filter(|a| a.owner == self.program_id)
+                    .and_then(|a| state::decode_game_escrow(&a.data).ok());
+                matches!((&config, escrow), (Some(c), Some(e)) if c.requires_multisig(e.amount_per_player))
+            })
+            .collect();
+        Ok((config, multisig))
+    }
+
+    /// Verdict that `game.winner` won, valid for `verdict_ttl_secs` and signed by the resolve
+    /// authority, with the signer. None when verdicts are off, no resolve signer is configured or
+    /// the game needs co-signers: the API resolves those itself. Blocking.
+    pub fn sign_verdict(&self, game: &BatchGame) -> Result<Option<(VerdictTerms, Pubkey, Signature)>, TxError> {
+        let Some(signer) = self.resolve_signer.as_deref().filter(|_| self.verdict_ttl_secs > 0) else {
+            return Ok(None);
+        };
+        let (_, multisig) = self
+            .multisig_games(std::slice::from_ref(game))
+            .map_err(|e| TxError::other("RpcError", e, false))?;
+        if multisig.first() != Some(&false) {
+            return Ok(None);
+        }
+        let terms = VerdictTerms {
+            game_escrow: pda::game_escrow_pda(&self.program_id, &game.creator, &game.game_id).0,
+            game_id: game.game_id,
+            winner: game.winner,
+            expiry: chrono::Utc::now().timestamp() + self.verdict_ttl_secs as i64,
+        };
+        let signature = signer::sign_verdict(signer, &terms)?;
+        Ok(Some((terms, signer.pubkey(), signature)))
+    }
+
+    /// Co-signers completing `authority`'s signature to `config`'s threshold.
+    fn cosigners_for(
+        &self,
+        config: &ResolveConfig,
+        authority: &Pubkey,
+    ) -> Result<Vec<&dyn ResolveSigner>, TxError> {
+        let in_set = |pubkey: &Pubkey| config.authorities.contains(pubkey);
+        let needed = usize::from(config.threshold).saturating_sub(usize::from(in_set(authority)));
+        let cosigners: Vec<&dyn ResolveSigner> = self
+            .cosigners
+            .iter()
+            .map(|s| s.as_ref())
+            .filter(|s| in_set(&s.pubkey()) && s.pubkey() != *authority)
+            .take(needed)
+            .collect();
+        if cosigners.len() < needed {
+            return Err(TxError::other(
+                "NotEnoughCosigners",
+                format!(
+                    "the game needs {} of the resolve authorities, {} co-signer(s) configured in the set",
+                    config.threshold,
+                    cosigners.len()
+                ),
+                true,
+            ));
+        }
+        Ok(cosigners)
+    }
+
+    /// `create_game` instruction, or `create_challenge` when `opponent` is set.
+    pub fn create_game_instruction(
+        &self,
+        creator: Pubkey,
+        game_id: [u8; 16],
+        amount: u64,
+        opponent: Option<Pubkey>,
+    ) -> Instruction {
+        match opponent {
+            Some(opponent) => {
+                instructions::create_challenge(&self.program_id, &creator, game_id, amount, &opponent)
+            }
+            None => instructions::create_game(&self.program_id, &creator, game_id, amount),
+        }
+    }
+
+    /// `join_game` instruction.
+    pub fn join_game_instruction(&self, joiner: Pubkey, creator: Pubkey, game_id: [u8; 16]) -> Instruction {
+        instructions::join_game(&self.program_id, &joiner, &creator, game_id)
+    }
+
+    /// `cancel` instruction.
+    pub fn cancel_instruction(&self, creator: Pubkey, game_id: [u8; 16]) -> Instruction {
+        instructions::cancel(&self.program_id, &creator, game_id)
+    }
+
+    /// Unsigned transaction for a player instruction, paid by `payer`: fresh blockhash, priority
+    /// fee on the escrow and vault, simulated compute budget. Returns it with the blockhash's last
+    /// valid block height. Blocking.
+    pub fn build_player_transaction(
+        &self,
+        ix: &Instruction,
+        payer: &Pubkey,
+    ) -> Result<(Transaction, u64, PriorityFee), TxError> {
+        let writable: Vec<Pubkey> = ix.accounts.iter().skip(1).take(2).map(|m| m.pubkey).collect();
+        self.build_unsigned_transaction(std::slice::from_ref(ix), &writable, payer)
+    }
+
+    /// Unsigned claim of a verdict, paid by `payer`: the Ed25519 program instruction verifying it,
+    /// then `resolve_with_attestation` (`ixs`, see [`crate::verdict::Verdict::claim_instructions`]).
+    /// Built like [`Self::build_player_transaction`]. Blocking.
+    pub fn build_claim_transaction(
+        &self,
+        ixs: &[Instruction; 2],
+        payer: &Pubkey,
+    ) -> Result<(Transaction, u64, PriorityFee), TxError> {
+        let writable: Vec<Pubkey> = ixs[1].accounts.iter().take(2).map(|m| m.pubkey).collect();
+        self.build_unsigned_transaction(ixs, &writable, payer)
+    }
+
+    fn build_unsigned_transaction(
+        &self,
+        ixs: &[Instruction],
+        writable: &[Pubkey],
+        payer: &Pubkey,
+    ) -> Result<(Transaction, u64, PriorityFee), TxError> {
+        let mut sender = self.sender();
+        let (blockhash, last_valid_block_height) = sender.with_failover("getLatestBlockhash", |rpc| {
+            rpc.get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+        })?;
+        let (tx, fee) =
+            build_budgeted_transaction(sender.rpc(), &self.fee_policy, ixs, writable, payer, blockhash)?;
+        Ok((tx, last_valid_block_height, fee))
+    }
+
+    /// Forwards a transaction signed by a player (preflight on). Blocking.
+    pub fn submit_player_transaction(&self, tx: &Transaction) -> Result<String, TxError> {
+        self.sender()
+            .with_failover("sendTransaction", |rpc| rpc.send_transaction(tx))
+            .map(|sig| sig.to_string())
+    }
+
+    /// Calls the rps-escrow **resolve** instruction, with co-signers when the game needs them.
+    /// `game_id` must be the 16-byte UUID (no hyphens).
+    /// The transaction is simulated first; failures are decoded into a [`TxError`]. Sent with
+    /// [`TransactionSender`]; each signed transaction is appended to `attempts`. Blocking.
     pub fn resolve(
         &self,
         game_id: [u8; 16],
-        creator_pubkey: &str,
-        winner_pubkey: &str,
-    ) -> Result<ResolveResult, String> {
-        let authority = self
-            .resolve_authority
-            .as_ref()
-            .ok_or_else(|| "resolve authority keypair not configured".to_string())?;
-        resolve_game(
-            &self.rpc_url,
-            authority.as_ref(),
+        creator: &Pubkey,
+        winner: &Pubkey,
+        attempts: &mut Vec<SendAttempt>,
+    ) -> Result<ResolveResult, TxError> {
+        let signer = self.signer()?;
+        let game = BatchGame {
+            creator: *creator,
             game_id,
-            creator_pubkey,
-            winner_pubkey,
-        )
+            winner: *winner,
+        };
+        let (config, multisig) = self
+            .multisig_games(&[game])
+            .map_err(|e| TxError::other("RpcError", e, false))?;
+        let cosigners = match config {
+            Some(config) if multisig[0] => self.cosigners_for(&config, &signer.pubkey())?,
+            _ => Vec::new(),
+        };
+        let resolve_ix = instructions::resolve(
+            &self.program_id,
+            &signer.pubkey(),
+            creator,
+            game_id,
+            winner,
+            &self.treasury,
+        );
+        let cosigner_keys: Vec<Pubkey> = cosigners.iter().map(|s| s.pubkey()).collect();
+        let resolve_ix = instructions::with_cosigners(resolve_ix, &cosigner_keys);
+        let mut decoded = describe_instruction(
+            "resolve",
+            &resolve_ix,
+            serde_json::json!({ "winner": winner.to_string() }),
+        );
+        name_cosigners(&mut decoded, cosigner_keys.len());
+        let (game_escrow, vault) = pda::game_addresses(&self.program_id, creator, &game_id);
+        self.send_resolve(signer, &cosigners, resolve_ix, decoded, &[game_escrow, vault], attempts)
+    }
+
+    /// Calls the rps-escrow **refund** instruction, returning both deposits to `creator` and
+    /// `joiner`, with co-signers when the game needs them. Sent like [`Self::resolve`], the fee
+    /// payer paying the fees. Blocking.
+    pub fn refund(
+        &self,
+        game_id: [u8; 16],
+        creator: &Pubkey,
+        joiner: &Pubkey,
+        attempts: &mut Vec<SendAttempt>,
+    ) -> Result<ResolveResult, TxError> {
+        let signer = self.signer()?;
+        // `multisig_games` only reads the escrow; a refund has no winner.
+        let game = BatchGame {
+            creator: *creator,
+            game_id,
+            winner: Pubkey::default(),
+        };
+        let (config, multisig) = self
+            .multisig_games(&[game])
+            .map_err(|e| TxError::other("RpcError", e, false))?;
+        let cosigners = match config {
+            Some(config) if multisig[0] => self.cosigners_for(&config, &signer.pubkey())?,
+            _ => Vec::new(),
+        };
+        let refund_ix = instructions::refund(&self.program_id, &signer.pubkey(), creator, joiner, game_id);
+        let cosigner_keys: Vec<Pubkey> = cosigners.iter().map(|s| s.pubkey()).collect();
+        let refund_ix = instructions::with_cosigners(refund_ix, &cosigner_keys);
+        let mut decoded = describe_instruction("refund", &refund_ix, serde_json::json!({}));
+        name_cosigners(&mut decoded, cosigner_keys.len());
+        let (game_escrow, vault) = pda::game_addresses(&self.program_id, creator, &game_id);
+        self.send_resolve(signer, &cosigners, refund_ix, decoded, &[game_escrow, vault], attempts)
+    }
+
+    /// Calls the rps-escrow **resolve_batch** instruction for `games`, like [`Self::resolve`]:
+    /// either every game is resolved by one transaction, or none is. A batch has no co-signers, so
+    /// it fails on games that need them. Blocking.
+    pub fn resolve_batch(
+        &self,
+        games: &[BatchGame],
+        attempts: &mut Vec<SendAttempt>,
+    ) -> Result<ResolveResult, TxError> {
+        let signer = self.signer()?;
+        let batch_ix = instructions::resolve_batch(&self.program_id, &signer.pubkey(), &self.treasury, games);
+        let winners: Vec<String> = games.iter().map(|g| g.winner.to_string()).collect();
+        let args = serde_json::json!({ "winners": winners });
+        let mut decoded = describe_instruction("resolve_batch", &batch_ix, args);
+        // The game groups are `remaining_accounts`, unnamed in the IDL.
+        let group_names = ["game_escrow", "vault", "winner_destination", "creator", "game_result"];
+        let groups = decoded.accounts.iter_mut().skip(instructions::RESOLVE_BATCH_FIXED_ACCOUNTS);
+        for (i, account) in groups.enumerate() {
+            account.name = format!("{}_{}", group_names[i % group_names.len()], i / group_names.len());
+        }
+        let writable: Vec<Pubkey> = games
+            .iter()
+            .flat_map(|g| {
+                let (game_escrow, vault) = pda::game_addresses(&self.program_id, &g.creator, &g.game_id);
+                [game_escrow, vault]
+            })
+            .collect();
+        self.send_resolve(signer, &[], batch_ix, decoded, &writable, attempts)
+    }
+
+    /// True if a `resolve_batch` of `games` fits in one transaction.
+    pub fn resolve_batch_fits(&self, games: &[BatchGame]) -> bool {
+        let Ok(signer) = self.signer() else {
+            return false;
+        };
+        let batch_ix = instructions::resolve_batch(&self.program_id, &signer.pubkey(), &self.treasury, games);
+        budgeted_transaction_size(&[batch_ix], &self.fee_payer_for(signer)) <= PACKET_DATA_SIZE
+    }
+
+    fn signer(&self) -> Result<&dyn ResolveSigner, TxError> {
+        self.resolve_signer.as_deref().ok_or_else(|| {
+            TxError::other("NotConfigured", "resolve signer not configured", true)
+        })
+    }
+
+    fn fee_payer_for(&self, signer: &dyn ResolveSigner) -> Pubkey {
+        self.fee_payer.as_ref().map_or_else(|| signer.pubkey(), |k| k.pubkey())
+    }
+
+    /// Budgets, signs and sends a transaction made of the authority instruction `ix`, co-signed by
+    /// `cosigners`; the priority fee is estimated on `writable`.
+    fn send_resolve(
+        &self,
+        signer: &dyn ResolveSigner,
+        cosigners: &[&dyn ResolveSigner],
+        ix: Instruction,
+        decoded: DecodedInstruction,
+        writable: &[Pubkey],
+        attempts: &mut Vec<SendAttempt>,
+    ) -> Result<ResolveResult, TxError> {
+        let fee_payer = self.fee_payer_for(signer);
+        let decoded = [decoded];
+        let mut priority_fee = None;
+        let sig = self.sender().send(attempts, |rpc, recent_blockhash| {
+            let (mut tx, fee) = build_budgeted_transaction(
+                rpc,
+                &self.fee_policy,
+                std::slice::from_ref(&ix),
+                writable,
+                &fee_payer,
+                recent_blockhash,
+            )?;
+            signer::sign_transaction(signer, cosigners, self.fee_payer.as_deref(), &mut tx, &decoded)?;
+            priority_fee = Some(fee);
+            Ok(tx)
+        })?;
+        let priority_fee = priority_fee.expect("set when the transaction was signed");
+        Ok(ResolveResult {
+            signature: sig.to_string(),
+            priority_fee,
+        })
     }
 }
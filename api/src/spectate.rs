@@ -0,0 +1,286 @@
+//! Read-only spectator access to live games.
+//!
+//! Spectators get a [`SpectatorView`]: players, stake, completed rounds and who has chosen, never
+//! the choice itself until the round is settled. `GET /games/:game_id/spectate/stream` is a
+//! server-sent event stream; each open stream counts in the game's `spectator_count`. The creator
+//! can turn spectating off with `POST /games/:game_id/spectating`, which also hides the game from
+//! non-players in `GET /games/:game_id`.
+//!
+//! **Collections:**
+//! - **spectators**: { _id: stream id, game_id, seen_at } — one per open stream, whichever replica
+//!   serves it
+//!
+//! A stream refreshes its `seen_at` every [`PRESENCE_REFRESH_INTERVAL`] and deletes its document
+//! when closed. Only documents seen within [`PRESENCE_TTL`] count, and a TTL index removes the
+//! rest, so a replica that stops without closing its streams leaves no count behind.
+
+use std::convert::Infallible;
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    response::sse::{Event, KeepAlive, Sse},
+    routing::{get, post},
+    Json, Router,
+};
+use futures::stream::{self, Stream};
+use mongodb::{
+    bson::{doc, DateTime},
+    options::IndexOptions,
+    Database, IndexModel,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::games::{AppState, Game, GameIdPath, GameStatus, Round};
+
+/// How often a spectator stream re-reads the game.
+const STREAM_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often an open stream refreshes its presence.
+pub const PRESENCE_REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Age after which a presence no longer counts (and is removed by MongoDB).
+pub const PRESENCE_TTL: Duration = Duration::from_secs(45);
+
+/// One open spectator stream (collection `spectators`).
+#[derive(Serialize, Deserialize)]
+struct Presence {
+    #[serde(rename = "_id")]
+    id: String,
+    game_id: String,
+    seen_at: DateTime,
+}
+
+/// What a spectator sees of a game.
+#[derive(Serialize, PartialEq)]
+pub struct SpectatorView {
+    pub game_id: String,
+    pub status: GameStatus,
+    pub creator_pubkey: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub joiner_pubkey: Option<String>,
+    pub amount_per_player: i64,
+    pub round_count: usize,
+    /// Completed rounds; the round in progress is never included.
+    pub rounds: Vec<Round>,
+    pub creator_has_chosen: bool,
+    pub joiner_has_chosen: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub winner_pubkey: Option<String>,
+    pub spectator_count: i64,
+}
+
+impl SpectatorView {
+    fn new(game: &Game, spectator_count: i64) -> Self {
+        Self {
+            game_id: game.id.clone(),
+            status: game.status,
+            creator_pubkey: game.creator_pubkey.clone(),
+            joiner_pubkey: game.joiner_pubkey.clone(),
+            amount_per_player: game.amount_per_player,
+            round_count: game.rounds.len(),
+            rounds: game.rounds.clone(),
+            creator_has_chosen: game.round_pending() && game.creator_choice.is_some(),
+            joiner_has_chosen: game.round_pending() && game.joiner_choice.is_some(),
+            winner_pubkey: game.winner_pubkey.clone(),
+            spectator_count,
+        }
+    }
+}
+
+/// Request body for `POST /games/:game_id/spectating`. Creator identity from SIWS auth.
+#[derive(Deserialize)]
+pub struct SpectatingRequest {
+    pub enabled: bool,
+}
+
+/// Loads a game that allows spectators.
+async fn find_spectatable(db: &Database, game_id: &str) -> Result<Game, ApiError> {
+    let game = db
+        .collection::<Game>("games")
+        .find_one(doc! { "_id": game_id }, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to find game: {}", e);
+            ApiError::internal(e.to_string())
+        })?
+        .ok_or_else(|| ApiError::not_found("Game not found"))?;
+    if game.spectatable == Some(false) {
+        return Err(ApiError::forbidden("Spectating is disabled for this game"));
+    }
+    Ok(game)
+}
+
+/// Indexes of `spectators`: counting by game, and the TTL that drops presences no replica
+/// refreshes anymore.
+pub async fn ensure_spectator_indexes(db: &Database) -> Result<(), mongodb::error::Error> {
+    let presences = db.collection::<Presence>("spectators");
+    presences
+        .create_index(IndexModel::builder().keys(doc! { "game_id": 1, "seen_at": 1 }).build(), None)
+        .await?;
+    let ttl = IndexOptions::builder().expire_after(PRESENCE_TTL).build();
+    presences
+        .create_index(IndexModel::builder().keys(doc! { "seen_at": 1 }).options(ttl).build(), None)
+        .await?;
+    log::info!("Spectator indexes ensured");
+    Ok(())
+}
+
+/// Open streams on `game_id` seen within [`PRESENCE_TTL`], across replicas.
+async fn spectator_count(db: &Database, game_id: &str) -> Result<i64, ApiError> {
+    let cutoff = DateTime::from_millis(DateTime::now().timestamp_millis() - PRESENCE_TTL.as_millis() as i64);
+    let count = db
+        .collection::<Presence>("spectators")
+        .count_documents(doc! { "game_id": game_id, "seen_at": { "$gte": cutoff } }, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to count spectators: {}", e);
+            ApiError::internal(e.to_string())
+        })?;
+    Ok(count as i64)
+}
+
+/// Records or refreshes the presence of stream `id` on `game_id`.
+async fn touch_presence(db: &Database, id: &str, game_id: &str) {
+    if let Err(e) = db
+        .collection::<Presence>("spectators")
+        .update_one(
+            doc! { "_id": id },
+            doc! { "$set": { "game_id": game_id, "seen_at": DateTime::now() } },
+            mongodb::options::UpdateOptions::builder().upsert(true).build(),
+        )
+        .await
+    {
+        log::error!("Failed to record spectator game_id={}: {}", game_id, e);
+    }
+}
+
+/// One open stream's presence; deleted when the stream is dropped (client disconnect).
+struct SpectatorGuard {
+    db: Database,
+    id: String,
+    game_id: String,
+}
+
+impl Drop for SpectatorGuard {
+    fn drop(&mut self) {
+        let db = self.db.clone();
+        let id = std::mem::take(&mut self.id);
+        tokio::spawn(async move {
+            let presences = db.collection::<Presence>("spectators");
+            if let Err(e) = presences.delete_one(doc! { "_id": &id }, None).await {
+                log::error!("Failed to remove spectator {}: {}", id, e);
+            }
+        });
+    }
+}
+
+fn is_over(status: GameStatus) -> bool {
+    matches!(status, GameStatus::Finished | GameStatus::Cancelled | GameStatus::Refunded)
+}
+
+async fn spectate(
+    State(state): State<AppState>,
+    Path(path): Path<GameIdPath>,
+) -> Result<Json<SpectatorView>, ApiError> {
+    let game = find_spectatable(&state.db, &path.game_id).await?;
+    let count = spectator_count(&state.db, &game.id).await?;
+    Ok(Json(SpectatorView::new(&game, count)))
+}
+
+async fn spectate_stream(
+    State(state): State<AppState>,
+    Path(path): Path<GameIdPath>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    find_spectatable(&state.db, &path.game_id).await?;
+    let guard = SpectatorGuard {
+        db: state.db.clone(),
+        id: uuid::Uuid::new_v4().to_string(),
+        game_id: path.game_id.clone(),
+    };
+    touch_presence(&guard.db, &guard.id, &guard.game_id).await;
+    let touched = tokio::time::Instant::now();
+
+    // State: (guard, last presence refresh, last view sent, stream finished). Emits only when
+    // the view changes and ends after sending a finished or cancelled game.
+    let events = stream::unfold(
+        (guard, touched, None::<SpectatorView>, false),
+        |(guard, mut touched, last, done)| async move {
+            if done {
+                return None;
+            }
+            loop {
+                if touched.elapsed() >= PRESENCE_REFRESH_INTERVAL {
+                    touch_presence(&guard.db, &guard.id, &guard.game_id).await;
+                    touched = tokio::time::Instant::now();
+                }
+                let view = match find_spectatable(&guard.db, &guard.game_id).await {
+                    Ok(game) => spectator_count(&guard.db, &game.id)
+                        .await
+                        .map(|count| SpectatorView::new(&game, count)),
+                    Err(e) => Err(e),
+                };
+                let view = match view {
+                    Ok(view) => view,
+                    Err(e) => {
+                        let event = Event::default().event("error").data(e.message);
+                        return Some((Ok(event), (guard, touched, last, true)));
+                    }
+                };
+                if last.as_ref() != Some(&view) {
+                    let over = is_over(view.status);
+                    let event = Event::default()
+                        .event("game")
+                        .json_data(&view)
+                        .unwrap_or_else(|_| Event::default().event("error"));
+                    return Some((Ok(event), (guard, touched, Some(view), over)));
+                }
+                tokio::time::sleep(STREAM_POLL_INTERVAL).await;
+            }
+        },
+    );
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+async fn set_spectating(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(path): Path<GameIdPath>,
+    Json(body): Json<SpectatingRequest>,
+) -> Result<Json<Game>, ApiError> {
+    let creator_pubkey = auth.pubkey.trim();
+    let opts = mongodb::options::FindOneAndUpdateOptions::builder()
+        .return_document(mongodb::options::ReturnDocument::After)
+        .build();
+    let updated = state
+        .db
+        .collection::<Game>("games")
+        .find_one_and_update(
+            doc! { "_id": &path.game_id, "creator_pubkey": creator_pubkey },
+            doc! { "$set": { "spectatable": body.enabled } },
+            opts,
+        )
+        .await
+        .map_err(|e| {
+            log::error!("Failed to update spectating: {}", e);
+            ApiError::internal(e.to_string())
+        })?;
+    match updated {
+        Some(mut game) => {
+            log::info!("Spectating {} game_id={}", if body.enabled { "enabled" } else { "disabled" }, game.id);
+            game.redact_pending_choice(Some(creator_pubkey));
+            Ok(Json(game))
+        }
+        None => Err(ApiError::not_found("Game not found or not owned by this creator")),
+    }
+}
+
+pub fn spectate_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/games/:game_id/spectate", get(spectate))
+        .route("/games/:game_id/spectate/stream", get(spectate_stream))
+        .route("/games/:game_id/spectating", post(set_spectating))
+        .with_state(state)
+}
@@ -0,0 +1,360 @@
+//! Server-built player transactions: clients sign what the API builds instead of assembling
+//! rps-escrow instructions themselves.
+//!
+//! `POST /games/:game_id/tx/create|join|cancel` returns an unsigned, base64 (bincode) transaction
+//! for the caller: recent blockhash, priority fee, simulated compute budget and the program's
+//! accounts, with the caller as fee payer and only signer. `create` builds `create_challenge`
+//! for direct challenges. The wallet signs it and `POST /games/:game_id/tx/submit` forwards it
+//! (preflight on) and links its signature to the game (`create_tx`, `join_tx` or `cancel_tx`).
+//! Game status still follows the API calls and the indexer.
+//!
+//! `POST /games/:game_id/tx/claim` builds the claim of the game's verdict (see [`crate::verdict`])
+//! the same way: the Ed25519 program instruction verifying it, then `resolve_with_attestation`.
+//! Anyone may pay for it; submitted, its signature is linked as `resolve_tx`.
+
+use axum::{
+    extract::{Path, State},
+    routing::post,
+    Json, Router,
+};
+use base64::Engine;
+use mongodb::bson::doc;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, transaction::Transaction};
+
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::fees::PriorityFee;
+use crate::pause::PauseMonitor;
+use crate::games::{AppState, Game, GameIdPath, GameStatus};
+use crate::indexer::{decode_instruction, EscrowInstruction};
+use crate::solana::SolanaAppClient;
+
+/// Player instruction a transaction is built for.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayerAction {
+    Create,
+    Join,
+    Cancel,
+    /// Claim of a verdict.
+    Claim,
+}
+
+impl PlayerAction {
+    /// Game field holding the signature.
+    fn tx_field(self) -> &'static str {
+        match self {
+            PlayerAction::Create => "create_tx",
+            PlayerAction::Join => "join_tx",
+            PlayerAction::Cancel => "cancel_tx",
+            PlayerAction::Claim => "resolve_tx",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct UnsignedTransactionResponse {
+    pub action: PlayerAction,
+    /// Base64 bincode transaction with empty signatures.
+    pub transaction: String,
+    pub recent_blockhash: String,
+    pub last_valid_block_height: u64,
+    pub priority_fee: PriorityFee,
+}
+
+#[derive(Deserialize)]
+pub struct SubmitTransactionRequest {
+    /// Base64 bincode transaction, signed.
+    pub transaction: String,
+}
+
+#[derive(Serialize)]
+pub struct SubmitTransactionResponse {
+    pub action: PlayerAction,
+    pub signature: String,
+}
+
+fn solana_client(state: &AppState) -> Result<SolanaAppClient, ApiError> {
+    state
+        .solana
+        .clone()
+        .ok_or_else(|| ApiError::internal("Solana client not configured"))
+}
+
+async fn find_game(state: &AppState, game_id: &str) -> Result<Game, ApiError> {
+    state
+        .db
+        .collection::<Game>("games")
+        .find_one(doc! { "_id": game_id }, None)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to find game: {}", e);
+            ApiError::internal(e.to_string())
+        })?
+        .ok_or_else(|| ApiError::not_found("Game not found"))
+}
+
+fn parse_pubkey(pubkey: &str) -> Result<Pubkey, ApiError> {
+    pubkey
+        .trim()
+        .parse()
+        .map_err(|_| ApiError::bad_request("invalid pubkey (not a valid base58 pubkey)"))
+}
+
+/// Checks that `caller` may take `action` on `game` and builds the instruction. Create and join
+/// are refused while the escrow is paused. Claims take two instructions (see [`build_claim`]).
+pub(crate) fn player_instruction(
+    solana: &SolanaAppClient,
+    pause: &PauseMonitor,
+    game: &Game,
+    action: PlayerAction,
+    caller: &str,
+) -> Result<Instruction, ApiError> {
+    let game_id = *uuid::Uuid::parse_str(&game.id)
+        .map_err(|e| ApiError::internal(e.to_string()))?
+        .as_bytes();
+    let creator = parse_pubkey(&game.creator_pubkey)?;
+    let is_creator = game.creator_pubkey == caller;
+    match action {
+        PlayerAction::Create => {
+            if !is_creator {
+                return Err(ApiError::forbidden("Only the creator can create the escrow"));
+            }
+            pause.ensure_open()?;
+            if game.status != GameStatus::Waiting || game.create_tx.is_some() {
+                return Err(ApiError::bad_request("Escrow already created or game not waiting"));
+            }
+            let opponent = game
+                .challenge
+                .as_ref()
+                .map(|c| parse_pubkey(&c.opponent_pubkey))
+                .transpose()?;
+            Ok(solana.create_game_instruction(creator, game_id, game.amount_per_player as u64, opponent))
+        }
+        PlayerAction::Join => {
+            if is_creator {
+                return Err(ApiError::bad_request("Creator cannot join their own game"));
+            }
+            if let Some(challenge) = &game.challenge {
+                if challenge.opponent_pubkey != caller {
+                    return Err(ApiError::forbidden("Only the challenged player can join"));
+                }
+            }
+            let joinable = game.status == GameStatus::Waiting
+                && game.joiner_pubkey.as_deref().is_none_or(|j| j == caller);
+            if !joinable {
+                return Err(ApiError::bad_request("Game is not waiting for a joiner"));
+            }
+            pause.ensure_open()?;
+            Ok(solana.join_game_instruction(parse_pubkey(caller)?, creator, game_id))
+        }
+        PlayerAction::Cancel => {
+            if !is_creator {
+                return Err(ApiError::forbidden("Only the creator can cancel"));
+            }
+            let cancellable = matches!(game.status, GameStatus::Waiting | GameStatus::Cancelled)
+                && game.joiner_pubkey.is_none()
+                && game.cancel_tx.is_none();
+            if !cancellable {
+                return Err(ApiError::bad_request("Game already joined or cancelled on-chain"));
+            }
+            Ok(solana.cancel_instruction(creator, game_id))
+        }
+        PlayerAction::Claim => Err(ApiError::bad_request("Claims are built by POST /games/:game_id/tx/claim")),
+    }
+}
+
+async fn build_transaction(
+    state: &AppState,
+    auth: AuthUser,
+    game_id: &str,
+    action: PlayerAction,
+) -> Result<Json<UnsignedTransactionResponse>, ApiError> {
+    let solana = solana_client(state)?;
+    let caller = auth.pubkey.trim().to_string();
+    let game = find_game(state, game_id).await?;
+    let ix = player_instruction(&solana, &state.pause, &game, action, &caller)?;
+    let payer = parse_pubkey(&caller)?;
+
+    let (tx, last_valid_block_height, priority_fee) =
+        tokio::task::spawn_blocking(move || solana.build_player_transaction(&ix, &payer))
+            .await
+            .map_err(|e| ApiError::internal(e.to_string()))?
+            .map_err(|e| {
+                log::warn!("Building {:?} transaction failed game_id={}: {}", action, game_id, e);
+                ApiError::bad_request(e.to_string())
+            })?;
+    unsigned_response(action, &tx, last_valid_block_height, priority_fee)
+}
+
+fn unsigned_response(
+    action: PlayerAction,
+    tx: &Transaction,
+    last_valid_block_height: u64,
+    priority_fee: PriorityFee,
+) -> Result<Json<UnsignedTransactionResponse>, ApiError> {
+    let bytes = bincode::serialize(tx).map_err(|e| ApiError::internal(e.to_string()))?;
+    Ok(Json(UnsignedTransactionResponse {
+        action,
+        transaction: base64::engine::general_purpose::STANDARD.encode(bytes),
+        recent_blockhash: tx.message.recent_blockhash.to_string(),
+        last_valid_block_height,
+        priority_fee,
+    }))
+}
+
+async fn build_create(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(path): Path<GameIdPath>,
+) -> Result<Json<UnsignedTransactionResponse>, ApiError> {
+    build_transaction(&state, auth, &path.game_id, PlayerAction::Create).await
+}
+
+async fn build_join(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(path): Path<GameIdPath>,
+) -> Result<Json<UnsignedTransactionResponse>, ApiError> {
+    build_transaction(&state, auth, &path.game_id, PlayerAction::Join).await
+}
+
+async fn build_cancel(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(path): Path<GameIdPath>,
+) -> Result<Json<UnsignedTransactionResponse>, ApiError> {
+    build_transaction(&state, auth, &path.game_id, PlayerAction::Cancel).await
+}
+
+/// Unsigned claim of the game's verdict, paid by the caller. Refused once the verdict expired: the
+/// API resolves the game then.
+async fn build_claim(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(path): Path<GameIdPath>,
+) -> Result<Json<UnsignedTransactionResponse>, ApiError> {
+    let solana = solana_client(&state)?;
+    let payer = parse_pubkey(&auth.pubkey)?;
+    let game = find_game(&state, &path.game_id).await?;
+    let verdict = match (&game.verdict, game.status) {
+        (Some(verdict), GameStatus::Resolving) => verdict,
+        _ => return Err(ApiError::bad_request("Game has no verdict to claim")),
+    };
+    if verdict.expiry <= chrono::Utc::now().timestamp() {
+        return Err(ApiError::bad_request("Verdict expired; the game is resolved by the API"));
+    }
+    let ixs = verdict
+        .claim_instructions(&solana, &game)
+        .map_err(ApiError::internal)?;
+
+    let (tx, last_valid_block_height, priority_fee) =
+        tokio::task::spawn_blocking(move || solana.build_claim_transaction(&ixs, &payer))
+            .await
+            .map_err(|e| ApiError::internal(e.to_string()))?
+            .map_err(|e| {
+                log::warn!("Building claim transaction failed game_id={}: {}", game.id, e);
+                ApiError::bad_request(e.to_string())
+            })?;
+    unsigned_response(PlayerAction::Claim, &tx, last_valid_block_height, priority_fee)
+}
+
+/// The single rps-escrow instruction of `tx`, which must target `game_escrow` and be signed by
+/// `caller` (as fee payer).
+fn submitted_action(
+    tx: &Transaction,
+    program_id: &Pubkey,
+    game_escrow: &Pubkey,
+    caller: &Pubkey,
+) -> Result<PlayerAction, ApiError> {
+    if tx.message.account_keys.first() != Some(caller) {
+        return Err(ApiError::forbidden("Transaction must be paid by the caller"));
+    }
+    tx.verify()
+        .map_err(|e| ApiError::bad_request(format!("invalid signatures: {}", e)))?;
+
+    let keys = &tx.message.account_keys;
+    let mut escrow_ixs = tx
+        .message
+        .instructions
+        .iter()
+        .filter(|ix| keys.get(usize::from(ix.program_id_index)) == Some(program_id));
+    let (Some(ix), None) = (escrow_ixs.next(), escrow_ixs.next()) else {
+        return Err(ApiError::bad_request("Transaction must hold exactly one rps-escrow instruction"));
+    };
+    let accounts: Vec<Pubkey> = ix
+        .accounts
+        .iter()
+        .filter_map(|i| keys.get(usize::from(*i)).copied())
+        .collect();
+    let (escrow, decoded) = decode_instruction(&ix.data, &accounts)
+        .ok_or_else(|| ApiError::bad_request("Unknown rps-escrow instruction"))?;
+    if escrow != *game_escrow {
+        return Err(ApiError::bad_request("Instruction is for another game's escrow"));
+    }
+    match decoded {
+        EscrowInstruction::Create => Ok(PlayerAction::Create),
+        EscrowInstruction::Join { .. } => Ok(PlayerAction::Join),
+        EscrowInstruction::Cancel => Ok(PlayerAction::Cancel),
+        EscrowInstruction::Resolve { .. } => Ok(PlayerAction::Claim),
+        _ => Err(ApiError::bad_request("Only create, join, cancel and claim can be submitted")),
+    }
+}
+
+async fn submit_transaction(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(path): Path<GameIdPath>,
+    Json(body): Json<SubmitTransactionRequest>,
+) -> Result<Json<SubmitTransactionResponse>, ApiError> {
+    let solana = solana_client(&state)?;
+    let caller = parse_pubkey(&auth.pubkey)?;
+    let game = find_game(&state, &path.game_id).await?;
+    let game_escrow = game
+        .game_escrow_pubkey
+        .as_deref()
+        .ok_or_else(|| ApiError::bad_request("Game has no escrow"))
+        .and_then(parse_pubkey)?;
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(body.transaction.trim())
+        .map_err(|_| ApiError::bad_request("transaction must be base64"))?;
+    let tx: Transaction = bincode::deserialize(&bytes)
+        .map_err(|_| ApiError::bad_request("transaction is not a serialized transaction"))?;
+    let action = submitted_action(&tx, &solana.program_id, &game_escrow, &caller)?;
+
+    let signature = tokio::task::spawn_blocking(move || solana.submit_player_transaction(&tx))
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?
+        .map_err(|e| {
+            log::warn!("Submit {:?} failed game_id={}: {}", action, game.id, e);
+            ApiError::bad_request(e.to_string())
+        })?;
+
+    state
+        .db
+        .collection::<Game>("games")
+        .update_one(
+            doc! { "_id": &game.id },
+            doc! { "$set": { action.tx_field(): &signature } },
+            None,
+        )
+        .await
+        .map_err(|e| {
+            log::error!("Failed to link transaction: {}", e);
+            ApiError::internal(e.to_string())
+        })?;
+    log::info!("Submitted {:?} game_id={} sig={}", action, game.id, signature);
+    Ok(Json(SubmitTransactionResponse { action, signature }))
+}
+
+pub fn transaction_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/games/:game_id/tx/create", post(build_create))
+        .route("/games/:game_id/tx/join", post(build_join))
+        .route("/games/:game_id/tx/cancel", post(build_cancel))
+        .route("/games/:game_id/tx/claim", post(build_claim))
+        .route("/games/:game_id/tx/submit", post(submit_transaction))
+        .with_state(state)
+}
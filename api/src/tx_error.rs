@@ -0,0 +1,155 @@
+//! Structured errors for escrow transactions sent by the API.
+//!
+//! Program errors (`InstructionError::Custom`) are mapped back to the program's `EscrowError`
+//! variant by `rps-escrow-client`; Anchor framework errors (constraints, account checks) are read from the
+//! program logs. Each error says whether it is permanent: resending the same transaction cannot
+//! succeed (program rejected it) versus it may succeed later (expired blockhash, RPC timeout).
+
+use serde::{Deserialize, Deserializer, Serialize};
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
+/// Why an escrow transaction failed. Stored on the game as `resolve_error`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TxError {
+    /// Program error code (e.g. 6001), if the program rejected the transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<i64>,
+    /// Error name: the `EscrowError` or Anchor variant, else the Solana error kind.
+    pub name: String,
+    pub message: String,
+    /// Program logs from simulation, when available.
+    #[serde(default)]
+    pub logs: Vec<String>,
+    /// True if retrying the same transaction cannot succeed.
+    #[serde(default)]
+    pub permanent: bool,
+}
+
+impl std::fmt::Display for TxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "{} ({}): {}", self.name, code, self.message),
+            None => write!(f, "{}: {}", self.name, self.message),
+        }
+    }
+}
+
+/// Name and message of the `EscrowError` with custom error `code`.
+fn escrow_error(code: u32) -> Option<(String, String)> {
+    rps_escrow_client::errors::escrow_error(code).map(|e| (e.name(), e.to_string()))
+}
+
+/// Parses Anchor's `Error Code: X. Error Number: N. Error Message: M.` log line.
+fn anchor_error_from_logs(logs: &[String], code: u32) -> Option<(String, String)> {
+    let number = format!("Error Number: {}.", code);
+    let line = logs.iter().find(|l| l.contains(&number))?;
+    let name = line.split("Error Code: ").nth(1)?.split('.').next()?.trim();
+    let message = line
+        .split("Error Message: ")
+        .nth(1)
+        .map(|m| m.trim().trim_end_matches('.'))
+        .unwrap_or(name);
+    Some((name.to_string(), message.to_string()))
+}
+
+/// Errors where the same transaction may land if sent again later.
+fn is_transient(err: &TransactionError) -> bool {
+    matches!(
+        err,
+        TransactionError::BlockhashNotFound
+            | TransactionError::AccountInUse
+            | TransactionError::ClusterMaintenance
+            | TransactionError::WouldExceedMaxBlockCostLimit
+            | TransactionError::WouldExceedMaxAccountCostLimit
+            | TransactionError::WouldExceedAccountDataBlockLimit
+            | TransactionError::WouldExceedMaxVoteCostLimit
+    )
+}
+
+impl TxError {
+    /// An error raised before anything was sent (configuration, bad input).
+    pub fn other(name: &str, message: impl Into<String>, permanent: bool) -> Self {
+        Self {
+            code: None,
+            name: name.to_string(),
+            message: message.into(),
+            logs: Vec::new(),
+            permanent,
+        }
+    }
+
+    /// Maps a transaction error (from simulation or execution) and its logs.
+    pub fn from_transaction_error(err: &TransactionError, logs: Vec<String>) -> Self {
+        match err {
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+                let (name, message) = escrow_error(*code)
+                    .or_else(|| anchor_error_from_logs(&logs, *code))
+                    .unwrap_or_else(|| ("Custom".to_string(), err.to_string()));
+                Self {
+                    code: Some(i64::from(*code)),
+                    name,
+                    message,
+                    logs,
+                    permanent: true,
+                }
+            }
+            TransactionError::InstructionError(_, ix_err) => Self {
+                code: None,
+                name: format!("{:?}", ix_err),
+                message: ix_err.to_string(),
+                logs,
+                permanent: true,
+            },
+            _ => Self {
+                code: None,
+                name: format!("{:?}", err),
+                message: err.to_string(),
+                logs,
+                permanent: !is_transient(err),
+            },
+        }
+    }
+
+    /// Maps a failed simulation.
+    pub fn from_simulation(err: &TransactionError, result: &RpcSimulateTransactionResult) -> Self {
+        Self::from_transaction_error(err, result.logs.clone().unwrap_or_default())
+    }
+
+    /// Maps an RPC client error. Preflight failures carry the simulation logs; transport errors
+    /// and timeouts are transient.
+    pub fn from_client_error(err: &ClientError) -> Self {
+        match err.kind() {
+            ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+                ..
+            }) => match &result.err {
+                Some(tx_err) => Self::from_simulation(tx_err, result),
+                None => Self::other("PreflightFailure", err.to_string(), false),
+            },
+            ClientErrorKind::TransactionError(tx_err) => Self::from_transaction_error(tx_err, Vec::new()),
+            ClientErrorKind::SigningError(_) => Self::other("SigningError", err.to_string(), true),
+            _ => Self::other("RpcError", err.to_string(), false),
+        }
+    }
+}
+
+/// Reads `resolve_error`, which older games store as a plain string.
+pub fn deserialize_legacy<'de, D>(deserializer: D) -> Result<Option<TxError>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Structured(TxError),
+        Message(String),
+    }
+    Ok(Option::<Stored>::deserialize(deserializer)?.map(|stored| match stored {
+        Stored::Structured(e) => e,
+        Stored::Message(message) => TxError::other("Unknown", message, false),
+    }))
+}
@@ -0,0 +1,186 @@
+//! Verdicts the winner claims on-chain, instead of the API resolving every game.
+//!
+//! With `VERDICT_TTL_SECS` set, a decided game gets a [`Verdict`] rather than a resolve: the
+//! resolve authority's ed25519 signature of the program's `verdict_message` (escrow, game id,
+//! winner, expiry). `POST /games/:game_id/choice` returns it with the game, which stays
+//! `resolving`. The winner (or anyone) sends the Ed25519 program instruction verifying it, then
+//! `resolve_with_attestation`, and pays the fees; `POST /games/:game_id/tx/claim` builds that
+//! transaction. The indexer finishes the game when it lands.
+//!
+//! Games that need co-signers, and every game when the verdict cannot be signed, are resolved by
+//! the API as before. So are unclaimed verdicts: every [`FALLBACK_CHECK_INTERVAL`], games still
+//! `resolving` [`FALLBACK_GRACE_SECS`] after their verdict expired are resolved by the backend
+//! resolver if their escrow is still open.
+
+use std::time::Duration;
+
+use base64::Engine;
+use mongodb::bson::doc;
+use rps_escrow_client::instructions::{self, BatchGame};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Signature};
+
+use crate::games::{short_pk, AppState, Game};
+use crate::signer::VerdictTerms;
+use crate::solana::SolanaAppClient;
+
+/// Interval between checks for unclaimed verdicts.
+pub const FALLBACK_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Margin past a verdict's expiry before the API resolves the game, so a claim sent just before
+/// it can still land (and be indexed).
+pub const FALLBACK_GRACE_SECS: i64 = 60;
+
+/// A signed verdict, as stored on the game and returned to the players.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Verdict {
+    pub game_escrow: String,
+    pub winner: String,
+    /// Unix timestamp after which the program refuses the verdict.
+    pub expiry: i64,
+    /// Resolve authority that signed it.
+    pub signer: String,
+    /// Base58 ed25519 signature of `message`.
+    pub signature: String,
+    /// Base64 verdict message, as the Ed25519 program instruction must carry it.
+    pub message: String,
+}
+
+impl Verdict {
+    fn new(terms: &VerdictTerms, signer: &Pubkey, signature: &Signature) -> Self {
+        Self {
+            game_escrow: terms.game_escrow.to_string(),
+            winner: terms.winner.to_string(),
+            expiry: terms.expiry,
+            signer: signer.to_string(),
+            signature: signature.to_string(),
+            message: base64::engine::general_purpose::STANDARD.encode(terms.message()),
+        }
+    }
+
+    /// The Ed25519 program instruction verifying the verdict, then `resolve_with_attestation`.
+    pub fn claim_instructions(&self, solana: &SolanaAppClient, game: &Game) -> Result<[Instruction; 2], String> {
+        let game_id = *uuid::Uuid::parse_str(&game.id).map_err(|e| e.to_string())?.as_bytes();
+        let creator: Pubkey = game.creator_pubkey.parse().map_err(|_| "invalid creator pubkey")?;
+        let winner: Pubkey = self.winner.parse().map_err(|_| "invalid winner pubkey")?;
+        let signer: Pubkey = self.signer.parse().map_err(|_| "invalid verdict signer")?;
+        let signature: Signature = self.signature.parse().map_err(|_| "invalid verdict signature")?;
+        let message = base64::engine::general_purpose::STANDARD
+            .decode(&self.message)
+            .map_err(|e| e.to_string())?;
+        Ok([
+            instructions::ed25519_verdict(&message, &[(signer, signature.into())]),
+            instructions::resolve_with_attestation(
+                &solana.program_id,
+                &creator,
+                game_id,
+                &winner,
+                &solana.treasury,
+                self.expiry,
+            ),
+        ])
+    }
+}
+
+/// Signs and records a verdict that `winner` won `game`. None when the API should resolve the
+/// game itself: verdicts off, a game needing co-signers, or signing failed.
+pub(crate) async fn issue(state: &AppState, game: &Game, winner: &str) -> Option<Verdict> {
+    let solana = state.solana.clone()?;
+    let batch_game = BatchGame {
+        creator: game.creator_pubkey.parse().ok()?,
+        game_id: *uuid::Uuid::parse_str(&game.id).ok()?.as_bytes(),
+        winner: winner.parse().ok()?,
+    };
+    let signed = tokio::task::spawn_blocking(move || solana.sign_verdict(&batch_game))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r.map_err(|e| e.to_string()));
+    let verdict = match signed {
+        Ok(Some((terms, signer, signature))) => Verdict::new(&terms, &signer, &signature),
+        Ok(None) => return None,
+        Err(e) => {
+            log::warn!("Verdict signing failed game_id={}, resolving instead: {}", game.id, e);
+            return None;
+        }
+    };
+    let stored = match mongodb::bson::to_bson(&verdict) {
+        Ok(bson) => state
+            .db
+            .collection::<Game>("games")
+            .update_one(doc! { "_id": &game.id }, doc! { "$set": { "verdict": bson } }, None)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = stored {
+        log::error!("Failed to store verdict game_id={}, resolving instead: {}", game.id, e);
+        return None;
+    }
+    log::info!(
+        "Verdict issued game_id={} winner={} expiry={}",
+        game.id,
+        short_pk(winner),
+        verdict.expiry
+    );
+    Some(verdict)
+}
+
+/// Resolves the games whose verdict expired unclaimed and whose escrow is still open; a closed
+/// escrow was claimed (or refunded), which the indexer records.
+async fn resolve_unclaimed(state: &AppState) -> Result<(), String> {
+    let Some(solana) = state.solana.clone() else {
+        return Ok(());
+    };
+    let games = state.db.collection::<Game>("games");
+    let deadline = chrono::Utc::now().timestamp() - FALLBACK_GRACE_SECS;
+    let filter = doc! { "status": "resolving", "verdict.expiry": { "$lt": deadline } };
+    let mut cursor = games.find(filter, None).await.map_err(|e| e.to_string())?;
+    let mut unclaimed: Vec<Game> = Vec::new();
+    while cursor.advance().await.map_err(|e| e.to_string())? {
+        unclaimed.push(cursor.deserialize_current().map_err(|e| e.to_string())?);
+    }
+
+    for game in unclaimed {
+        let (Some(verdict), Some(escrow)) = (&game.verdict, &game.game_escrow_pubkey) else {
+            continue;
+        };
+        let Ok(escrow) = escrow.parse::<Pubkey>() else {
+            continue;
+        };
+        let client = solana.clone();
+        let open = tokio::task::spawn_blocking(move || client.fetch_game_escrow(&escrow))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r);
+        match open {
+            Ok(Some(_)) => {
+                log::info!("Verdict unclaimed game_id={}, resolving on-chain", game.id);
+                let _ = crate::games::try_resolve_on_chain(
+                    state,
+                    &games,
+                    &game.id,
+                    &game.creator_pubkey,
+                    &verdict.winner,
+                )
+                .await;
+            }
+            Ok(None) => log::debug!("Verdict game_id={} escrow closed, left to the indexer", game.id),
+            Err(e) => log::warn!("Unclaimed verdict check failed game_id={}: {}", game.id, e),
+        }
+    }
+    Ok(())
+}
+
+/// Resolves unclaimed verdicts every `interval`.
+pub fn spawn_fallback_job(state: AppState, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = resolve_unclaimed(&state).await {
+                log::error!("Unclaimed verdict check failed: {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,54 @@
+//! Program error codes (`InstructionError::Custom`) mapped back to [`EscrowError`].
+
+use crate::EscrowError;
+
+/// Every `EscrowError` variant, in declaration order.
+pub const ESCROW_ERRORS: [EscrowError; 19] = [
+    EscrowError::InvalidAmount,
+    EscrowError::AlreadyResolved,
+    EscrowError::JoinerAlreadySet,
+    EscrowError::WinnerAlreadySet,
+    EscrowError::NoJoiner,
+    EscrowError::InsufficientBalance,
+    EscrowError::InvalidWinner,
+    EscrowError::Unauthorized,
+    EscrowError::UnauthorizedCreator,
+    EscrowError::UnauthorizedJoiner,
+    EscrowError::InvalidTreasury,
+    EscrowError::InvalidOpponent,
+    EscrowError::InvalidBatch,
+    EscrowError::InvalidResolveConfig,
+    EscrowError::NotEnoughSigners,
+    EscrowError::Paused,
+    EscrowError::GameIdUsed,
+    EscrowError::InvalidAttestation,
+    EscrowError::AttestationExpired,
+];
+
+/// Stops compiling when the program gains a variant: add it to [`ESCROW_ERRORS`] too.
+const _: fn(EscrowError) = |e| match e {
+    EscrowError::InvalidAmount
+    | EscrowError::AlreadyResolved
+    | EscrowError::JoinerAlreadySet
+    | EscrowError::WinnerAlreadySet
+    | EscrowError::NoJoiner
+    | EscrowError::InsufficientBalance
+    | EscrowError::InvalidWinner
+    | EscrowError::Unauthorized
+    | EscrowError::UnauthorizedCreator
+    | EscrowError::UnauthorizedJoiner
+    | EscrowError::InvalidTreasury
+    | EscrowError::InvalidOpponent
+    | EscrowError::InvalidBatch
+    | EscrowError::InvalidResolveConfig
+    | EscrowError::NotEnoughSigners
+    | EscrowError::Paused
+    | EscrowError::GameIdUsed
+    | EscrowError::InvalidAttestation
+    | EscrowError::AttestationExpired => {}
+};
+
+/// The `EscrowError` with custom error `code` (6000 and up). None for Anchor framework errors.
+pub fn escrow_error(code: u32) -> Option<EscrowError> {
+    ESCROW_ERRORS.into_iter().find(|e| u32::from(*e) == code)
+}
@@ -0,0 +1,29 @@
+//! Amounts moved by the program: the program's own minimum bet and fee math.
+
+pub use rps_escrow::{treasury_fee, MIN_BET_LAMPORTS, TREASURY_FEE_PERCENT};
+
+/// How `resolve` splits the vault of a game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Payout {
+    /// Both deposits.
+    pub pot: u64,
+    /// Sent to the treasury.
+    pub treasury_fee: u64,
+    /// Sent to the winner.
+    pub winner_amount: u64,
+}
+
+/// Resolve split of a game with `amount_per_player`. None on overflow (the program rejects it).
+pub fn resolve_payout(amount_per_player: u64) -> Option<Payout> {
+    let (pot, treasury_fee, winner_amount) = rps_escrow::resolve_payout(amount_per_player)?;
+    Some(Payout {
+        pot,
+        treasury_fee,
+        winner_amount,
+    })
+}
+
+/// Winner's profit over their own deposit.
+pub fn winner_net(amount_per_player: u64) -> Option<u64> {
+    resolve_payout(amount_per_player)?.winner_amount.checked_sub(amount_per_player)
+}
@@ -0,0 +1,425 @@
+//! Typed builders for every rps-escrow instruction, and decoding of instruction data.
+//!
+//! Accounts and data come from the program's generated `accounts` and `instruction` modules; the
+//! builders only derive the PDAs.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::{system_program, AnchorDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use rps_escrow::{accounts, instruction};
+
+use crate::pda::{game_addresses, game_result_pda, pause_pda, resolve_config_pda};
+
+fn build(program_id: &Pubkey, accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// `create_game`: `creator` opens game `game_id` and deposits `amount` lamports.
+pub fn create_game(program_id: &Pubkey, creator: &Pubkey, game_id: [u8; 16], amount: u64) -> Instruction {
+    let (game_escrow, vault) = game_addresses(program_id, creator, &game_id);
+    build(
+        program_id,
+        accounts::CreateGame {
+            creator: *creator,
+            game_escrow,
+            vault,
+            game_result: game_result_pda(program_id, &game_escrow).0,
+            pause: pause_pda(program_id).0,
+            system_program: system_program::ID,
+        },
+        instruction::CreateGame { game_id, amount },
+    )
+}
+
+/// `create_challenge`: as [`create_game`], but only `opponent` can join.
+pub fn create_challenge(
+    program_id: &Pubkey,
+    creator: &Pubkey,
+    game_id: [u8; 16],
+    amount: u64,
+    opponent: &Pubkey,
+) -> Instruction {
+    let (game_escrow, vault) = game_addresses(program_id, creator, &game_id);
+    build(
+        program_id,
+        accounts::CreateGame {
+            creator: *creator,
+            game_escrow,
+            vault,
+            game_result: game_result_pda(program_id, &game_escrow).0,
+            pause: pause_pda(program_id).0,
+            system_program: system_program::ID,
+        },
+        instruction::CreateChallenge {
+            game_id,
+            amount,
+            opponent: *opponent,
+        },
+    )
+}
+
+/// `join_game`: `joiner` deposits into `creator`'s game `game_id`.
+pub fn join_game(program_id: &Pubkey, joiner: &Pubkey, creator: &Pubkey, game_id: [u8; 16]) -> Instruction {
+    let (game_escrow, vault) = game_addresses(program_id, creator, &game_id);
+    build(
+        program_id,
+        accounts::JoinGame {
+            joiner: *joiner,
+            game_escrow,
+            vault,
+            pause: pause_pda(program_id).0,
+            system_program: system_program::ID,
+        },
+        instruction::JoinGame {},
+    )
+}
+
+/// `cancel`: `creator` takes their deposit back before anyone joined.
+pub fn cancel(program_id: &Pubkey, creator: &Pubkey, game_id: [u8; 16]) -> Instruction {
+    let (game_escrow, vault) = game_addresses(program_id, creator, &game_id);
+    build(
+        program_id,
+        accounts::Cancel {
+            creator: *creator,
+            game_escrow,
+            vault,
+            system_program: system_program::ID,
+        },
+        instruction::Cancel {},
+    )
+}
+
+/// `refund`: `authority` returns both deposits.
+pub fn refund(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    creator: &Pubkey,
+    joiner: &Pubkey,
+    game_id: [u8; 16],
+) -> Instruction {
+    let (game_escrow, vault) = game_addresses(program_id, creator, &game_id);
+    build(
+        program_id,
+        accounts::Refund {
+            authority: *authority,
+            game_escrow,
+            vault,
+            creator: *creator,
+            joiner: *joiner,
+            game_result: game_result_pda(program_id, &game_escrow).0,
+            resolve_config: resolve_config_pda(program_id).0,
+            system_program: system_program::ID,
+        },
+        instruction::Refund {},
+    )
+}
+
+/// `resolve`: `authority` pays the pot minus the fee to `winner` and the fee to `treasury`.
+pub fn resolve(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    creator: &Pubkey,
+    game_id: [u8; 16],
+    winner: &Pubkey,
+    treasury: &Pubkey,
+) -> Instruction {
+    let (game_escrow, vault) = game_addresses(program_id, creator, &game_id);
+    build(
+        program_id,
+        accounts::Resolve {
+            authority: *authority,
+            game_escrow,
+            vault,
+            winner_destination: *winner,
+            creator: *creator,
+            treasury: *treasury,
+            game_result: game_result_pda(program_id, &game_escrow).0,
+            resolve_config: resolve_config_pda(program_id).0,
+            system_program: system_program::ID,
+        },
+        instruction::Resolve { winner: *winner },
+    )
+}
+
+pub use rps_escrow::{verdict_message, RESOLVE_BATCH_GROUP, VERDICT_DOMAIN};
+
+/// Accounts of a [`resolve_batch`] before the game groups: authority, treasury, resolve config,
+/// system program.
+pub const RESOLVE_BATCH_FIXED_ACCOUNTS: usize = 4;
+
+/// Game escrows resolved by a [`resolve_batch`] instruction with account keys `accounts`.
+pub fn resolve_batch_escrows(accounts: &[Pubkey]) -> impl Iterator<Item = Pubkey> + '_ {
+    accounts
+        .get(RESOLVE_BATCH_FIXED_ACCOUNTS..)
+        .unwrap_or_default()
+        .chunks_exact(RESOLVE_BATCH_GROUP)
+        .map(|group| group[0])
+}
+
+/// One game of a [`resolve_batch`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchGame {
+    pub creator: Pubkey,
+    pub game_id: [u8; 16],
+    pub winner: Pubkey,
+}
+
+/// `resolve_batch`: [`resolve`] for every game of `games` in one instruction.
+///
+/// Each game adds a writable `[game_escrow, vault, winner, creator, game_result]` group to the accounts.
+pub fn resolve_batch(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    treasury: &Pubkey,
+    games: &[BatchGame],
+) -> Instruction {
+    let mut ix = build(
+        program_id,
+        accounts::ResolveBatch {
+            authority: *authority,
+            treasury: *treasury,
+            resolve_config: resolve_config_pda(program_id).0,
+            system_program: system_program::ID,
+        },
+        instruction::ResolveBatch {
+            winners: games.iter().map(|g| g.winner).collect(),
+        },
+    );
+    for game in games {
+        let (game_escrow, vault) = game_addresses(program_id, &game.creator, &game.game_id);
+        let game_result = game_result_pda(program_id, &game_escrow).0;
+        let group = [game_escrow, vault, game.winner, game.creator, game_result];
+        ix.accounts.extend(group.map(|pubkey| AccountMeta::new(pubkey, false)));
+    }
+    ix
+}
+
+/// `resolve_with_attestation`: pays out `creator`'s game `game_id` to `winner` on the strength of a
+/// verdict valid until `expiry`; no signer. Send it right after [`ed25519_verdict`].
+pub fn resolve_with_attestation(
+    program_id: &Pubkey,
+    creator: &Pubkey,
+    game_id: [u8; 16],
+    winner: &Pubkey,
+    treasury: &Pubkey,
+    expiry: i64,
+) -> Instruction {
+    let (game_escrow, vault) = game_addresses(program_id, creator, &game_id);
+    build(
+        program_id,
+        accounts::ResolveWithAttestation {
+            game_escrow,
+            vault,
+            winner_destination: *winner,
+            creator: *creator,
+            treasury: *treasury,
+            game_result: game_result_pda(program_id, &game_escrow).0,
+            resolve_config: resolve_config_pda(program_id).0,
+            instructions: solana_sdk_ids::sysvar::instructions::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ResolveWithAttestation {
+            winner: *winner,
+            expiry,
+        },
+    )
+}
+
+/// Ed25519 program instruction verifying `signatures` (signer, signature) of the verdict `message`
+/// (see [`verdict_message`]), with all data inline as `resolve_with_attestation` expects. One
+/// signature per authority for a high-stake game.
+pub fn ed25519_verdict(message: &[u8], signatures: &[(Pubkey, [u8; 64])]) -> Instruction {
+    const OFFSETS_SIZE: usize = 14;
+    let keys_start = 2 + signatures.len() * OFFSETS_SIZE;
+    let message_offset = keys_start + signatures.len() * 96;
+    let mut data = vec![signatures.len() as u8, 0];
+    for i in 0..signatures.len() {
+        let key_offset = keys_start + i * 96;
+        // Signature, key and message offsets; instruction index u16::MAX is this instruction.
+        let fields = [
+            key_offset + 32,
+            usize::from(u16::MAX),
+            key_offset,
+            usize::from(u16::MAX),
+            message_offset,
+            message.len(),
+            usize::from(u16::MAX),
+        ];
+        data.extend(fields.iter().flat_map(|&f| (f as u16).to_le_bytes()));
+    }
+    for (key, signature) in signatures {
+        data.extend_from_slice(key.as_ref());
+        data.extend_from_slice(signature);
+    }
+    data.extend_from_slice(message);
+    Instruction {
+        program_id: solana_sdk_ids::ed25519_program::ID,
+        accounts: Vec::new(),
+        data,
+    }
+}
+
+/// Adds the co-signing resolve authorities of a high-stake game to a [`resolve`], [`refund`],
+/// [`update_resolve_config`] or [`set_paused`] instruction (as readonly signers in the remaining accounts).
+pub fn with_cosigners(mut ix: Instruction, cosigners: &[Pubkey]) -> Instruction {
+    ix.accounts
+        .extend(cosigners.iter().map(|pubkey| AccountMeta::new_readonly(*pubkey, true)));
+    ix
+}
+
+/// `init_resolve_config`: `RESOLVE_AUTHORITY` creates the resolve authority set.
+pub fn init_resolve_config(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    authorities: Vec<Pubkey>,
+    threshold: u8,
+    multisig_min_amount: u64,
+) -> Instruction {
+    build(
+        program_id,
+        accounts::InitResolveConfig {
+            authority: *authority,
+            resolve_config: resolve_config_pda(program_id).0,
+            system_program: system_program::ID,
+        },
+        instruction::InitResolveConfig {
+            authorities,
+            threshold,
+            multisig_min_amount,
+        },
+    )
+}
+
+/// `update_resolve_config`: replaces the set; add the other current authorities with
+/// [`with_cosigners`] up to its threshold.
+pub fn update_resolve_config(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    authorities: Vec<Pubkey>,
+    threshold: u8,
+    multisig_min_amount: u64,
+) -> Instruction {
+    build(
+        program_id,
+        accounts::UpdateResolveConfig {
+            authority: *authority,
+            resolve_config: resolve_config_pda(program_id).0,
+        },
+        instruction::UpdateResolveConfig {
+            authorities,
+            threshold,
+            multisig_min_amount,
+        },
+    )
+}
+
+/// `set_paused`: `authority` pauses or reopens new games and joins; add co-signers with
+/// [`with_cosigners`] once the resolve config exists.
+pub fn set_paused(program_id: &Pubkey, authority: &Pubkey, paused: bool) -> Instruction {
+    build(
+        program_id,
+        accounts::SetPaused {
+            authority: *authority,
+            pause: pause_pda(program_id).0,
+            resolve_config: resolve_config_pda(program_id).0,
+            system_program: system_program::ID,
+        },
+        instruction::SetPaused { paused },
+    )
+}
+
+/// Decoded rps-escrow instruction data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EscrowInstruction {
+    CreateGame { game_id: [u8; 16], amount: u64 },
+    CreateChallenge { game_id: [u8; 16], amount: u64, opponent: Pubkey },
+    JoinGame,
+    Cancel,
+    Refund,
+    Resolve { winner: Pubkey },
+    ResolveBatch { winners: Vec<Pubkey> },
+    ResolveWithAttestation { winner: Pubkey, expiry: i64 },
+    InitResolveConfig { authorities: Vec<Pubkey>, threshold: u8, multisig_min_amount: u64 },
+    UpdateResolveConfig { authorities: Vec<Pubkey>, threshold: u8, multisig_min_amount: u64 },
+    SetPaused { paused: bool },
+}
+
+impl EscrowInstruction {
+    /// Decodes instruction data by discriminator. None for unknown or malformed data.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let (disc, mut args) = data.split_at_checked(8)?;
+        let ix = if disc == instruction::CreateGame::DISCRIMINATOR {
+            let a = instruction::CreateGame::deserialize(&mut args).ok()?;
+            Self::CreateGame {
+                game_id: a.game_id,
+                amount: a.amount,
+            }
+        } else if disc == instruction::CreateChallenge::DISCRIMINATOR {
+            let a = instruction::CreateChallenge::deserialize(&mut args).ok()?;
+            Self::CreateChallenge {
+                game_id: a.game_id,
+                amount: a.amount,
+                opponent: a.opponent,
+            }
+        } else if disc == instruction::JoinGame::DISCRIMINATOR {
+            Self::JoinGame
+        } else if disc == instruction::Cancel::DISCRIMINATOR {
+            Self::Cancel
+        } else if disc == instruction::Refund::DISCRIMINATOR {
+            Self::Refund
+        } else if disc == instruction::Resolve::DISCRIMINATOR {
+            let a = instruction::Resolve::deserialize(&mut args).ok()?;
+            Self::Resolve { winner: a.winner }
+        } else if disc == instruction::ResolveBatch::DISCRIMINATOR {
+            let a = instruction::ResolveBatch::deserialize(&mut args).ok()?;
+            Self::ResolveBatch { winners: a.winners }
+        } else if disc == instruction::ResolveWithAttestation::DISCRIMINATOR {
+            let a = instruction::ResolveWithAttestation::deserialize(&mut args).ok()?;
+            Self::ResolveWithAttestation {
+                winner: a.winner,
+                expiry: a.expiry,
+            }
+        } else if disc == instruction::InitResolveConfig::DISCRIMINATOR {
+            let a = instruction::InitResolveConfig::deserialize(&mut args).ok()?;
+            Self::InitResolveConfig {
+                authorities: a.authorities,
+                threshold: a.threshold,
+                multisig_min_amount: a.multisig_min_amount,
+            }
+        } else if disc == instruction::UpdateResolveConfig::DISCRIMINATOR {
+            let a = instruction::UpdateResolveConfig::deserialize(&mut args).ok()?;
+            Self::UpdateResolveConfig {
+                authorities: a.authorities,
+                threshold: a.threshold,
+                multisig_min_amount: a.multisig_min_amount,
+            }
+        } else if disc == instruction::SetPaused::DISCRIMINATOR {
+            let a = instruction::SetPaused::deserialize(&mut args).ok()?;
+            Self::SetPaused { paused: a.paused }
+        } else {
+            return None;
+        };
+        Some(ix)
+    }
+
+    /// Instruction name, as in the program and its IDL.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::CreateGame { .. } => "create_game",
+            Self::CreateChallenge { .. } => "create_challenge",
+            Self::JoinGame => "join_game",
+            Self::Cancel => "cancel",
+            Self::Refund => "refund",
+            Self::Resolve { .. } => "resolve",
+            Self::ResolveBatch { .. } => "resolve_batch",
+            Self::ResolveWithAttestation { .. } => "resolve_with_attestation",
+            Self::InitResolveConfig { .. } => "init_resolve_config",
+            Self::UpdateResolveConfig { .. } => "update_resolve_config",
+            Self::SetPaused { .. } => "set_paused",
+        }
+    }
+}
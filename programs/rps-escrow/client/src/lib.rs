@@ -0,0 +1,26 @@
+//! Rust client for the rps-escrow program, shared by the API and the Rust tests.
+//!
+//! Built on the program crate itself (`no-entrypoint`): instruction data, account layouts, error
+//! codes, constants and fee math are the program's own types and functions, so they cannot drift
+//! from the deployed source.
+//!
+//! - [`pda`]: game escrow, vault, result receipt, resolve config and pause addresses.
+//! - [`instructions`]: typed builders for every instruction (and the Ed25519 instruction carrying a
+//!   verdict), and instruction decoding.
+//! - [`state`]: `GameEscrow`, `GameResult`, `ResolveConfig` and `PauseState` account decoding.
+//! - [`fees`]: pot, treasury fee and payout math.
+//! - [`errors`]: program error codes to [`EscrowError`].
+//!
+//! Builders and PDA helpers take the program id, so the same client serves every deployment
+//! (localnet, devnet, mainnet); [`ID`] is the id declared by the program.
+
+pub mod errors;
+pub mod fees;
+pub mod instructions;
+pub mod pda;
+pub mod state;
+
+pub use rps_escrow::{
+    EscrowError, GameEscrow, GameResult, PauseState, ResolveConfig, ID, MAX_RESOLVE_AUTHORITIES,
+    RESOLVE_AUTHORITY, TREASURY_PUBKEY,
+};
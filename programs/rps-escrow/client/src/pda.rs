@@ -0,0 +1,45 @@
+//! Program derived addresses, with the seeds of the program's account constraints.
+
+use anchor_lang::prelude::Pubkey;
+
+/// Seed prefix of the game escrow: `["game_escrow", creator, game_id]`.
+pub const GAME_ESCROW_SEED: &[u8] = b"game_escrow";
+/// Seed prefix of the vault: `["vault", game_escrow]`.
+pub const VAULT_SEED: &[u8] = b"vault";
+/// Seed of the resolve authority set: `["resolve_config"]`.
+pub const RESOLVE_CONFIG_SEED: &[u8] = b"resolve_config";
+/// Seed prefix of the result receipt: `["game_result", game_escrow]`.
+pub const GAME_RESULT_SEED: &[u8] = b"game_result";
+/// Seed of the emergency pause flag: `["pause"]`.
+pub const PAUSE_SEED: &[u8] = b"pause";
+
+/// Game escrow PDA and bump for `creator`'s game `game_id` (16-byte UUID).
+pub fn game_escrow_pda(program_id: &Pubkey, creator: &Pubkey, game_id: &[u8; 16]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GAME_ESCROW_SEED, creator.as_ref(), game_id.as_ref()], program_id)
+}
+
+/// Vault PDA and bump of `game_escrow`.
+pub fn vault_pda(program_id: &Pubkey, game_escrow: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, game_escrow.as_ref()], program_id)
+}
+
+/// Result receipt PDA and bump of `game_escrow`.
+pub fn game_result_pda(program_id: &Pubkey, game_escrow: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GAME_RESULT_SEED, game_escrow.as_ref()], program_id)
+}
+
+/// Resolve config PDA and bump.
+pub fn resolve_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RESOLVE_CONFIG_SEED], program_id)
+}
+
+/// Pause flag PDA and bump.
+pub fn pause_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PAUSE_SEED], program_id)
+}
+
+/// Game escrow and vault addresses of `creator`'s game `game_id`.
+pub fn game_addresses(program_id: &Pubkey, creator: &Pubkey, game_id: &[u8; 16]) -> (Pubkey, Pubkey) {
+    let game_escrow = game_escrow_pda(program_id, creator, game_id).0;
+    (game_escrow, vault_pda(program_id, &game_escrow).0)
+}
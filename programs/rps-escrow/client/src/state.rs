@@ -0,0 +1,40 @@
+//! Account decoding.
+
+use anchor_lang::{prelude::Pubkey, AccountDeserialize, Discriminator, Space};
+
+use crate::{GameEscrow, GameResult, PauseState, ResolveConfig};
+
+/// Size of a `GameEscrow` account (discriminator included).
+pub const GAME_ESCROW_SPACE: usize = GameEscrow::SPACE;
+
+/// Size of a `GameResult` receipt (discriminator included).
+pub const GAME_RESULT_SPACE: usize = 8 + GameResult::INIT_SPACE;
+
+/// Anchor discriminator of `GameEscrow` accounts (e.g. for `getProgramAccounts` filters).
+pub const GAME_ESCROW_DISCRIMINATOR: &[u8] = GameEscrow::DISCRIMINATOR;
+
+/// Decodes a `GameEscrow` account's data, checking its discriminator.
+pub fn decode_game_escrow(data: &[u8]) -> anchor_lang::Result<GameEscrow> {
+    GameEscrow::try_deserialize(&mut &data[..])
+}
+
+/// Challenged wallet of a `GameEscrow` account's data; None for open games and for escrows created
+/// before challenges, which lack the field.
+pub fn decode_game_escrow_opponent(data: &[u8]) -> anchor_lang::Result<Option<Pubkey>> {
+    GameEscrow::opponent(data)
+}
+
+/// Decodes a `GameResult` receipt's data, checking its discriminator.
+pub fn decode_game_result(data: &[u8]) -> anchor_lang::Result<GameResult> {
+    GameResult::try_deserialize(&mut &data[..])
+}
+
+/// Decodes the `ResolveConfig` account's data, checking its discriminator.
+pub fn decode_resolve_config(data: &[u8]) -> anchor_lang::Result<ResolveConfig> {
+    ResolveConfig::try_deserialize(&mut &data[..])
+}
+
+/// Decodes the `PauseState` account's data, checking its discriminator.
+pub fn decode_pause_state(data: &[u8]) -> anchor_lang::Result<PauseState> {
+    PauseState::try_deserialize(&mut &data[..])
+}
@@ -26,4 +26,20 @@ pub enum EscrowError {
     UnauthorizedJoiner,
     #[msg("Invalid treasury")]
     InvalidTreasury,
+    #[msg("Opponent must differ from the creator")]
+    InvalidOpponent,
+    #[msg("Batch must hold one [game_escrow, vault, winner, creator, game_result] group per winner, each game once")]
+    InvalidBatch,
+    #[msg("Resolve config needs 1 <= threshold <= authorities <= 5, without duplicate authorities")]
+    InvalidResolveConfig,
+    #[msg("Not enough resolve authorities signed for this stake")]
+    NotEnoughSigners,
+    #[msg("Escrow is paused: new games and joins are disabled")]
+    Paused,
+    #[msg("Game id already used: the result receipt of this escrow exists")]
+    GameIdUsed,
+    #[msg("Verdict must be signed in an Ed25519 instruction just before resolve_with_attestation")]
+    InvalidAttestation,
+    #[msg("Verdict expired")]
+    AttestationExpired,
 }
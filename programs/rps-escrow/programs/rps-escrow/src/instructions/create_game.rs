@@ -5,14 +5,14 @@ use anchor_lang::prelude::*;
 use anchor_lang::system_program::{transfer, Transfer};
 
 use crate::errors::EscrowError;
+use crate::instructions::check_not_paused;
 use crate::state::GameEscrow;
 
 /// Minimum bet per player: 0.001 SOL (also above rent-exempt for vault).
-const MIN_BET_LAMPORTS: u64 = 1_000_000;
+pub const MIN_BET_LAMPORTS: u64 = 1_000_000;
 
 #[derive(Accounts)]
 #[instruction(game_id: [u8; 16], amount: u64)]
-
 pub struct CreateGame<'info> {
     #[account(mut)]
     pub creator: Signer<'info>,
@@ -20,7 +20,7 @@ pub struct CreateGame<'info> {
     #[account(
         init,
         payer = creator,
-        space = 8 + GameEscrow::INIT_SPACE,
+        space = GameEscrow::SPACE,
         seeds = [b"game_escrow", creator.key().as_ref(), game_id.as_ref()],
         bump
     )]
@@ -33,21 +33,38 @@ pub struct CreateGame<'info> {
     )]
     pub vault: SystemAccount<'info>,
 
+    /// CHECK: Receipt of a finished game with the same escrow, which must not exist.
+    #[account(seeds = [b"game_result", game_escrow.key().as_ref()], bump)]
+    pub game_result: UncheckedAccount<'info>,
+
+    /// CHECK: Emergency pause flag, if initialized (see `check_not_paused`).
+    #[account(seeds = [b"pause"], bump)]
+    pub pause: UncheckedAccount<'info>,
+
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> CreateGame<'info> {
     /// Initializes the game escrow and transfers `amount` lamports from creator to the vault.
+    /// `opponent` pins the only wallet allowed to join (direct challenge).
     pub fn create_and_deposit(
         &mut self,
         game_id: [u8; 16],
         amount: u64,
+        opponent: Option<Pubkey>,
         bumps: &CreateGameBumps,
     ) -> Result<()> {
+        check_not_paused(&self.pause)?;
+        // A reused id would leave the new game unable to write its receipt, so never to close.
+        require!(self.game_result.data_is_empty(), EscrowError::GameIdUsed);
         // amount must be at least MIN_BET_LAMPORTS (0.001 SOL) and above rent-exempt for the vault
         let rent_exempt: u64 = Rent::get()?.minimum_balance(self.vault.to_account_info().data_len());
         require!(amount >= MIN_BET_LAMPORTS, EscrowError::InvalidAmount);
         require!(amount > rent_exempt, EscrowError::InvalidAmount);
+        require!(
+            opponent != Some(self.creator.key()),
+            EscrowError::InvalidOpponent
+        );
 
         self.game_escrow.set_inner(GameEscrow {
             creator: self.creator.key(),
@@ -59,6 +76,7 @@ impl<'info> CreateGame<'info> {
             resolved: false,
             winner: None,
         });
+        GameEscrow::write_opponent(&mut self.game_escrow.to_account_info().try_borrow_mut_data()?, opponent)?;
 
         // transfer the amount from the creator to the vault. It will also create the vault account owned by system program. 
         let cpi_ctx = CpiContext::new(
@@ -4,6 +4,7 @@ use anchor_lang::prelude::*;
 use anchor_lang::system_program::{transfer, Transfer};
 
 use crate::errors::EscrowError;
+use crate::instructions::check_not_paused;
 use crate::state::GameEscrow;
 
 #[derive(Accounts)]
@@ -27,13 +28,23 @@ pub struct JoinGame<'info> {
     )]
     pub vault: SystemAccount<'info>,
 
+    /// CHECK: Emergency pause flag, if initialized (see `check_not_paused`).
+    #[account(seeds = [b"pause"], bump)]
+    pub pause: UncheckedAccount<'info>,
 
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> JoinGame<'info> {
     /// Deposits `amount_per_player` lamports from joiner into the vault and sets joiner on the game.
+    /// A direct challenge only lets its opponent join.
     pub fn deposit_and_join(&mut self) -> Result<()> {
+        check_not_paused(&self.pause)?;
+        let opponent = GameEscrow::opponent(&self.game_escrow.to_account_info().try_borrow_data()?)?;
+        require!(
+            opponent.is_none_or(|o| o == self.joiner.key()),
+            EscrowError::UnauthorizedJoiner
+        );
         let amount = self.game_escrow.amount_per_player;
 
         let cpi_ctx = CpiContext::new(
@@ -3,9 +3,19 @@ pub mod join_game;
 pub mod resolve;
 pub mod cancel;
 pub mod refund;
+pub mod resolve_batch;
+pub mod resolve_authority;
+pub mod pause;
+pub mod receipt;
+pub mod resolve_with_attestation;
 
 pub use create_game::*;
 pub use join_game::*;
 pub use resolve::*;
 pub use cancel::*;
-pub use refund::*;
\ No newline at end of file
+pub use refund::*;
+pub use resolve_batch::*;
+pub use resolve_authority::*;
+pub use pause::*;
+pub use receipt::*;
+pub use resolve_with_attestation::*;
\ No newline at end of file
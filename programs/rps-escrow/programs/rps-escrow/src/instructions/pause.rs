@@ -0,0 +1,56 @@
+//! Emergency pause: `set_paused` stops or reopens new deposits (`create_game`, `create_challenge`,
+//! `join_game`). Games already funded can still be cancelled, refunded and resolved.
+
+use anchor_lang::prelude::*;
+
+use crate::errors::EscrowError;
+use crate::instructions::check_resolve_authority;
+use crate::state::PauseState;
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + PauseState::INIT_SPACE,
+        seeds = [b"pause"],
+        bump
+    )]
+    pub pause: Account<'info, PauseState>,
+
+    /// CHECK: Resolve authority set, if initialized (see `check_resolve_authority`).
+    #[account(seeds = [b"resolve_config"], bump)]
+    pub resolve_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetPaused<'info> {
+    /// Sets the flag. Signed by `RESOLVE_AUTHORITY` or, once the resolve config exists, by
+    /// `threshold` of its authorities (signer plus signing `cosigners`), whatever the stake.
+    pub fn set_paused(
+        &mut self,
+        cosigners: &[AccountInfo<'info>],
+        paused: bool,
+        bumps: &SetPausedBumps,
+    ) -> Result<()> {
+        check_resolve_authority(&self.authority, &self.resolve_config, cosigners, u64::MAX)?;
+        self.pause.set_inner(PauseState {
+            paused,
+            bump: bumps.pause,
+        });
+        Ok(())
+    }
+}
+
+/// Fails with `Paused` if `pause` (the ["pause"] PDA) exists and is set.
+pub fn check_not_paused(pause: &AccountInfo) -> Result<()> {
+    if pause.owner == &crate::ID && !pause.data_is_empty() {
+        let state = PauseState::try_deserialize(&mut &pause.try_borrow_data()?[..])?;
+        require!(!state.paused, EscrowError::Paused);
+    }
+    Ok(())
+}
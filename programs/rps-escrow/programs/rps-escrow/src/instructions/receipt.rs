@@ -0,0 +1,81 @@
+//! Result receipts: `resolve`, `resolve_batch`, `resolve_with_attestation` and `refund` write a
+//! `GameResult` before closing the escrow. Its rent comes out of the escrow's, so the creator gets
+//! back only the difference and neither the authority nor the players pay anything extra.
+//!
+//! Escrows created before receipts existed are smaller than a receipt and cannot fund its rent:
+//! they close without one, so their games can still be settled.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, allocate, assign, Allocate, Assign};
+
+use crate::errors::EscrowError;
+use crate::state::{GameEscrow, GameResult};
+
+/// Creates the `GameResult` of `escrow` (account `game_escrow`) at `game_result`, the
+/// ["game_result", game_escrow] PDA with `bump`. `winner` is None for a refund. Writes nothing
+/// when the escrow's lamports do not cover the receipt's rent (see the module doc).
+pub fn record_result<'info>(
+    game_escrow: &AccountInfo<'info>,
+    escrow: &GameEscrow,
+    game_result: &AccountInfo<'info>,
+    bump: u8,
+    system_program: &AccountInfo<'info>,
+    winner: Option<Pubkey>,
+    fee: u64,
+) -> Result<()> {
+    // Anyone may send lamports to the address, but only this program can give it data.
+    require!(
+        game_result.owner == &system_program::ID && game_result.data_is_empty(),
+        EscrowError::GameIdUsed
+    );
+    let space = 8 + GameResult::INIT_SPACE;
+    let rent = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(game_result.lamports());
+    let Some(escrow_lamports) = game_escrow.lamports().checked_sub(rent) else {
+        msg!("Escrow too small to fund a result receipt, closing without one");
+        return Ok(());
+    };
+    let result_lamports = game_result
+        .lamports()
+        .checked_add(rent)
+        .ok_or(EscrowError::InvalidAmount)?;
+    **game_escrow.try_borrow_mut_lamports()? = escrow_lamports;
+    **game_result.try_borrow_mut_lamports()? = result_lamports;
+
+    let escrow_key = game_escrow.key();
+    let seeds: &[&[&[u8]]] = &[&[b"game_result", escrow_key.as_ref(), &[bump]]];
+    allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Allocate {
+                account_to_allocate: game_result.clone(),
+            },
+            seeds,
+        ),
+        space as u64,
+    )?;
+    assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Assign {
+                account_to_assign: game_result.clone(),
+            },
+            seeds,
+        ),
+        &crate::ID,
+    )?;
+
+    let receipt = GameResult {
+        game_id: escrow.game_id,
+        creator: escrow.creator,
+        joiner: escrow.joiner.ok_or(EscrowError::NoJoiner)?,
+        winner,
+        amount_per_player: escrow.amount_per_player,
+        fee,
+        slot: Clock::get()?.slot,
+        bump,
+    };
+    let mut data = game_result.try_borrow_mut_data()?;
+    receipt.try_serialize(&mut &mut data[..])
+}
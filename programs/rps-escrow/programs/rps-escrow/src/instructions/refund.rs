@@ -1,9 +1,11 @@
 //! Refund game: both the creator and the joiner get their money back in case the game was not played.
+//! A `GameResult` receipt without winner records it.
 
 use anchor_lang::prelude::*;
 use anchor_lang::system_program::{transfer, Transfer};
 
 use crate::errors::EscrowError;
+use crate::instructions::{check_resolve_authority, record_result};
 use crate::state::GameEscrow;
 
 #[derive(Accounts)]
@@ -40,16 +42,27 @@ pub struct Refund<'info> {
     #[account(mut)]
     pub joiner: AccountInfo<'info>,
 
+    /// CHECK: Result receipt, created by the instruction (see `record_result`).
+    #[account(mut, seeds = [b"game_result", game_escrow.key().as_ref()], bump)]
+    pub game_result: UncheckedAccount<'info>,
+
+    /// CHECK: Resolve authority set, if initialized (see `check_resolve_authority`).
+    #[account(seeds = [b"resolve_config"], bump)]
+    pub resolve_config: UncheckedAccount<'info>,
+
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Refund<'info> {
-    /// Refund both the creator and the joiner.
-    pub fn refund(&mut self) -> Result<()> {
-        require!(
-            self.authority.key() == crate::RESOLVE_AUTHORITY,
-            EscrowError::Unauthorized
-        );
+    /// Refund both the creator and the joiner. `cosigners` are the other resolve authorities,
+    /// for high-stake games.
+    pub fn refund(&mut self, cosigners: &[AccountInfo<'info>], bumps: &RefundBumps) -> Result<()> {
+        check_resolve_authority(
+            &self.authority,
+            &self.resolve_config,
+            cosigners,
+            self.game_escrow.amount_per_player,
+        )?;
 
         let joiner_pubkey = self.game_escrow.joiner.ok_or(EscrowError::NoJoiner)?;
 
@@ -94,7 +107,16 @@ impl<'info> Refund<'info> {
             seeds);
                 
         transfer(cpi_ctx_2, self.game_escrow.amount_per_player)?; // send the full amount to the creator
-            
+
+        record_result(
+            &self.game_escrow.to_account_info(),
+            &self.game_escrow,
+            &self.game_result,
+            bumps.game_result,
+            &self.system_program,
+            None,
+            0,
+        )?;
         Ok(()) // close the escrow account and give back rent to the creator
     }
 }
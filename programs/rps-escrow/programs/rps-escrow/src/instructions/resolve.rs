@@ -1,9 +1,11 @@
-//! Resolve game: authority closes the escrow; all SOL (full balance) is sent to the winner.
+//! Resolve game: authority closes the escrow; all SOL (full balance) is sent to the winner, and a
+//! `GameResult` receipt records the outcome.
 
 use anchor_lang::prelude::*;
 use anchor_lang::system_program::{transfer, Transfer};
 
 use crate::errors::EscrowError;
+use crate::instructions::{check_resolve_authority, record_result};
 use crate::state::GameEscrow;
 
 #[derive(Accounts)]
@@ -41,16 +43,33 @@ pub struct Resolve<'info> {
 
     #[account(mut)]
     pub treasury: SystemAccount<'info>,
+
+    /// CHECK: Result receipt, created by the instruction (see `record_result`).
+    #[account(mut, seeds = [b"game_result", game_escrow.key().as_ref()], bump)]
+    pub game_result: UncheckedAccount<'info>,
+
+    /// CHECK: Resolve authority set, if initialized (see `check_resolve_authority`).
+    #[account(seeds = [b"resolve_config"], bump)]
+    pub resolve_config: UncheckedAccount<'info>,
+
     pub system_program: Program<'info, System>
 }
 
 impl<'info> Resolve<'info> {
     /// Resolves the game: validates winner, then close sends all SOL to winner_destination.
-    pub fn resolve(&mut self, winner: Pubkey) -> Result<()> {
-        require!(
-            self.authority.key() == crate::RESOLVE_AUTHORITY,
-            EscrowError::Unauthorized
-        );
+    /// `cosigners` are the other resolve authorities, for high-stake games.
+    pub fn resolve(
+        &mut self,
+        winner: Pubkey,
+        cosigners: &[AccountInfo<'info>],
+        bumps: &ResolveBumps,
+    ) -> Result<()> {
+        check_resolve_authority(
+            &self.authority,
+            &self.resolve_config,
+            cosigners,
+            self.game_escrow.amount_per_player,
+        )?;
         require!(
             winner == self.game_escrow.creator || self.game_escrow.joiner == Some(winner),
             EscrowError::InvalidWinner
@@ -66,13 +85,9 @@ impl<'info> Resolve<'info> {
         );
 
 
-        let payout = self.game_escrow.amount_per_player.checked_mul(2).ok_or(EscrowError::InvalidAmount)?;
         // 3% fee to treasury
-        let treasury_fee = payout
-            .checked_mul(3)
-            .and_then(|v| v.checked_div(100))
+        let (payout, treasury_fee, winner_amount) = crate::resolve_payout(self.game_escrow.amount_per_player)
             .ok_or(EscrowError::InvalidAmount)?;
-        let winner_amount = payout.checked_sub(treasury_fee).ok_or(EscrowError::InvalidAmount)?;
 
         require!(self.vault.lamports() >= payout, EscrowError::InsufficientBalance);
 
@@ -103,6 +118,15 @@ impl<'info> Resolve<'info> {
 
         transfer(cpi_ctx_winner, winner_amount)?; // send (payout - 3% fee) to the winner
 
+        record_result(
+            &self.game_escrow.to_account_info(),
+            &self.game_escrow,
+            &self.game_result,
+            bumps.game_result,
+            &self.system_program,
+            Some(winner),
+            treasury_fee,
+        )?;
 
         Ok(()) // close the escrow account and give back rent to the creator
     }
@@ -0,0 +1,160 @@
+//! Resolve authority set: `init_resolve_config` (by `RESOLVE_AUTHORITY`, once) and
+//! `update_resolve_config` (by `threshold` of the current set).
+//!
+//! Co-signers are signing `remaining_accounts`; [`check_resolve_authority`] applies the set to
+//! `resolve`, `resolve_batch`, `refund` and `set_paused`, [`check_attesters`] to the signers of a
+//! `resolve_with_attestation` verdict.
+
+use anchor_lang::prelude::*;
+
+use crate::errors::EscrowError;
+use crate::state::{ResolveConfig, MAX_RESOLVE_AUTHORITIES};
+
+#[derive(Accounts)]
+pub struct InitResolveConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ResolveConfig::INIT_SPACE,
+        seeds = [b"resolve_config"],
+        bump
+    )]
+    pub resolve_config: Account<'info, ResolveConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateResolveConfig<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"resolve_config"],
+        bump = resolve_config.bump,
+    )]
+    pub resolve_config: Account<'info, ResolveConfig>,
+}
+
+/// 1 <= threshold <= authorities <= MAX_RESOLVE_AUTHORITIES, without duplicates.
+fn validate(authorities: &[Pubkey], threshold: u8) -> Result<()> {
+    let distinct = authorities
+        .iter()
+        .enumerate()
+        .all(|(i, a)| !authorities[..i].contains(a));
+    require!(
+        distinct
+            && authorities.len() <= MAX_RESOLVE_AUTHORITIES
+            && threshold >= 1
+            && usize::from(threshold) <= authorities.len(),
+        EscrowError::InvalidResolveConfig
+    );
+    Ok(())
+}
+
+/// `authority` and the signing `cosigners`.
+fn signer_keys(authority: &Signer, cosigners: &[AccountInfo]) -> Vec<Pubkey> {
+    std::iter::once(authority.key())
+        .chain(cosigners.iter().filter(|c| c.is_signer).map(|c| c.key()))
+        .collect()
+}
+
+impl<'info> InitResolveConfig<'info> {
+    pub fn init_resolve_config(
+        &mut self,
+        authorities: Vec<Pubkey>,
+        threshold: u8,
+        multisig_min_amount: u64,
+        bumps: &InitResolveConfigBumps,
+    ) -> Result<()> {
+        require!(
+            self.authority.key() == crate::RESOLVE_AUTHORITY,
+            EscrowError::Unauthorized
+        );
+        validate(&authorities, threshold)?;
+        self.resolve_config.set_inner(ResolveConfig {
+            authorities,
+            threshold,
+            multisig_min_amount,
+            bump: bumps.resolve_config,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> UpdateResolveConfig<'info> {
+    /// Replaces the set; `threshold` of the current one must sign.
+    pub fn update_resolve_config(
+        &mut self,
+        cosigners: &[AccountInfo<'info>],
+        authorities: Vec<Pubkey>,
+        threshold: u8,
+        multisig_min_amount: u64,
+    ) -> Result<()> {
+        let signers = signer_keys(&self.authority, cosigners);
+        require!(
+            self.resolve_config.approvals(&signers) >= usize::from(self.resolve_config.threshold),
+            EscrowError::NotEnoughSigners
+        );
+        validate(&authorities, threshold)?;
+        self.resolve_config.authorities = authorities;
+        self.resolve_config.threshold = threshold;
+        self.resolve_config.multisig_min_amount = multisig_min_amount;
+        Ok(())
+    }
+}
+
+/// Checks who may resolve or refund a game with `amount_per_player`: `RESOLVE_AUTHORITY` alone,
+/// unless `resolve_config` (the ["resolve_config"] PDA) exists and the stake reaches its
+/// `multisig_min_amount`; then `threshold` of its authorities must sign, as `authority` or as
+/// signing `cosigners`.
+pub fn check_resolve_authority(
+    authority: &Signer,
+    resolve_config: &AccountInfo,
+    cosigners: &[AccountInfo],
+    amount_per_player: u64,
+) -> Result<()> {
+    if resolve_config.owner == &crate::ID && !resolve_config.data_is_empty() {
+        let config = ResolveConfig::try_deserialize(&mut &resolve_config.try_borrow_data()?[..])?;
+        if config.requires_multisig(amount_per_player) {
+            let signers = signer_keys(authority, cosigners);
+            require!(
+                config.approvals(&signers) >= usize::from(config.threshold),
+                EscrowError::NotEnoughSigners
+            );
+            return Ok(());
+        }
+    }
+    require!(
+        authority.key() == crate::RESOLVE_AUTHORITY,
+        EscrowError::Unauthorized
+    );
+    Ok(())
+}
+
+/// As [`check_resolve_authority`], for the keys that signed a verdict: `threshold` of the resolve
+/// config's authorities for a high-stake game, else `RESOLVE_AUTHORITY` among them.
+pub fn check_attesters(
+    resolve_config: &AccountInfo,
+    attesters: &[Pubkey],
+    amount_per_player: u64,
+) -> Result<()> {
+    if resolve_config.owner == &crate::ID && !resolve_config.data_is_empty() {
+        let config = ResolveConfig::try_deserialize(&mut &resolve_config.try_borrow_data()?[..])?;
+        if config.requires_multisig(amount_per_player) {
+            require!(
+                config.approvals(attesters) >= usize::from(config.threshold),
+                EscrowError::NotEnoughSigners
+            );
+            return Ok(());
+        }
+    }
+    require!(
+        attesters.contains(&crate::RESOLVE_AUTHORITY),
+        EscrowError::Unauthorized
+    );
+    Ok(())
+}
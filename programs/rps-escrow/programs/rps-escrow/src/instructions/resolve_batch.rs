@@ -0,0 +1,181 @@
+//! Resolve batch: authority resolves several games in one transaction.
+//!
+//! `remaining_accounts` holds one `[game_escrow, vault, winner_destination, creator, game_result]`
+//! group per winner, all writable. Each group is validated exactly like `Resolve`, then paid out
+//! the same way (3% fee to the treasury, the rest to the winner), its receipt written and its
+//! escrow closed to the creator.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+
+use crate::errors::EscrowError;
+use crate::instructions::{check_resolve_authority, record_result};
+use crate::state::GameEscrow;
+
+/// Accounts per game in `remaining_accounts`.
+pub const RESOLVE_BATCH_GROUP: usize = 5;
+
+#[derive(Accounts)]
+pub struct ResolveBatch<'info> {
+    /// Authority that can resolve (e.g. backend)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub treasury: SystemAccount<'info>,
+
+    /// CHECK: Resolve authority set, if initialized (see `check_resolve_authority`).
+    #[account(seeds = [b"resolve_config"], bump)]
+    pub resolve_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ResolveBatch<'info> {
+    /// Resolves one game per winner; `games` are the matching account groups.
+    pub fn resolve_batch(
+        &mut self,
+        program_id: &Pubkey,
+        games: &'info [AccountInfo<'info>],
+        winners: &[Pubkey],
+    ) -> Result<()> {
+        require!(
+            self.authority.key() == crate::RESOLVE_AUTHORITY,
+            EscrowError::Unauthorized
+        );
+        require!(
+            self.treasury.key() == crate::TREASURY_PUBKEY,
+            EscrowError::InvalidTreasury
+        );
+        require!(
+            !winners.is_empty() && games.len() == winners.len() * RESOLVE_BATCH_GROUP,
+            EscrowError::InvalidBatch
+        );
+
+        for (i, (group, winner)) in games.chunks(RESOLVE_BATCH_GROUP).zip(winners).enumerate() {
+            // The same game twice would be paid out twice.
+            require!(
+                games[..i * RESOLVE_BATCH_GROUP]
+                    .chunks(RESOLVE_BATCH_GROUP)
+                    .all(|g| g[0].key() != group[0].key()),
+                EscrowError::InvalidBatch
+            );
+            self.resolve_one(program_id, group, *winner)?;
+        }
+        Ok(())
+    }
+
+    /// Same checks and payout as `Resolve::resolve`, for one account group.
+    fn resolve_one(
+        &self,
+        program_id: &Pubkey,
+        group: &'info [AccountInfo<'info>],
+        winner: Pubkey,
+    ) -> Result<()> {
+        let [escrow_info, vault_info, winner_destination, creator, game_result] = group else {
+            return err!(EscrowError::InvalidBatch);
+        };
+        let game_escrow: Account<'info, GameEscrow> = Account::try_from(escrow_info)?;
+        let vault: SystemAccount<'info> = SystemAccount::try_from(vault_info)?;
+        // No co-signers in a batch: games that need several signatures go through `resolve`.
+        check_resolve_authority(
+            &self.authority,
+            &self.resolve_config,
+            &[],
+            game_escrow.amount_per_player,
+        )?;
+
+        // Account constraints of `Resolve`.
+        let escrow_address = Pubkey::create_program_address(
+            &[
+                b"game_escrow",
+                game_escrow.creator.as_ref(),
+                game_escrow.game_id.as_ref(),
+                &[game_escrow.bump],
+            ],
+            program_id,
+        )
+        .map_err(|_| ErrorCode::ConstraintSeeds)?;
+        require_keys_eq!(
+            escrow_info.key(),
+            escrow_address,
+            ErrorCode::ConstraintSeeds
+        );
+        require!(!game_escrow.resolved, EscrowError::AlreadyResolved);
+        require!(game_escrow.joiner.is_some(), EscrowError::NoJoiner);
+        require!(
+            game_escrow.creator == creator.key(),
+            EscrowError::UnauthorizedCreator
+        );
+        let vault_address = Pubkey::create_program_address(
+            &[
+                b"vault",
+                escrow_info.key().as_ref(),
+                &[game_escrow.vault_bump],
+            ],
+            program_id,
+        )
+        .map_err(|_| ErrorCode::ConstraintSeeds)?;
+        require_keys_eq!(vault.key(), vault_address, ErrorCode::ConstraintSeeds);
+        let (result_address, result_bump) =
+            Pubkey::find_program_address(&[b"game_result", escrow_info.key().as_ref()], program_id);
+        require_keys_eq!(game_result.key(), result_address, ErrorCode::ConstraintSeeds);
+
+        // Instruction checks of `Resolve`.
+        require!(
+            winner == game_escrow.creator || game_escrow.joiner == Some(winner),
+            EscrowError::InvalidWinner
+        );
+        require!(
+            winner_destination.key() == winner,
+            EscrowError::InvalidWinner
+        );
+
+        // 3% fee to treasury
+        let (payout, treasury_fee, winner_amount) =
+            crate::resolve_payout(game_escrow.amount_per_player)
+                .ok_or(EscrowError::InvalidAmount)?;
+        require!(vault.lamports() >= payout, EscrowError::InsufficientBalance);
+
+        let seeds: &[&[&[u8]]] = &[&[
+            b"vault",
+            &escrow_info.key().to_bytes(),
+            &[game_escrow.vault_bump],
+        ]];
+        transfer(
+            CpiContext::new_with_signer(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: vault.to_account_info(),
+                    to: self.treasury.to_account_info(),
+                },
+                seeds,
+            ),
+            treasury_fee,
+        )?;
+        transfer(
+            CpiContext::new_with_signer(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: vault.to_account_info(),
+                    to: winner_destination.clone(),
+                },
+                seeds,
+            ),
+            winner_amount,
+        )?;
+
+        record_result(
+            escrow_info,
+            &game_escrow,
+            game_result,
+            result_bump,
+            &self.system_program,
+            Some(winner),
+            treasury_fee,
+        )?;
+
+        // close the escrow account and give back rent to the creator
+        game_escrow.close(creator.clone())
+    }
+}
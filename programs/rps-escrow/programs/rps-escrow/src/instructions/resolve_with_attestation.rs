@@ -0,0 +1,206 @@
+//! Resolve with attestation: anyone (typically the winner) submits a verdict the resolve authority
+//! signed off-chain, instead of the authority sending `resolve` itself.
+//!
+//! The verdict is [`verdict_message`] signed with ed25519. The transaction verifies it with an
+//! Ed25519 program instruction placed just before this one, with the signatures, keys and message
+//! inline; this instruction reads it back through the instructions sysvar and checks who signed
+//! which message. Payout, receipt and close are those of `resolve`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::system_program::{transfer, Transfer};
+use solana_instructions_sysvar::{load_current_index_checked, load_instruction_at_checked};
+
+use crate::errors::EscrowError;
+use crate::instructions::{check_attesters, record_result};
+use crate::state::GameEscrow;
+
+/// Prefix of every verdict message, so a verdict signature can never pass for a transaction
+/// signature, nor the reverse.
+pub const VERDICT_DOMAIN: &[u8] = b"rps-escrow:verdict:v1";
+
+/// Message the resolve authority signs to attest that `winner` won game `game_id` at escrow
+/// `game_escrow`, until the unix timestamp `expiry`: the domain, then the fields (`expiry` little
+/// endian).
+pub fn verdict_message(game_escrow: &Pubkey, game_id: &[u8; 16], winner: &Pubkey, expiry: i64) -> Vec<u8> {
+    [
+        VERDICT_DOMAIN,
+        game_escrow.as_ref(),
+        game_id,
+        winner.as_ref(),
+        &expiry.to_le_bytes(),
+    ]
+    .concat()
+}
+
+/// Size of one signature offsets entry of an Ed25519 program instruction.
+const ED25519_OFFSETS_SIZE: usize = 14;
+
+/// Keys that signed `message` in the Ed25519 program instruction `ix`. Every signature must be
+/// over `message`, with signature, key and message in `ix` itself; the Ed25519 program already
+/// failed the transaction if one does not verify.
+fn ed25519_signers(ix: &Instruction, message: &[u8]) -> Result<Vec<Pubkey>> {
+    require_keys_eq!(
+        ix.program_id,
+        solana_sdk_ids::ed25519_program::ID,
+        EscrowError::InvalidAttestation
+    );
+    let data = &ix.data;
+    let count = usize::from(*data.first().ok_or(EscrowError::InvalidAttestation)?);
+    let mut signers = Vec::with_capacity(count);
+    for i in 0..count {
+        let start = 2 + i * ED25519_OFFSETS_SIZE;
+        let offsets = data
+            .get(start..start + ED25519_OFFSETS_SIZE)
+            .ok_or(EscrowError::InvalidAttestation)?;
+        let field = |n: usize| usize::from(u16::from_le_bytes([offsets[2 * n], offsets[2 * n + 1]]));
+        // Signature, public key and message instruction indexes: u16::MAX is this instruction.
+        let inline = [1, 3, 6].iter().all(|&n| field(n) == usize::from(u16::MAX));
+        let (key_offset, message_offset, message_size) = (field(2), field(4), field(5));
+        require!(
+            inline && data.get(message_offset..message_offset + message_size) == Some(message),
+            EscrowError::InvalidAttestation
+        );
+        let key = data
+            .get(key_offset..key_offset + 32)
+            .ok_or(EscrowError::InvalidAttestation)?;
+        signers.push(Pubkey::try_from(key).map_err(|_| EscrowError::InvalidAttestation)?);
+    }
+    Ok(signers)
+}
+
+#[derive(Accounts)]
+pub struct ResolveWithAttestation<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_escrow", game_escrow.creator.as_ref(), game_escrow.game_id.as_ref()],
+        bump = game_escrow.bump,
+        constraint = !game_escrow.resolved @ EscrowError::AlreadyResolved,
+        constraint = game_escrow.joiner.is_some() @ EscrowError::NoJoiner,
+        constraint = game_escrow.creator == creator.key() @ EscrowError::UnauthorizedCreator,
+        close = creator
+    )]
+    pub game_escrow: Account<'info, GameEscrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", game_escrow.key().as_ref()],
+        bump = game_escrow.vault_bump,
+    )]
+    pub vault: SystemAccount<'info>,
+
+    /// CHECK: Validated in instruction: must match the verdict's winner, the creator or joiner.
+    #[account(mut)]
+    pub winner_destination: UncheckedAccount<'info>,
+
+    /// CHECK: Validated in instruction: must match creator pubkey.
+    #[account(mut)]
+    pub creator: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub treasury: SystemAccount<'info>,
+
+    /// CHECK: Result receipt, created by the instruction (see `record_result`).
+    #[account(mut, seeds = [b"game_result", game_escrow.key().as_ref()], bump)]
+    pub game_result: UncheckedAccount<'info>,
+
+    /// CHECK: Resolve authority set, if initialized (see `check_attesters`).
+    #[account(seeds = [b"resolve_config"], bump)]
+    pub resolve_config: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, to read the Ed25519 program instruction.
+    #[account(address = solana_sdk_ids::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ResolveWithAttestation<'info> {
+    /// Checks the verdict (`winner`, `expiry`) and its signers, then pays out like `resolve`.
+    pub fn resolve_with_attestation(
+        &mut self,
+        winner: Pubkey,
+        expiry: i64,
+        bumps: &ResolveWithAttestationBumps,
+    ) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp <= expiry,
+            EscrowError::AttestationExpired
+        );
+        let current = load_current_index_checked(&self.instructions)?;
+        let previous = current.checked_sub(1).ok_or(EscrowError::InvalidAttestation)?;
+        let ed25519_ix = load_instruction_at_checked(usize::from(previous), &self.instructions)?;
+        let message = verdict_message(
+            &self.game_escrow.key(),
+            &self.game_escrow.game_id,
+            &winner,
+            expiry,
+        );
+        let attesters = ed25519_signers(&ed25519_ix, &message)?;
+        check_attesters(
+            &self.resolve_config,
+            &attesters,
+            self.game_escrow.amount_per_player,
+        )?;
+
+        // Instruction checks of `Resolve`.
+        require!(
+            winner == self.game_escrow.creator || self.game_escrow.joiner == Some(winner),
+            EscrowError::InvalidWinner
+        );
+        require!(
+            self.winner_destination.key() == winner,
+            EscrowError::InvalidWinner
+        );
+        require!(
+            self.treasury.key() == crate::TREASURY_PUBKEY,
+            EscrowError::InvalidTreasury
+        );
+
+        // 3% fee to treasury
+        let (payout, treasury_fee, winner_amount) =
+            crate::resolve_payout(self.game_escrow.amount_per_player)
+                .ok_or(EscrowError::InvalidAmount)?;
+        require!(self.vault.lamports() >= payout, EscrowError::InsufficientBalance);
+
+        let seeds: &[&[&[u8]]] = &[&[
+            b"vault",
+            &self.game_escrow.key().to_bytes(),
+            &[self.game_escrow.vault_bump],
+        ]];
+        transfer(
+            CpiContext::new_with_signer(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.vault.to_account_info(),
+                    to: self.treasury.to_account_info(),
+                },
+                seeds,
+            ),
+            treasury_fee,
+        )?;
+        transfer(
+            CpiContext::new_with_signer(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.vault.to_account_info(),
+                    to: self.winner_destination.to_account_info(),
+                },
+                seeds,
+            ),
+            winner_amount,
+        )?;
+
+        record_result(
+            &self.game_escrow.to_account_info(),
+            &self.game_escrow,
+            &self.game_result,
+            bumps.game_result,
+            &self.system_program,
+            Some(winner),
+            treasury_fee,
+        )?;
+
+        Ok(()) // close the escrow account and give back rent to the creator
+    }
+}
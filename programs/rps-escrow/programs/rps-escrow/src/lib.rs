@@ -1,8 +1,17 @@
 //! RPS game escrow program (Anchor).
 //!
 //! - **create_game(game_id, amount)**: Creator initializes escrow for one game (game_id = MongoDB _id as 16 bytes, UUID without hyphens) and deposits `amount` SOL.
+//! - **create_challenge(game_id, amount, opponent)**: Same as create_game, but only `opponent` can join.
 //! - **join_game**: Second player deposits the same amount into the same PDA.
 //! - **resolve(winner)**: Authority resolves the game: all SOL to `winner`, then closes the escrow.
+//!   `resolve`, `resolve_batch`, `resolve_with_attestation` and `refund` leave a `GameResult` receipt at
+//!   ["game_result", game_escrow].
+//! - **resolve_batch(winners)**: `resolve` for several games at once (account groups in `remaining_accounts`).
+//! - **resolve_with_attestation(winner, expiry)**: Anyone resolves the game with a verdict signed off-chain by
+//!   the resolve authority, checked through the Ed25519 program instruction just before it.
+//! - **init_resolve_config / update_resolve_config**: M-of-N resolve authority set; games staking at least
+//!   `multisig_min_amount` need `threshold` of its authorities to sign `resolve` and `refund`.
+//! - **set_paused(paused)**: Emergency pause of `create_game`, `create_challenge` and `join_game`; funds can still leave.
 //!
 //! PDA seeds: ["game_escrow", creator, game_id] so one creator can have multiple games.
 
@@ -21,11 +30,26 @@ pub use errors::*;
 
 declare_id!("F4d4VwBaQrqf5hUZs74XoiVCAo76BpeRSqABxMMzG7kN");
 
-/// Only this pubkey can call `resolve`. Set to your backend authority keypair's public key.
+/// Only this pubkey can call `resolve` (below the multisig stake of the resolve config, if any). Set to your backend authority keypair's public key.
 /// Change by upgrading the program if needed.
 pub const RESOLVE_AUTHORITY: Pubkey = pubkey!("GVEseebBBBL1aykkpM2J3opBHkdGjYSdKNEdR68kfQkF");
 pub const TREASURY_PUBKEY: Pubkey = pubkey!("Ft6kMwkButM1J7iHJBJTb8QFEBuoBPnG1jq83HMRE9mF");
 
+/// Treasury fee taken on resolve, in percent of the pot.
+pub const TREASURY_FEE_PERCENT: u64 = 3;
+
+/// Treasury fee on a pot of `pot` lamports (integer division). None on overflow.
+pub fn treasury_fee(pot: u64) -> Option<u64> {
+    pot.checked_mul(TREASURY_FEE_PERCENT)?.checked_div(100)
+}
+
+/// Resolve split of a game with `amount_per_player`: (pot, treasury fee, winner amount).
+pub fn resolve_payout(amount_per_player: u64) -> Option<(u64, u64, u64)> {
+    let pot = amount_per_player.checked_mul(2)?;
+    let fee = treasury_fee(pot)?;
+    Some((pot, fee, pot.checked_sub(fee)?))
+}
+
 
 #[program]
 pub mod rps_escrow {
@@ -34,7 +58,18 @@ pub mod rps_escrow {
     /// Creator creates a game escrow and deposits `amount` lamports. `game_id` = 16 bytes (UUID without hyphens, hex).
     pub fn create_game(ctx: Context<CreateGame>, game_id: [u8; 16], amount: u64) -> Result<()> {
         ctx.accounts
-            .create_and_deposit(game_id, amount, &ctx.bumps)
+            .create_and_deposit(game_id, amount, None, &ctx.bumps)
+    }
+
+    /// Creator challenges `opponent` directly: same as `create_game`, but only `opponent` can join.
+    pub fn create_challenge(
+        ctx: Context<CreateGame>,
+        game_id: [u8; 16],
+        amount: u64,
+        opponent: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts
+            .create_and_deposit(game_id, amount, Some(opponent), &ctx.bumps)
     }
 
     /// Joiner deposits the same amount as the creator into the escrow.
@@ -43,8 +78,30 @@ pub mod rps_escrow {
     }
 
     /// Authority resolves the game: full payout to winner, then closes the escrow.
-    pub fn resolve(ctx: Context<Resolve>, winner: Pubkey) -> Result<()> {
-        ctx.accounts.resolve(winner)
+    /// Co-signing authorities of a high-stake game go in `remaining_accounts`.
+    pub fn resolve<'info>(ctx: Context<'_, '_, 'info, 'info, Resolve<'info>>, winner: Pubkey) -> Result<()> {
+        ctx.accounts.resolve(winner, ctx.remaining_accounts, &ctx.bumps)
+    }
+
+    /// Authority resolves one game per winner; `remaining_accounts` holds a
+    /// `[game_escrow, vault, winner_destination, creator, game_result]` group per game, validated like `resolve`.
+    pub fn resolve_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ResolveBatch<'info>>,
+        winners: Vec<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts
+            .resolve_batch(ctx.program_id, ctx.remaining_accounts, &winners)
+    }
+
+    /// Anyone submits the resolve authority's verdict that `winner` won, valid until `expiry` (unix
+    /// timestamp): same payout as `resolve`. The verdict's Ed25519 program instruction comes just before.
+    pub fn resolve_with_attestation(
+        ctx: Context<ResolveWithAttestation>,
+        winner: Pubkey,
+        expiry: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .resolve_with_attestation(winner, expiry, &ctx.bumps)
     }
 
     /// Creator cancels the game: gets his money back in case no game was played and there is no joiner.
@@ -52,7 +109,40 @@ pub mod rps_escrow {
         ctx.accounts.cancel()
     }
 
-    pub fn refund(ctx: Context<Refund>) -> Result<()> {
-        ctx.accounts.refund()
+    /// Authority refunds both players; co-signing authorities of a high-stake game go in `remaining_accounts`.
+    pub fn refund<'info>(ctx: Context<'_, '_, 'info, 'info, Refund<'info>>) -> Result<()> {
+        ctx.accounts.refund(ctx.remaining_accounts, &ctx.bumps)
+    }
+
+    /// `RESOLVE_AUTHORITY` creates the resolve authority set (once).
+    pub fn init_resolve_config(
+        ctx: Context<InitResolveConfig>,
+        authorities: Vec<Pubkey>,
+        threshold: u8,
+        multisig_min_amount: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .init_resolve_config(authorities, threshold, multisig_min_amount, &ctx.bumps)
+    }
+
+    /// `threshold` of the current authorities (signer plus `remaining_accounts`) replace the set.
+    pub fn update_resolve_config<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateResolveConfig<'info>>,
+        authorities: Vec<Pubkey>,
+        threshold: u8,
+        multisig_min_amount: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .update_resolve_config(ctx.remaining_accounts, authorities, threshold, multisig_min_amount)
+    }
+
+    /// Resolve authority (or `threshold` of the resolve config, co-signers in `remaining_accounts`)
+    /// pauses or reopens new games and joins.
+    pub fn set_paused<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SetPaused<'info>>,
+        paused: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .set_paused(ctx.remaining_accounts, paused, &ctx.bumps)
     }
 }
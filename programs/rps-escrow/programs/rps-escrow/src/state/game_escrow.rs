@@ -12,6 +12,38 @@ pub struct GameEscrow {
     pub amount_per_player: u64,
     pub bump: u8,
     pub vault_bump:u8,
+    /// Never set by an instruction: resolve closes the escrow. The outcome is in `GameResult`.
     pub resolved: bool,
+    /// Never set by an instruction, like `resolved`.
     pub winner: Option<Pubkey>,
+    // The challenged wallet follows these fields, outside the Borsh layout (see `opponent`).
+}
+
+impl GameEscrow {
+    /// Offset of the challenged wallet (`Option<Pubkey>`), right after the fields. Escrows created
+    /// before challenges end there and still decode.
+    pub const OPPONENT_OFFSET: usize = 8 + GameEscrow::INIT_SPACE;
+
+    /// Size of an escrow account: discriminator, fields and challenged wallet.
+    pub const SPACE: usize = Self::OPPONENT_OFFSET + 1 + 32;
+
+    /// Challenged wallet of a direct challenge in escrow account `data`: only this key may join.
+    /// None = anyone may join, as for every escrow too short to hold one.
+    pub fn opponent(data: &[u8]) -> Result<Option<Pubkey>> {
+        match data.get(Self::OPPONENT_OFFSET..Self::SPACE) {
+            None | Some([0, ..]) => Ok(None),
+            Some([1, key @ ..]) => Ok(Some(Pubkey::try_from(key).unwrap())),
+            Some(_) => err!(ErrorCode::AccountDidNotDeserialize),
+        }
+    }
+
+    /// Writes the challenged wallet into escrow account `data` of [`GameEscrow::SPACE`] bytes.
+    pub fn write_opponent(data: &mut [u8], opponent: Option<Pubkey>) -> Result<()> {
+        let mut slot = data
+            .get_mut(Self::OPPONENT_OFFSET..Self::SPACE)
+            .ok_or(ErrorCode::AccountDidNotSerialize)?;
+        opponent
+            .serialize(&mut slot)
+            .map_err(|_| ErrorCode::AccountDidNotSerialize.into())
+    }
 }
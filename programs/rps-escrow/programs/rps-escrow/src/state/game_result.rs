@@ -0,0 +1,24 @@
+//! Result receipt of a finished game.
+
+use anchor_lang::prelude::*;
+
+/// PDA ["game_result", game_escrow]. Written by `resolve`, `resolve_batch`,
+/// `resolve_with_attestation` and `refund` as the escrow closes, and never closed, so anyone can
+/// audit who won after the payout. Escrows created before receipts close without one.
+#[account]
+#[derive(InitSpace)]
+pub struct GameResult {
+    /// Same as `GameEscrow::game_id`.
+    pub game_id: [u8; 16],
+    pub creator: Pubkey,
+    pub joiner: Pubkey,
+    /// None when both deposits were refunded.
+    pub winner: Option<Pubkey>,
+    /// Stake of each player, in lamports.
+    pub amount_per_player: u64,
+    /// Treasury fee taken from the pot (0 on refund).
+    pub fee: u64,
+    /// Slot of the resolve or refund.
+    pub slot: u64,
+    pub bump: u8,
+}
@@ -1,4 +1,10 @@
 //! Program state accounts.
 
 pub mod game_escrow;
+pub mod game_result;
+pub mod pause_state;
+pub mod resolve_config;
 pub use game_escrow::GameEscrow;
+pub use game_result::GameResult;
+pub use pause_state::PauseState;
+pub use resolve_config::{ResolveConfig, MAX_RESOLVE_AUTHORITIES};
@@ -0,0 +1,12 @@
+//! Emergency pause switch.
+
+use anchor_lang::prelude::*;
+
+/// PDA ["pause"]. While `paused`, `create_game`, `create_challenge` and `join_game` fail; `cancel`,
+/// `refund` and `resolve` still let funds leave. Until it exists the escrow is open.
+#[account]
+#[derive(InitSpace)]
+pub struct PauseState {
+    pub paused: bool,
+    pub bump: u8,
+}
@@ -0,0 +1,32 @@
+//! Resolve authority set: M-of-N signatures for high-stake games.
+
+use anchor_lang::prelude::*;
+
+/// Max keys in the resolve authority set.
+pub const MAX_RESOLVE_AUTHORITIES: usize = 5;
+
+/// PDA ["resolve_config"]. Until it exists every game is resolved and refunded by
+/// `RESOLVE_AUTHORITY` alone.
+#[account]
+#[derive(InitSpace)]
+pub struct ResolveConfig {
+    #[max_len(MAX_RESOLVE_AUTHORITIES)]
+    pub authorities: Vec<Pubkey>,
+    /// Signatures from `authorities` needed at or above `multisig_min_amount`.
+    pub threshold: u8,
+    /// Stake per player (lamports) from which `resolve` and `refund` need `threshold` signatures.
+    pub multisig_min_amount: u64,
+    pub bump: u8,
+}
+
+impl ResolveConfig {
+    /// True if a game with `amount_per_player` needs `threshold` signatures.
+    pub fn requires_multisig(&self, amount_per_player: u64) -> bool {
+        amount_per_player >= self.multisig_min_amount
+    }
+
+    /// Number of distinct `authorities` among `signers`.
+    pub fn approvals(&self, signers: &[Pubkey]) -> usize {
+        self.authorities.iter().filter(|a| signers.contains(a)).count()
+    }
+}
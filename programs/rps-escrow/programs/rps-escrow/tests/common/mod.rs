@@ -0,0 +1,90 @@
+//! LiteSVM setup shared by the program tests.
+//!
+//! Tests load the SBF build at `target/deploy/rps_escrow.so` (`anchor build` or `cargo build-sbf`),
+//! or the file in `RPS_ESCROW_SO`; no validator is needed, and a plain `cargo test` runs them.
+//! Without the binary each test is skipped with a warning, except in CI (`CI` set), where a
+//! missing binary fails them.
+//!
+//! Signature verification is off: `resolve` and `refund` are signed by the hard-coded
+//! `RESOLVE_AUTHORITY`, whose key is not in the repository. The program only sees signer flags.
+//! The Ed25519 program still verifies the verdicts of `resolve_with_attestation`, so those are
+//! signed by test authorities of a resolve config.
+//! A separate fee payer pays every transaction fee so player balances move by exact amounts.
+
+use litesvm::types::TransactionResult;
+use litesvm::LiteSVM;
+use rps_escrow_client::{ID, TREASURY_PUBKEY};
+use solana_sdk::{
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+pub const SOL: u64 = 1_000_000_000;
+
+/// The program binary. None (and a skip warning) if it has not been built; in CI, panics instead.
+pub fn load_program() -> Option<Vec<u8>> {
+    let path = std::env::var("RPS_ESCROW_SO").unwrap_or_else(|_| {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy/rps_escrow.so").to_string()
+    });
+    match std::fs::read(&path) {
+        Ok(program) => Some(program),
+        Err(e) => {
+            let message = format!(
+                "cannot read the program binary {}: {} (run `cargo build-sbf` or `anchor build` in \
+                 programs/rps-escrow, or point RPS_ESCROW_SO at it)",
+                path, e
+            );
+            if std::env::var_os("CI").is_some() {
+                panic!("{}", message);
+            }
+            eprintln!("warning: test skipped: {}", message);
+            None
+        }
+    }
+}
+
+/// SVM with `program` deployed at the program id and a rent-exempt treasury, so a small fee can
+/// land in it.
+pub fn new_svm(program: &[u8]) -> LiteSVM {
+    let mut svm = LiteSVM::new().with_sigverify(false);
+    svm.add_program(ID, program).expect("program loads");
+    svm.airdrop(&TREASURY_PUBKEY, SOL).unwrap();
+    svm
+}
+
+/// Sends `ix` paid by `fee_payer` and signed by `signers` (other signers, such as the resolve
+/// authority, get an empty signature). The blockhash is expired afterwards so the same
+/// instruction can be sent again.
+pub fn send(svm: &mut LiteSVM, fee_payer: &Keypair, ix: Instruction, signers: &[&Keypair]) -> TransactionResult {
+    send_all(svm, fee_payer, &[ix], signers)
+}
+
+/// [`send`] for several instructions in one transaction.
+pub fn send_all(
+    svm: &mut LiteSVM,
+    fee_payer: &Keypair,
+    ixs: &[Instruction],
+    signers: &[&Keypair],
+) -> TransactionResult {
+    let blockhash = svm.latest_blockhash();
+    let message = Message::new_with_blockhash(ixs, Some(&fee_payer.pubkey()), &blockhash);
+    let mut tx = Transaction::new_unsigned(message);
+    let keypairs: Vec<&Keypair> = std::iter::once(fee_payer).chain(signers.iter().copied()).collect();
+    let pubkeys: Vec<Pubkey> = keypairs.iter().map(|k| k.pubkey()).collect();
+    let positions = tx.get_signing_keypair_positions(&pubkeys).unwrap();
+    for (keypair, position) in keypairs.into_iter().zip(positions) {
+        let position = position.expect("keypair is a required signer");
+        tx.signatures[position] = keypair.sign_message(&tx.message_data());
+    }
+    let result = svm.send_transaction(tx);
+    svm.expire_blockhash();
+    result
+}
+
+/// Lamports of `pubkey`, 0 if the account does not exist.
+pub fn balance(svm: &LiteSVM, pubkey: &Pubkey) -> u64 {
+    svm.get_balance(pubkey).unwrap_or(0)
+}
@@ -0,0 +1,1042 @@
+//! In-process tests of the rps-escrow program on LiteSVM: every `EscrowError` path, lamport
+//! accounting of each instruction, and independence from the clock. See `common` for the setup.
+
+// LiteSVM's own `TransactionResult`.
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use anchor_lang::{AccountSerialize, InstructionData, ToAccountMetas};
+use litesvm::types::TransactionResult;
+use litesvm::LiteSVM;
+use rps_escrow::instruction as ix_args;
+use rps_escrow_client::errors::escrow_error;
+use rps_escrow_client::fees::{resolve_payout, MIN_BET_LAMPORTS};
+use rps_escrow_client::pda::{game_addresses, game_result_pda, resolve_config_pda};
+use rps_escrow_client::state::{
+    decode_game_escrow, decode_game_escrow_opponent, decode_game_result, decode_resolve_config, GAME_ESCROW_SPACE,
+    GAME_RESULT_SPACE,
+};
+use rps_escrow_client::instructions::BatchGame;
+use rps_escrow_client::{
+    instructions, EscrowError, GameEscrow, GameResult, ID, RESOLVE_AUTHORITY, TREASURY_PUBKEY,
+};
+use solana_sdk::{
+    clock::Clock,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+use common::SOL;
+
+const GAME_ID: [u8; 16] = [7; 16];
+/// Pot of 2_000_066: the 3% fee (60_001.98) rounds down.
+const AMOUNT: u64 = 1_000_033;
+
+struct Env {
+    svm: LiteSVM,
+    fee_payer: Keypair,
+    creator: Keypair,
+    joiner: Keypair,
+}
+
+impl Env {
+    /// Fresh SVM with the program deployed and funded players. None (test skipped) without the
+    /// program binary outside CI (see [`common::load_program`]).
+    fn new() -> Option<Self> {
+        let mut env = Self {
+            svm: common::new_svm(&common::load_program()?),
+            fee_payer: Keypair::new(),
+            creator: Keypair::new(),
+            joiner: Keypair::new(),
+        };
+        for pubkey in [env.fee_payer.pubkey(), env.creator.pubkey(), env.joiner.pubkey()] {
+            env.svm.airdrop(&pubkey, 10 * SOL).unwrap();
+        }
+        // Pays the rent of the resolve config and the pause flag.
+        env.svm.airdrop(&RESOLVE_AUTHORITY, SOL).unwrap();
+        Some(env)
+    }
+
+    fn send(&mut self, ix: Instruction, signers: &[&Keypair]) -> TransactionResult {
+        common::send(&mut self.svm, &self.fee_payer, ix, signers)
+    }
+
+    fn balance(&self, pubkey: &Pubkey) -> u64 {
+        common::balance(&self.svm, pubkey)
+    }
+
+    fn addresses(&self) -> (Pubkey, Pubkey) {
+        game_addresses(&ID, &self.creator.pubkey(), &GAME_ID)
+    }
+
+    /// The escrow account, None once closed.
+    fn escrow(&self) -> Option<GameEscrow> {
+        let account = self.svm.get_account(&self.addresses().0).filter(|a| a.lamports > 0)?;
+        Some(decode_game_escrow(&account.data).unwrap())
+    }
+
+    /// Challenged wallet of the open escrow.
+    fn opponent(&self) -> Option<Pubkey> {
+        let account = self.svm.get_account(&self.addresses().0).unwrap();
+        decode_game_escrow_opponent(&account.data).unwrap()
+    }
+
+    /// The result receipt of `creator`'s game `game_id`, None until resolved or refunded.
+    fn receipt(&self, game_id: [u8; 16]) -> Option<GameResult> {
+        let game_escrow = game_addresses(&ID, &self.creator.pubkey(), &game_id).0;
+        let account = self.svm.get_account(&game_result_pda(&ID, &game_escrow).0)?;
+        (account.owner == ID).then(|| decode_game_result(&account.data).unwrap())
+    }
+
+    /// Rewrites the escrow account, to reach states no instruction produces.
+    fn patch_escrow(&mut self, patch: impl FnOnce(&mut GameEscrow)) {
+        let address = self.addresses().0;
+        let mut account = self.svm.get_account(&address).unwrap();
+        let mut escrow = decode_game_escrow(&account.data).unwrap();
+        patch(&mut escrow);
+        let mut data = Vec::with_capacity(GAME_ESCROW_SPACE);
+        escrow.try_serialize(&mut data).unwrap();
+        account.data[..data.len()].copy_from_slice(&data);
+        self.svm.set_account(address, account).unwrap();
+    }
+
+    fn create(&mut self, amount: u64) -> TransactionResult {
+        let creator = self.creator.insecure_clone();
+        self.send(instructions::create_game(&ID, &creator.pubkey(), GAME_ID, amount), &[&creator])
+    }
+
+    fn create_challenge(&mut self, opponent: &Pubkey) -> TransactionResult {
+        let creator = self.creator.insecure_clone();
+        let ix = instructions::create_challenge(&ID, &creator.pubkey(), GAME_ID, AMOUNT, opponent);
+        self.send(ix, &[&creator])
+    }
+
+    fn join_as(&mut self, joiner: &Keypair) -> TransactionResult {
+        let ix = instructions::join_game(&ID, &joiner.pubkey(), &self.creator.pubkey(), GAME_ID);
+        self.send(ix, &[joiner])
+    }
+
+    fn join(&mut self) -> TransactionResult {
+        let joiner = self.joiner.insecure_clone();
+        self.join_as(&joiner)
+    }
+
+    fn cancel_as(&mut self, signer: &Keypair) -> TransactionResult {
+        let (game_escrow, vault) = self.addresses();
+        let ix = Instruction {
+            program_id: ID,
+            accounts: rps_escrow::accounts::Cancel {
+                creator: signer.pubkey(),
+                game_escrow,
+                vault,
+                system_program: anchor_lang::system_program::ID,
+            }
+            .to_account_metas(None),
+            data: ix_args::Cancel {}.data(),
+        };
+        self.send(ix, &[signer])
+    }
+
+    fn cancel(&mut self) -> TransactionResult {
+        let creator = self.creator.insecure_clone();
+        self.cancel_as(&creator)
+    }
+
+    /// `resolve` with explicit accounts, so each can be wrong on purpose.
+    fn resolve_with(
+        &mut self,
+        authority: &Pubkey,
+        winner: &Pubkey,
+        winner_destination: &Pubkey,
+        creator: &Pubkey,
+        treasury: &Pubkey,
+    ) -> TransactionResult {
+        let (game_escrow, vault) = self.addresses();
+        let ix = Instruction {
+            program_id: ID,
+            accounts: rps_escrow::accounts::Resolve {
+                authority: *authority,
+                game_escrow,
+                vault,
+                winner_destination: *winner_destination,
+                creator: *creator,
+                treasury: *treasury,
+                game_result: game_result_pda(&ID, &game_escrow).0,
+                resolve_config: resolve_config_pda(&ID).0,
+                system_program: anchor_lang::system_program::ID,
+            }
+            .to_account_metas(None),
+            data: ix_args::Resolve { winner: *winner }.data(),
+        };
+        self.send(ix, &[])
+    }
+
+    fn resolve(&mut self, winner: &Pubkey) -> TransactionResult {
+        let ix = instructions::resolve(
+            &ID,
+            &RESOLVE_AUTHORITY,
+            &self.creator.pubkey(),
+            GAME_ID,
+            winner,
+            &TREASURY_PUBKEY,
+        );
+        self.send(ix, &[])
+    }
+
+    fn refund_with(&mut self, authority: &Pubkey, creator: &Pubkey, joiner: &Pubkey) -> TransactionResult {
+        let (game_escrow, vault) = self.addresses();
+        let ix = Instruction {
+            program_id: ID,
+            accounts: rps_escrow::accounts::Refund {
+                authority: *authority,
+                game_escrow,
+                vault,
+                creator: *creator,
+                joiner: *joiner,
+                game_result: game_result_pda(&ID, &game_escrow).0,
+                resolve_config: resolve_config_pda(&ID).0,
+                system_program: anchor_lang::system_program::ID,
+            }
+            .to_account_metas(None),
+            data: ix_args::Refund {}.data(),
+        };
+        self.send(ix, &[])
+    }
+
+    fn refund(&mut self) -> TransactionResult {
+        let ix = instructions::refund(
+            &ID,
+            &RESOLVE_AUTHORITY,
+            &self.creator.pubkey(),
+            &self.joiner.pubkey(),
+            GAME_ID,
+        );
+        self.send(ix, &[])
+    }
+
+    /// Creates and joins game `game_id` of the creator, with the usual players and `AMOUNT`.
+    fn open_game(&mut self, game_id: [u8; 16]) {
+        let (creator, joiner) = (self.creator.insecure_clone(), self.joiner.insecure_clone());
+        let ix = instructions::create_game(&ID, &creator.pubkey(), game_id, AMOUNT);
+        self.send(ix, &[&creator]).unwrap();
+        let ix = instructions::join_game(&ID, &joiner.pubkey(), &creator.pubkey(), game_id);
+        self.send(ix, &[&joiner]).unwrap();
+    }
+
+    fn game(&self, game_id: [u8; 16], winner: &Pubkey) -> BatchGame {
+        BatchGame {
+            creator: self.creator.pubkey(),
+            game_id,
+            winner: *winner,
+        }
+    }
+
+    fn resolve_batch(&mut self, games: &[BatchGame]) -> TransactionResult {
+        let ix = instructions::resolve_batch(&ID, &RESOLVE_AUTHORITY, &TREASURY_PUBKEY, games);
+        self.send(ix, &[])
+    }
+
+    /// Resolves game `game_id` to the joiner, signed by the resolve authority and `cosigners`.
+    fn resolve_signed(&mut self, game_id: [u8; 16], cosigners: &[Pubkey]) -> TransactionResult {
+        let (creator, joiner) = (self.creator.pubkey(), self.joiner.pubkey());
+        let ix = instructions::resolve(&ID, &RESOLVE_AUTHORITY, &creator, game_id, &joiner, &TREASURY_PUBKEY);
+        self.send(instructions::with_cosigners(ix, cosigners), &[])
+    }
+
+    /// Refunds game `game_id`, signed by the resolve authority and `cosigners`.
+    fn refund_signed(&mut self, game_id: [u8; 16], cosigners: &[Pubkey]) -> TransactionResult {
+        let (creator, joiner) = (self.creator.pubkey(), self.joiner.pubkey());
+        let ix = instructions::refund(&ID, &RESOLVE_AUTHORITY, &creator, &joiner, game_id);
+        self.send(instructions::with_cosigners(ix, cosigners), &[])
+    }
+
+    fn init_resolve_config(
+        &mut self,
+        authorities: &[Pubkey],
+        threshold: u8,
+        multisig_min_amount: u64,
+    ) -> TransactionResult {
+        let ix = instructions::init_resolve_config(
+            &ID,
+            &RESOLVE_AUTHORITY,
+            authorities.to_vec(),
+            threshold,
+            multisig_min_amount,
+        );
+        self.send(ix, &[])
+    }
+
+    /// The verdict that game `game_id` went to `winner`, valid until `expiry`, signed by
+    /// `attesters` in an Ed25519 program instruction, then `resolve_with_attestation`.
+    fn verdict(
+        &self,
+        game_id: [u8; 16],
+        winner: &Pubkey,
+        expiry: i64,
+        attesters: &[&Keypair],
+    ) -> [Instruction; 2] {
+        let game_escrow = game_addresses(&ID, &self.creator.pubkey(), &game_id).0;
+        let message = instructions::verdict_message(&game_escrow, &game_id, winner, expiry);
+        let signatures: Vec<(Pubkey, [u8; 64])> = attesters
+            .iter()
+            .map(|k| (k.pubkey(), k.sign_message(&message).into()))
+            .collect();
+        [
+            instructions::ed25519_verdict(&message, &signatures),
+            instructions::resolve_with_attestation(
+                &ID,
+                &self.creator.pubkey(),
+                game_id,
+                winner,
+                &TREASURY_PUBKEY,
+                expiry,
+            ),
+        ]
+    }
+
+    /// Submits `ixs` signed by the fee payer alone, as any wallet could.
+    fn submit(&mut self, ixs: &[Instruction]) -> TransactionResult {
+        common::send_all(&mut self.svm, &self.fee_payer, ixs, &[])
+    }
+
+    fn now(&self) -> i64 {
+        self.svm.get_sysvar::<Clock>().unix_timestamp
+    }
+
+    fn set_paused(&mut self, paused: bool) -> TransactionResult {
+        self.send(instructions::set_paused(&ID, &RESOLVE_AUTHORITY, paused), &[])
+    }
+
+    fn escrow_rent(&self) -> u64 {
+        self.svm.minimum_balance_for_rent_exemption(GAME_ESCROW_SPACE)
+    }
+
+    /// Escrow rent returned to the creator by resolve and refund, after the receipt's.
+    fn rent_back(&self) -> u64 {
+        self.escrow_rent() - self.svm.minimum_balance_for_rent_exemption(GAME_RESULT_SPACE)
+    }
+}
+
+/// Asserts that `result` failed with `expected`.
+#[track_caller]
+fn assert_error(result: TransactionResult, expected: EscrowError) {
+    assert_error_at(result, 0, expected)
+}
+
+/// Asserts that instruction `index` of the transaction failed with `expected`.
+#[track_caller]
+fn assert_error_at(result: TransactionResult, index: u8, expected: EscrowError) {
+    let failed = result.expect_err("transaction should fail");
+    let code = match failed.err {
+        TransactionError::InstructionError(i, InstructionError::Custom(code)) if i == index => code,
+        other => panic!("expected {:?}, got {:?}\n{:#?}", expected, other, failed.meta.logs),
+    };
+    assert_eq!(
+        escrow_error(code).map(|e| e.name()),
+        Some(expected.name()),
+        "code {}\n{:#?}",
+        code,
+        failed.meta.logs
+    );
+}
+
+// --- Lamport accounting ---
+
+#[test]
+fn create_moves_amount_to_vault_and_rent_to_escrow() {
+    let Some(mut env) = Env::new() else { return };
+    let creator_before = env.balance(&env.creator.pubkey());
+
+    env.create(AMOUNT).unwrap();
+
+    let (game_escrow, vault) = env.addresses();
+    assert_eq!(env.balance(&vault), AMOUNT);
+    assert_eq!(env.balance(&game_escrow), env.escrow_rent());
+    assert_eq!(
+        env.balance(&env.creator.pubkey()),
+        creator_before - AMOUNT - env.escrow_rent()
+    );
+    let escrow = env.escrow().unwrap();
+    assert_eq!(escrow.creator, env.creator.pubkey());
+    assert_eq!(escrow.game_id, GAME_ID);
+    assert_eq!(escrow.amount_per_player, AMOUNT);
+    assert_eq!(escrow.joiner, None);
+    assert_eq!(env.opponent(), None);
+    assert!(!escrow.resolved);
+}
+
+#[test]
+fn join_deposits_the_same_amount() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    let joiner_before = env.balance(&env.joiner.pubkey());
+
+    env.join().unwrap();
+
+    assert_eq!(env.balance(&env.addresses().1), 2 * AMOUNT);
+    assert_eq!(env.balance(&env.joiner.pubkey()), joiner_before - AMOUNT);
+    assert_eq!(env.escrow().unwrap().joiner, Some(env.joiner.pubkey()));
+}
+
+#[test]
+fn resolve_pays_winner_treasury_and_rent_to_creator() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    env.join().unwrap();
+    let (game_escrow, vault) = env.addresses();
+    let rent = env.rent_back();
+    let creator_before = env.balance(&env.creator.pubkey());
+    let joiner_before = env.balance(&env.joiner.pubkey());
+    let treasury_before = env.balance(&TREASURY_PUBKEY);
+
+    let joiner = env.joiner.pubkey();
+    env.resolve(&joiner).unwrap();
+
+    let payout = resolve_payout(AMOUNT).unwrap();
+    assert_eq!(payout.pot, 2_000_066);
+    assert_eq!(payout.treasury_fee, 60_001);
+    assert_eq!(payout.winner_amount, 1_940_065);
+    assert_eq!(env.balance(&TREASURY_PUBKEY), treasury_before + payout.treasury_fee);
+    assert_eq!(env.balance(&joiner), joiner_before + payout.winner_amount);
+    assert_eq!(env.balance(&env.creator.pubkey()), creator_before + rent);
+    assert_eq!(env.balance(&vault), 0);
+    assert_eq!(env.balance(&game_escrow), 0);
+    assert!(env.escrow().is_none());
+    let receipt = env.receipt(GAME_ID).unwrap();
+    assert_eq!(receipt.game_id, GAME_ID);
+    assert_eq!((receipt.creator, receipt.joiner), (env.creator.pubkey(), joiner));
+    assert_eq!(receipt.winner, Some(joiner));
+    assert_eq!((receipt.amount_per_player, receipt.fee), (AMOUNT, payout.treasury_fee));
+    assert_eq!(receipt.slot, env.svm.get_sysvar::<Clock>().slot);
+}
+
+#[test]
+fn resolve_to_creator_adds_rent_refund_to_payout() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    env.join().unwrap();
+    let creator_before = env.balance(&env.creator.pubkey());
+
+    let creator = env.creator.pubkey();
+    env.resolve(&creator).unwrap();
+
+    let payout = resolve_payout(AMOUNT).unwrap();
+    assert_eq!(
+        env.balance(&creator),
+        creator_before + payout.winner_amount + env.rent_back()
+    );
+    assert_eq!(env.balance(&env.addresses().1), 0);
+}
+
+#[test]
+fn resolve_at_the_minimum_bet_empties_the_vault() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(MIN_BET_LAMPORTS).unwrap();
+    env.join().unwrap();
+    let treasury_before = env.balance(&TREASURY_PUBKEY);
+    let joiner_before = env.balance(&env.joiner.pubkey());
+
+    let joiner = env.joiner.pubkey();
+    env.resolve(&joiner).unwrap();
+
+    // 3% of 2_000_000 is exact; the winner gets the rest, nothing stays in the vault.
+    assert_eq!(env.balance(&TREASURY_PUBKEY), treasury_before + 60_000);
+    assert_eq!(env.balance(&joiner), joiner_before + 1_940_000);
+    assert_eq!(env.balance(&env.addresses().1), 0);
+}
+
+#[test]
+fn resolve_batch_pays_out_every_game() {
+    let Some(mut env) = Env::new() else { return };
+    const OTHER_ID: [u8; 16] = [8; 16];
+    env.open_game(GAME_ID);
+    env.open_game(OTHER_ID);
+    let (creator, joiner) = (env.creator.pubkey(), env.joiner.pubkey());
+    let creator_before = env.balance(&creator);
+    let joiner_before = env.balance(&joiner);
+    let treasury_before = env.balance(&TREASURY_PUBKEY);
+
+    let games = [env.game(GAME_ID, &joiner), env.game(OTHER_ID, &creator)];
+    env.resolve_batch(&games).unwrap();
+
+    let payout = resolve_payout(AMOUNT).unwrap();
+    assert_eq!(env.balance(&TREASURY_PUBKEY), treasury_before + 2 * payout.treasury_fee);
+    assert_eq!(env.balance(&joiner), joiner_before + payout.winner_amount);
+    assert_eq!(
+        env.balance(&creator),
+        creator_before + payout.winner_amount + 2 * env.rent_back()
+    );
+    for game_id in [GAME_ID, OTHER_ID] {
+        let (game_escrow, vault) = game_addresses(&ID, &creator, &game_id);
+        assert_eq!(env.balance(&game_escrow), 0);
+        assert_eq!(env.balance(&vault), 0);
+    }
+    assert_eq!(env.receipt(GAME_ID).unwrap().winner, Some(joiner));
+    assert_eq!(env.receipt(OTHER_ID).unwrap().winner, Some(creator));
+}
+
+#[test]
+fn resolve_batch_fails_as_a_whole() {
+    let Some(mut env) = Env::new() else { return };
+    const OTHER_ID: [u8; 16] = [8; 16];
+    env.open_game(GAME_ID);
+    env.open_game(OTHER_ID);
+    let joiner = env.joiner.pubkey();
+    let stranger = Pubkey::new_unique();
+    let vault_before = env.balance(&env.addresses().1);
+
+    // The second game is checked like `resolve`; the first one stays unpaid.
+    let games = [env.game(GAME_ID, &joiner), env.game(OTHER_ID, &stranger)];
+    assert_error(env.resolve_batch(&games), EscrowError::InvalidWinner);
+    assert_eq!(env.balance(&env.addresses().1), vault_before);
+    assert!(env.escrow().is_some());
+
+    let games = [env.game(GAME_ID, &joiner), env.game(OTHER_ID, &joiner)];
+    env.resolve_batch(&games).unwrap();
+    assert!(env.escrow().is_none());
+}
+
+#[test]
+fn resolve_config_leaves_smaller_stakes_to_the_resolve_authority() {
+    let Some(mut env) = Env::new() else { return };
+    const OTHER_ID: [u8; 16] = [8; 16];
+    let cosigner = Pubkey::new_unique();
+    env.init_resolve_config(&[RESOLVE_AUTHORITY, cosigner], 2, AMOUNT + 1).unwrap();
+    env.open_game(GAME_ID);
+    env.open_game(OTHER_ID);
+
+    env.resolve_signed(GAME_ID, &[]).unwrap();
+    env.refund_signed(OTHER_ID, &[]).unwrap();
+}
+
+#[test]
+fn resolve_config_requires_threshold_signatures_from_the_min_amount() {
+    let Some(mut env) = Env::new() else { return };
+    const OTHER_ID: [u8; 16] = [8; 16];
+    let (cosigner, stranger) = (Pubkey::new_unique(), Pubkey::new_unique());
+    env.init_resolve_config(&[RESOLVE_AUTHORITY, cosigner, Pubkey::new_unique()], 2, AMOUNT).unwrap();
+    env.open_game(GAME_ID);
+    env.open_game(OTHER_ID);
+    let joiner = env.joiner.pubkey();
+
+    assert_error(env.resolve_signed(GAME_ID, &[]), EscrowError::NotEnoughSigners);
+    assert_error(env.resolve_signed(GAME_ID, &[stranger]), EscrowError::NotEnoughSigners);
+    assert_error(env.refund_signed(OTHER_ID, &[]), EscrowError::NotEnoughSigners);
+    // A batch has no co-signers.
+    let games = [env.game(GAME_ID, &joiner)];
+    assert_error(env.resolve_batch(&games), EscrowError::NotEnoughSigners);
+
+    let joiner_before = env.balance(&joiner);
+    env.resolve_signed(GAME_ID, &[cosigner]).unwrap();
+    assert_eq!(env.balance(&joiner), joiner_before + resolve_payout(AMOUNT).unwrap().winner_amount);
+    env.refund_signed(OTHER_ID, &[cosigner]).unwrap();
+}
+
+#[test]
+fn update_resolve_config_needs_the_current_threshold() {
+    let Some(mut env) = Env::new() else { return };
+    let cosigner = Pubkey::new_unique();
+    env.init_resolve_config(&[RESOLVE_AUTHORITY, cosigner], 2, AMOUNT).unwrap();
+    let update = |cosigners: &[Pubkey]| {
+        let ix = instructions::update_resolve_config(&ID, &RESOLVE_AUTHORITY, vec![RESOLVE_AUTHORITY], 1, 0);
+        instructions::with_cosigners(ix, cosigners)
+    };
+
+    assert_error(env.send(update(&[]), &[]), EscrowError::NotEnoughSigners);
+    env.send(update(&[cosigner]), &[]).unwrap();
+
+    let account = env.svm.get_account(&resolve_config_pda(&ID).0).unwrap();
+    let config = decode_resolve_config(&account.data).unwrap();
+    assert_eq!(config.authorities, vec![RESOLVE_AUTHORITY]);
+    assert_eq!((config.threshold, config.multisig_min_amount), (1, 0));
+    // Every stake now needs one signature of the set: the resolve authority's.
+    env.open_game(GAME_ID);
+    env.resolve_signed(GAME_ID, &[]).unwrap();
+}
+
+#[test]
+fn pause_blocks_deposits_but_lets_funds_leave() {
+    let Some(mut env) = Env::new() else { return };
+    const OTHER_ID: [u8; 16] = [8; 16];
+    const WAITING_ID: [u8; 16] = [9; 16];
+    env.open_game(GAME_ID);
+    env.open_game(OTHER_ID);
+    let creator = env.creator.insecure_clone();
+    env.send(instructions::create_game(&ID, &creator.pubkey(), WAITING_ID, AMOUNT), &[&creator])
+        .unwrap();
+
+    env.set_paused(true).unwrap();
+    assert_error(env.create(AMOUNT), EscrowError::Paused);
+    let joiner = env.joiner.insecure_clone();
+    let ix = instructions::join_game(&ID, &joiner.pubkey(), &creator.pubkey(), WAITING_ID);
+    assert_error(env.send(ix, &[&joiner]), EscrowError::Paused);
+
+    env.send(instructions::cancel(&ID, &creator.pubkey(), WAITING_ID), &[&creator]).unwrap();
+    env.resolve_signed(GAME_ID, &[]).unwrap();
+    env.refund_signed(OTHER_ID, &[]).unwrap();
+
+    env.set_paused(false).unwrap();
+    env.create(AMOUNT).unwrap();
+}
+
+#[test]
+fn refund_returns_both_deposits_and_rent_to_creator() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    env.join().unwrap();
+    let creator_before = env.balance(&env.creator.pubkey());
+    let joiner_before = env.balance(&env.joiner.pubkey());
+    let treasury_before = env.balance(&TREASURY_PUBKEY);
+
+    env.refund().unwrap();
+
+    assert_eq!(env.balance(&env.creator.pubkey()), creator_before + AMOUNT + env.rent_back());
+    assert_eq!(env.balance(&env.joiner.pubkey()), joiner_before + AMOUNT);
+    assert_eq!(env.balance(&TREASURY_PUBKEY), treasury_before);
+    assert_eq!(env.balance(&env.addresses().1), 0);
+    assert!(env.escrow().is_none());
+    let receipt = env.receipt(GAME_ID).unwrap();
+    assert_eq!((receipt.winner, receipt.fee), (None, 0));
+    assert_eq!(receipt.joiner, env.joiner.pubkey());
+}
+
+#[test]
+fn cancel_returns_deposit_and_rent_to_creator() {
+    let Some(mut env) = Env::new() else { return };
+    let creator_before = env.balance(&env.creator.pubkey());
+    env.create(AMOUNT).unwrap();
+
+    env.cancel().unwrap();
+
+    assert_eq!(env.balance(&env.creator.pubkey()), creator_before);
+    assert_eq!(env.balance(&env.addresses().1), 0);
+    assert!(env.escrow().is_none());
+}
+
+#[test]
+fn game_id_can_be_reused_after_cancel() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    env.cancel().unwrap();
+
+    env.create(2 * AMOUNT).unwrap();
+
+    assert_eq!(env.escrow().unwrap().amount_per_player, 2 * AMOUNT);
+    assert_eq!(env.balance(&env.addresses().1), 2 * AMOUNT);
+    assert!(env.receipt(GAME_ID).is_none());
+}
+
+#[test]
+fn challenge_only_lets_the_opponent_join() {
+    let Some(mut env) = Env::new() else { return };
+    let opponent = env.joiner.pubkey();
+    env.create_challenge(&opponent).unwrap();
+    assert_eq!(env.opponent(), Some(opponent));
+
+    let stranger = Keypair::new();
+    env.svm.airdrop(&stranger.pubkey(), SOL).unwrap();
+    assert_error(env.join_as(&stranger), EscrowError::UnauthorizedJoiner);
+
+    env.join().unwrap();
+    assert_eq!(env.escrow().unwrap().joiner, Some(opponent));
+}
+
+/// Escrows created before challenges and receipts: they end where the opponent starts, and hold
+/// only the rent of that size, less than a receipt's. They decode, can be joined by anyone, and
+/// resolve or refund without a receipt, the creator getting all of the escrow's rent back.
+#[test]
+fn escrows_without_the_opponent_field_still_work() {
+    let Some(mut env) = Env::new() else { return };
+    for refund in [false, true] {
+        env.create(AMOUNT).unwrap();
+        let address = env.addresses().0;
+        let legacy_rent = env.svm.minimum_balance_for_rent_exemption(GameEscrow::OPPONENT_OFFSET);
+        assert!(legacy_rent < env.svm.minimum_balance_for_rent_exemption(GAME_RESULT_SPACE));
+        let mut account = env.svm.get_account(&address).unwrap();
+        account.data.truncate(GameEscrow::OPPONENT_OFFSET);
+        account.lamports = legacy_rent;
+        env.svm.set_account(address, account).unwrap();
+        assert_eq!(env.opponent(), None);
+
+        env.join().unwrap();
+        assert_eq!(env.escrow().unwrap().joiner, Some(env.joiner.pubkey()));
+        let creator_before = env.balance(&env.creator.pubkey());
+        if refund {
+            env.refund().unwrap();
+            assert_eq!(env.balance(&env.creator.pubkey()), creator_before + AMOUNT + legacy_rent);
+        } else {
+            let joiner = env.joiner.pubkey();
+            env.resolve(&joiner).unwrap();
+            assert_eq!(env.balance(&env.creator.pubkey()), creator_before + legacy_rent);
+        }
+        assert!(env.escrow().is_none());
+        assert!(env.receipt(GAME_ID).is_none());
+    }
+}
+
+// --- Clock ---
+
+/// Only verdicts read the clock: a game left open for a year can still be cancelled, and a joined
+/// one resolved or refunded.
+#[test]
+fn open_games_do_not_expire() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    let year_later = |svm: &mut LiteSVM| {
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp += 365 * 24 * 3600;
+        clock.slot += 365 * 216_000;
+        svm.set_sysvar(&clock);
+    };
+    year_later(&mut env.svm);
+    env.cancel().unwrap();
+
+    env.create(AMOUNT).unwrap();
+    env.join().unwrap();
+    year_later(&mut env.svm);
+    env.refund().unwrap();
+
+    env.create(AMOUNT).unwrap();
+    env.join().unwrap();
+    year_later(&mut env.svm);
+    let creator = env.creator.pubkey();
+    env.resolve(&creator).unwrap();
+}
+
+#[test]
+fn resolve_with_attestation_pays_out_like_resolve() {
+    let Some(mut env) = Env::new() else { return };
+    let attesters = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let keys: Vec<Pubkey> = attesters.iter().map(|k| k.pubkey()).collect();
+    // Test keys can sign a verdict only as authorities of the set, for stakes from its min amount.
+    env.init_resolve_config(&keys, 2, AMOUNT).unwrap();
+    env.open_game(GAME_ID);
+    let (game_escrow, vault) = env.addresses();
+    let rent = env.rent_back();
+    let joiner = env.joiner.pubkey();
+    let creator_before = env.balance(&env.creator.pubkey());
+    let joiner_before = env.balance(&joiner);
+    let treasury_before = env.balance(&TREASURY_PUBKEY);
+
+    let ixs = env.verdict(GAME_ID, &joiner, env.now() + 60, &[&attesters[0], &attesters[2]]);
+    env.submit(&ixs).unwrap();
+
+    let payout = resolve_payout(AMOUNT).unwrap();
+    assert_eq!(env.balance(&TREASURY_PUBKEY), treasury_before + payout.treasury_fee);
+    assert_eq!(env.balance(&joiner), joiner_before + payout.winner_amount);
+    assert_eq!(env.balance(&env.creator.pubkey()), creator_before + rent);
+    assert_eq!(env.balance(&vault), 0);
+    assert_eq!(env.balance(&game_escrow), 0);
+    let receipt = env.receipt(GAME_ID).unwrap();
+    assert_eq!(receipt.winner, Some(joiner));
+    assert_eq!(receipt.fee, payout.treasury_fee);
+    // The verdict cannot be replayed.
+    assert!(env.submit(&ixs).is_err());
+}
+
+#[test]
+fn resolve_with_attestation_needs_the_resolve_authority_or_threshold() {
+    let Some(mut env) = Env::new() else { return };
+    const OTHER_ID: [u8; 16] = [8; 16];
+    let attesters = [Keypair::new(), Keypair::new()];
+    let keys: Vec<Pubkey> = attesters.iter().map(|k| k.pubkey()).collect();
+    env.init_resolve_config(&keys, 2, AMOUNT + 1).unwrap();
+    env.open_game(GAME_ID);
+    let creator = env.creator.insecure_clone();
+    let ix = instructions::create_game(&ID, &creator.pubkey(), OTHER_ID, AMOUNT + 1);
+    env.send(ix, &[&creator]).unwrap();
+    let joiner = env.joiner.insecure_clone();
+    let ix = instructions::join_game(&ID, &joiner.pubkey(), &creator.pubkey(), OTHER_ID);
+    env.send(ix, &[&joiner]).unwrap();
+    let expiry = env.now() + 60;
+
+    // Below the min amount only `RESOLVE_AUTHORITY` attests, even for keys of the set.
+    let ixs = env.verdict(GAME_ID, &joiner.pubkey(), expiry, &[&attesters[0], &attesters[1]]);
+    assert_error_at(env.submit(&ixs), 1, EscrowError::Unauthorized);
+    let ixs = env.verdict(OTHER_ID, &joiner.pubkey(), expiry, &[&attesters[1], &Keypair::new()]);
+    assert_error_at(env.submit(&ixs), 1, EscrowError::NotEnoughSigners);
+
+    let ixs = env.verdict(OTHER_ID, &joiner.pubkey(), expiry, &[&attesters[1], &attesters[0]]);
+    env.submit(&ixs).unwrap();
+}
+
+// --- EscrowError paths ---
+
+#[test]
+fn error_invalid_amount_below_minimum_bet() {
+    let Some(mut env) = Env::new() else { return };
+    assert_error(env.create(MIN_BET_LAMPORTS - 1), EscrowError::InvalidAmount);
+    assert_error(env.create(0), EscrowError::InvalidAmount);
+    env.create(MIN_BET_LAMPORTS).unwrap();
+}
+
+#[test]
+fn error_already_resolved() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    // No instruction sets `resolved` (resolve closes the escrow); patch it in.
+    env.patch_escrow(|e| e.resolved = true);
+    assert_error(env.join(), EscrowError::AlreadyResolved);
+    assert_error(env.cancel(), EscrowError::AlreadyResolved);
+
+    env.patch_escrow(|e| e.joiner = Some(Pubkey::new_unique()));
+    let creator = env.creator.pubkey();
+    assert_error(env.resolve(&creator), EscrowError::AlreadyResolved);
+    assert_error(env.refund(), EscrowError::AlreadyResolved);
+}
+
+#[test]
+fn error_joiner_already_set() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    env.join().unwrap();
+
+    let second = Keypair::new();
+    env.svm.airdrop(&second.pubkey(), SOL).unwrap();
+    assert_error(env.join_as(&second), EscrowError::JoinerAlreadySet);
+    assert_error(env.join(), EscrowError::JoinerAlreadySet);
+    assert_error(env.cancel(), EscrowError::JoinerAlreadySet);
+}
+
+#[test]
+fn error_winner_already_set() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    // No instruction sets `winner`; patch it in.
+    let creator = env.creator.pubkey();
+    env.patch_escrow(|e| e.winner = Some(creator));
+    assert_error(env.cancel(), EscrowError::WinnerAlreadySet);
+
+    env.patch_escrow(|e| e.winner = None);
+    env.join().unwrap();
+    env.patch_escrow(|e| e.winner = Some(creator));
+    assert_error(env.refund(), EscrowError::WinnerAlreadySet);
+}
+
+#[test]
+fn error_no_joiner() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    let creator = env.creator.pubkey();
+    assert_error(env.resolve(&creator), EscrowError::NoJoiner);
+    assert_error(env.refund(), EscrowError::NoJoiner);
+}
+
+#[test]
+fn error_insufficient_balance() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    let vault = env.addresses().1;
+
+    // Cancel needs the vault to hold exactly the deposit.
+    env.svm.airdrop(&vault, 1).unwrap();
+    assert_error(env.cancel(), EscrowError::InsufficientBalance);
+
+    env.join().unwrap();
+    let mut account = env.svm.get_account(&vault).unwrap();
+    account.lamports = 2 * AMOUNT - 1;
+    env.svm.set_account(vault, account).unwrap();
+    let creator = env.creator.pubkey();
+    assert_error(env.resolve(&creator), EscrowError::InsufficientBalance);
+    assert_error(env.refund(), EscrowError::InsufficientBalance);
+}
+
+#[test]
+fn error_invalid_winner() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    env.join().unwrap();
+    let (creator, joiner) = (env.creator.pubkey(), env.joiner.pubkey());
+    let stranger = Pubkey::new_unique();
+
+    // Winner is neither player.
+    assert_error(
+        env.resolve_with(&RESOLVE_AUTHORITY, &stranger, &stranger, &creator, &TREASURY_PUBKEY),
+        EscrowError::InvalidWinner,
+    );
+    // Winner is a player but the payout goes elsewhere.
+    assert_error(
+        env.resolve_with(&RESOLVE_AUTHORITY, &joiner, &creator, &creator, &TREASURY_PUBKEY),
+        EscrowError::InvalidWinner,
+    );
+}
+
+#[test]
+fn error_unauthorized_authority() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    env.join().unwrap();
+    let (creator, joiner) = (env.creator.pubkey(), env.joiner.pubkey());
+    let impostor = Pubkey::new_unique();
+    env.svm.airdrop(&impostor, SOL).unwrap();
+
+    assert_error(
+        env.resolve_with(&impostor, &creator, &creator, &creator, &TREASURY_PUBKEY),
+        EscrowError::Unauthorized,
+    );
+    assert_error(env.refund_with(&impostor, &creator, &joiner), EscrowError::Unauthorized);
+    let games = [env.game(GAME_ID, &creator)];
+    let ix = instructions::resolve_batch(&ID, &impostor, &TREASURY_PUBKEY, &games);
+    assert_error(env.send(ix, &[]), EscrowError::Unauthorized);
+    let ix = instructions::init_resolve_config(&ID, &impostor, vec![impostor], 1, 0);
+    assert_error(env.send(ix, &[]), EscrowError::Unauthorized);
+    let ix = instructions::set_paused(&ID, &impostor, true);
+    assert_error(env.send(ix, &[]), EscrowError::Unauthorized);
+}
+
+#[test]
+fn error_unauthorized_creator() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+
+    let impostor = Keypair::new();
+    env.svm.airdrop(&impostor.pubkey(), SOL).unwrap();
+    assert_error(env.cancel_as(&impostor), EscrowError::UnauthorizedCreator);
+
+    env.join().unwrap();
+    let joiner = env.joiner.pubkey();
+    let other = impostor.pubkey();
+    assert_error(
+        env.resolve_with(&RESOLVE_AUTHORITY, &joiner, &joiner, &other, &TREASURY_PUBKEY),
+        EscrowError::UnauthorizedCreator,
+    );
+    assert_error(
+        env.refund_with(&RESOLVE_AUTHORITY, &other, &joiner),
+        EscrowError::UnauthorizedCreator,
+    );
+}
+
+#[test]
+fn error_unauthorized_joiner() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    env.join().unwrap();
+    let creator = env.creator.pubkey();
+    assert_error(
+        env.refund_with(&RESOLVE_AUTHORITY, &creator, &Pubkey::new_unique()),
+        EscrowError::UnauthorizedJoiner,
+    );
+}
+
+#[test]
+fn error_invalid_treasury() {
+    let Some(mut env) = Env::new() else { return };
+    env.create(AMOUNT).unwrap();
+    env.join().unwrap();
+    let creator = env.creator.pubkey();
+    let treasury = Pubkey::new_unique();
+    env.svm.airdrop(&treasury, SOL).unwrap();
+    assert_error(
+        env.resolve_with(&RESOLVE_AUTHORITY, &creator, &creator, &creator, &treasury),
+        EscrowError::InvalidTreasury,
+    );
+    let games = [env.game(GAME_ID, &creator)];
+    let ix = instructions::resolve_batch(&ID, &RESOLVE_AUTHORITY, &treasury, &games);
+    assert_error(env.send(ix, &[]), EscrowError::InvalidTreasury);
+}
+
+#[test]
+fn error_invalid_batch() {
+    let Some(mut env) = Env::new() else { return };
+    env.open_game(GAME_ID);
+    let joiner = env.joiner.pubkey();
+
+    assert_error(env.resolve_batch(&[]), EscrowError::InvalidBatch);
+    // The same game twice would pay it out twice.
+    let game = env.game(GAME_ID, &joiner);
+    assert_error(env.resolve_batch(&[game, game]), EscrowError::InvalidBatch);
+    // A winner without its account group.
+    let mut ix = instructions::resolve_batch(&ID, &RESOLVE_AUTHORITY, &TREASURY_PUBKEY, &[game]);
+    ix.accounts.pop();
+    assert_error(env.send(ix, &[]), EscrowError::InvalidBatch);
+
+    env.resolve_batch(&[game]).unwrap();
+}
+
+#[test]
+fn error_game_id_used() {
+    let Some(mut env) = Env::new() else { return };
+    const OTHER_ID: [u8; 16] = [8; 16];
+    env.open_game(GAME_ID);
+    env.open_game(OTHER_ID);
+    let creator = env.creator.pubkey();
+    env.resolve(&creator).unwrap();
+    env.refund_signed(OTHER_ID, &[]).unwrap();
+
+    // The receipt outlives the escrow: neither id can open a new game.
+    assert_error(env.create(AMOUNT), EscrowError::GameIdUsed);
+    let creator = env.creator.insecure_clone();
+    let ix = instructions::create_game(&ID, &creator.pubkey(), OTHER_ID, AMOUNT);
+    assert_error(env.send(ix, &[&creator]), EscrowError::GameIdUsed);
+    // Lamports sent to a receipt address do not block the game.
+    let (game_escrow, _) = game_addresses(&ID, &creator.pubkey(), &[9; 16]);
+    env.svm.airdrop(&game_result_pda(&ID, &game_escrow).0, SOL).unwrap();
+    env.open_game([9; 16]);
+    env.refund_signed([9; 16], &[]).unwrap();
+    assert!(env.receipt([9; 16]).is_some());
+}
+
+#[test]
+fn error_invalid_opponent() {
+    let Some(mut env) = Env::new() else { return };
+    let creator = env.creator.pubkey();
+    assert_error(env.create_challenge(&creator), EscrowError::InvalidOpponent);
+}
+
+#[test]
+fn error_invalid_resolve_config() {
+    let Some(mut env) = Env::new() else { return };
+    let (a, b) = (RESOLVE_AUTHORITY, Pubkey::new_unique());
+    let six: Vec<Pubkey> = std::iter::once(a).chain((0..5).map(|_| Pubkey::new_unique())).collect();
+    for (authorities, threshold) in [(vec![a, b], 0), (vec![a, b], 3), (vec![a, a], 1), (six, 1)] {
+        assert_error(
+            env.init_resolve_config(&authorities, threshold, AMOUNT),
+            EscrowError::InvalidResolveConfig,
+        );
+    }
+    env.init_resolve_config(&[a, b], 2, AMOUNT).unwrap();
+}
+
+#[test]
+fn error_invalid_attestation() {
+    let Some(mut env) = Env::new() else { return };
+    let attester = Keypair::new();
+    env.init_resolve_config(&[attester.pubkey()], 1, AMOUNT).unwrap();
+    env.open_game(GAME_ID);
+    let (creator, joiner) = (env.creator.pubkey(), env.joiner.pubkey());
+    let expiry = env.now() + 60;
+    let [ed25519, resolve] = env.verdict(GAME_ID, &joiner, expiry, &[&attester]);
+
+    // No Ed25519 instruction, or not just before.
+    assert_error(env.submit(std::slice::from_ref(&resolve)), EscrowError::InvalidAttestation);
+    assert_error(env.submit(&[resolve.clone(), ed25519.clone()]), EscrowError::InvalidAttestation);
+    // A verdict for another winner, or with another expiry.
+    let [_, to_creator] = env.verdict(GAME_ID, &creator, expiry, &[&attester]);
+    assert_error_at(env.submit(&[ed25519.clone(), to_creator]), 1, EscrowError::InvalidAttestation);
+    let [_, later] = env.verdict(GAME_ID, &joiner, expiry + 1, &[&attester]);
+    assert_error_at(env.submit(&[ed25519.clone(), later]), 1, EscrowError::InvalidAttestation);
+
+    env.submit(&[ed25519, resolve]).unwrap();
+}
+
+#[test]
+fn error_attestation_expired() {
+    let Some(mut env) = Env::new() else { return };
+    let attester = Keypair::new();
+    env.init_resolve_config(&[attester.pubkey()], 1, AMOUNT).unwrap();
+    env.open_game(GAME_ID);
+    let joiner = env.joiner.pubkey();
+    let expiry = env.now();
+
+    let ixs = env.verdict(GAME_ID, &joiner, expiry, &[&attester]);
+    let mut clock: Clock = env.svm.get_sysvar();
+    clock.unix_timestamp += 1;
+    env.svm.set_sysvar(&clock);
+    assert_error_at(env.submit(&ixs), 1, EscrowError::AttestationExpired);
+
+    let ixs = env.verdict(GAME_ID, &joiner, expiry + 1, &[&attester]);
+    env.submit(&ixs).unwrap();
+}
@@ -0,0 +1,394 @@
+//! Property-based fuzzing of instruction sequences on LiteSVM.
+//!
+//! Random sequences of `create_game` / `create_challenge`, `join_game`, `cancel`, `refund` and
+//! `resolve`, with random signers, amounts and winners, run against the program while a model of
+//! each game predicts which instructions succeed and how much each account receives. After every
+//! transaction:
+//! - the outcome matches the model (only the creator cancels, only the opponent joins a challenge,
+//!   only the resolve authority refunds or resolves, only a player wins, a resolved or refunded
+//!   game id is never reused);
+//! - a failed transaction moves no lamports;
+//! - lamports are conserved across players, treasury, authorities, escrows and vaults, and every
+//!   account moves by exactly the expected amount, so nothing is paid out twice;
+//! - only the transaction's signers, and the game's own escrow and vault, lose lamports;
+//! - the vault of every closed escrow is empty, and resolved or refunded games keep their receipt.
+//!
+//! `PROPTEST_CASES` sets the number of sequences (default 256). See `common` for the setup.
+
+// LiteSVM's own `TransactionResult`.
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use std::collections::HashMap;
+
+use litesvm::LiteSVM;
+use proptest::prelude::*;
+use proptest::test_runner::{TestCaseError, TestRunner};
+use rps_escrow_client::fees::{resolve_payout, MIN_BET_LAMPORTS};
+use rps_escrow_client::pda::{game_addresses, game_result_pda};
+use rps_escrow_client::state::{GAME_ESCROW_SPACE, GAME_RESULT_SPACE};
+use rps_escrow_client::{instructions, ID, RESOLVE_AUTHORITY, TREASURY_PUBKEY};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+use common::SOL;
+
+const PLAYERS: usize = 3;
+/// Games per creator.
+const GAMES_PER_PLAYER: usize = 2;
+const SLOTS: usize = PLAYERS * GAMES_PER_PLAYER;
+
+#[derive(Clone, Debug)]
+enum Op {
+    /// `create_challenge` when `opponent` is set.
+    Create {
+        slot: usize,
+        amount: u64,
+        opponent: Option<usize>,
+    },
+    Join {
+        slot: usize,
+        player: usize,
+    },
+    Cancel {
+        slot: usize,
+        signer: usize,
+    },
+    Refund {
+        slot: usize,
+        authorized: bool,
+    },
+    /// `winner == PLAYERS` pays a stranger.
+    Resolve {
+        slot: usize,
+        winner: usize,
+        authorized: bool,
+    },
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let slot = 0..SLOTS;
+    let player = 0..PLAYERS;
+    let amount = prop_oneof![1 => 0..MIN_BET_LAMPORTS, 9 => MIN_BET_LAMPORTS..2 * SOL];
+    prop_oneof![
+        (slot.clone(), amount, proptest::option::of(player.clone()))
+            .prop_map(|(slot, amount, opponent)| Op::Create { slot, amount, opponent }),
+        (slot.clone(), player.clone()).prop_map(|(slot, player)| Op::Join { slot, player }),
+        (slot.clone(), player).prop_map(|(slot, signer)| Op::Cancel { slot, signer }),
+        (slot.clone(), prop::bool::weighted(0.8))
+            .prop_map(|(slot, authorized)| Op::Refund { slot, authorized }),
+        (slot, 0..=PLAYERS, prop::bool::weighted(0.8))
+            .prop_map(|(slot, winner, authorized)| Op::Resolve { slot, winner, authorized }),
+    ]
+}
+
+/// Model of an open escrow.
+#[derive(Clone, Debug)]
+struct Game {
+    amount: u64,
+    opponent: Option<usize>,
+    joiner: Option<usize>,
+}
+
+/// If the instruction must succeed: the slot's new state and each account's lamport change.
+type Prediction = Option<(Option<Game>, Vec<(Pubkey, i128)>)>;
+
+struct Harness {
+    svm: LiteSVM,
+    fee_payer: Keypair,
+    players: Vec<Keypair>,
+    stranger: Pubkey,
+    impostor: Pubkey,
+    games: [Option<Game>; SLOTS],
+    /// Slots whose game was resolved or refunded: their id cannot be used again.
+    finished: [bool; SLOTS],
+    escrow_rent: u64,
+    result_rent: u64,
+}
+
+impl Harness {
+    fn new(program: &[u8]) -> Self {
+        let mut svm = common::new_svm(program);
+        let fee_payer = Keypair::new();
+        let players: Vec<Keypair> = (0..PLAYERS).map(|_| Keypair::new()).collect();
+        svm.airdrop(&fee_payer.pubkey(), 100 * SOL).unwrap();
+        for player in &players {
+            svm.airdrop(&player.pubkey(), 1_000 * SOL).unwrap();
+        }
+        let escrow_rent = svm.minimum_balance_for_rent_exemption(GAME_ESCROW_SPACE);
+        let result_rent = svm.minimum_balance_for_rent_exemption(GAME_RESULT_SPACE);
+        Self {
+            svm,
+            fee_payer,
+            players,
+            stranger: Pubkey::new_unique(),
+            impostor: Pubkey::new_unique(),
+            games: Default::default(),
+            finished: Default::default(),
+            escrow_rent,
+            result_rent,
+        }
+    }
+
+    /// Slots are `GAMES_PER_PLAYER` games of each player, as creator.
+    fn creator(slot: usize) -> usize {
+        slot % PLAYERS
+    }
+
+    fn game_id(slot: usize) -> [u8; 16] {
+        [slot as u8; 16]
+    }
+
+    fn pubkey(&self, player: usize) -> Pubkey {
+        self.players[player].pubkey()
+    }
+
+    fn addresses(&self, slot: usize) -> (Pubkey, Pubkey) {
+        game_addresses(&ID, &self.pubkey(Self::creator(slot)), &Self::game_id(slot))
+    }
+
+    fn receipt(&self, slot: usize) -> Pubkey {
+        game_result_pda(&ID, &self.addresses(slot).0).0
+    }
+
+    /// Every account whose lamports the program may move.
+    fn tracked(&self) -> Vec<Pubkey> {
+        let mut accounts: Vec<Pubkey> = self.players.iter().map(|p| p.pubkey()).collect();
+        accounts.extend([self.stranger, TREASURY_PUBKEY, RESOLVE_AUTHORITY, self.impostor]);
+        for slot in 0..SLOTS {
+            let (game_escrow, vault) = self.addresses(slot);
+            accounts.extend([game_escrow, vault, self.receipt(slot)]);
+        }
+        accounts
+    }
+
+    fn balances(&self) -> HashMap<Pubkey, u64> {
+        self.tracked()
+            .into_iter()
+            .map(|pubkey| (pubkey, common::balance(&self.svm, &pubkey)))
+            .collect()
+    }
+
+    /// The instruction for `op`, its signing players, and the model's prediction.
+    fn plan(&self, op: &Op) -> (Instruction, Vec<usize>, Prediction) {
+        let rent = i128::from(self.escrow_rent);
+        // Resolve and refund move part of the escrow's rent to the receipt.
+        let result_rent = i128::from(self.result_rent);
+        match *op {
+            Op::Create { slot, amount, opponent } => {
+                let creator = Self::creator(slot);
+                let (game_escrow, vault) = self.addresses(slot);
+                let ix = match opponent {
+                    Some(o) => instructions::create_challenge(
+                        &ID,
+                        &self.pubkey(creator),
+                        Self::game_id(slot),
+                        amount,
+                        &self.pubkey(o),
+                    ),
+                    None => instructions::create_game(&ID, &self.pubkey(creator), Self::game_id(slot), amount),
+                };
+                let ok = self.games[slot].is_none()
+                    && !self.finished[slot]
+                    && amount >= MIN_BET_LAMPORTS
+                    && opponent != Some(creator);
+                let outcome = ok.then(|| {
+                    let game = Game {
+                        amount,
+                        opponent,
+                        joiner: None,
+                    };
+                    let amount = i128::from(amount);
+                    let deltas = vec![
+                        (self.pubkey(creator), -amount - rent),
+                        (game_escrow, rent),
+                        (vault, amount),
+                    ];
+                    (Some(game), deltas)
+                });
+                (ix, vec![creator], outcome)
+            }
+            Op::Join { slot, player } => {
+                let creator = Self::creator(slot);
+                let ix = instructions::join_game(&ID, &self.pubkey(player), &self.pubkey(creator), Self::game_id(slot));
+                let outcome = self.games[slot]
+                    .clone()
+                    .filter(|g| g.joiner.is_none() && g.opponent.is_none_or(|o| o == player))
+                    .map(|mut game| {
+                        game.joiner = Some(player);
+                        let amount = i128::from(game.amount);
+                        let deltas = vec![(self.pubkey(player), -amount), (self.addresses(slot).1, amount)];
+                        (Some(game), deltas)
+                    });
+                (ix, vec![player], outcome)
+            }
+            Op::Cancel { slot, signer } => {
+                let creator = Self::creator(slot);
+                let (game_escrow, vault) = self.addresses(slot);
+                let mut ix = instructions::cancel(&ID, &self.pubkey(creator), Self::game_id(slot));
+                ix.accounts[0].pubkey = self.pubkey(signer);
+                let outcome = self.games[slot]
+                    .as_ref()
+                    .filter(|g| g.joiner.is_none() && signer == creator)
+                    .map(|game| {
+                        let amount = i128::from(game.amount);
+                        let deltas = vec![
+                            (self.pubkey(creator), amount + rent),
+                            (game_escrow, -rent),
+                            (vault, -amount),
+                        ];
+                        (None, deltas)
+                    });
+                (ix, vec![signer], outcome)
+            }
+            Op::Refund { slot, authorized } => {
+                let creator = Self::creator(slot);
+                let (game_escrow, vault) = self.addresses(slot);
+                let joiner = self.games[slot].as_ref().and_then(|g| g.joiner).unwrap_or(creator);
+                let authority = if authorized { RESOLVE_AUTHORITY } else { self.impostor };
+                let ix = instructions::refund(
+                    &ID,
+                    &authority,
+                    &self.pubkey(creator),
+                    &self.pubkey(joiner),
+                    Self::game_id(slot),
+                );
+                let outcome = self.games[slot]
+                    .as_ref()
+                    .filter(|g| g.joiner.is_some() && authorized)
+                    .map(|game| {
+                        let amount = i128::from(game.amount);
+                        let deltas = vec![
+                            (self.pubkey(creator), amount + rent - result_rent),
+                            (self.pubkey(joiner), amount),
+                            (game_escrow, -rent),
+                            (vault, -2 * amount),
+                            (self.receipt(slot), result_rent),
+                        ];
+                        (None, deltas)
+                    });
+                (ix, vec![], outcome)
+            }
+            Op::Resolve { slot, winner, authorized } => {
+                let creator = Self::creator(slot);
+                let (game_escrow, vault) = self.addresses(slot);
+                let winner_pubkey = if winner < PLAYERS { self.pubkey(winner) } else { self.stranger };
+                let authority = if authorized { RESOLVE_AUTHORITY } else { self.impostor };
+                let ix = instructions::resolve(
+                    &ID,
+                    &authority,
+                    &self.pubkey(creator),
+                    Self::game_id(slot),
+                    &winner_pubkey,
+                    &TREASURY_PUBKEY,
+                );
+                let outcome = self.games[slot]
+                    .as_ref()
+                    .filter(|g| authorized && g.joiner.is_some() && (winner == creator || g.joiner == Some(winner)))
+                    .map(|game| {
+                        let payout = resolve_payout(game.amount).unwrap();
+                        let deltas = vec![
+                            (winner_pubkey, i128::from(payout.winner_amount)),
+                            (self.pubkey(creator), rent - result_rent),
+                            (TREASURY_PUBKEY, i128::from(payout.treasury_fee)),
+                            (game_escrow, -rent),
+                            (vault, -i128::from(payout.pot)),
+                            (self.receipt(slot), result_rent),
+                        ];
+                        (None, deltas)
+                    });
+                (ix, vec![], outcome)
+            }
+        }
+    }
+
+    fn slot(op: &Op) -> usize {
+        match *op {
+            Op::Create { slot, .. }
+            | Op::Join { slot, .. }
+            | Op::Cancel { slot, .. }
+            | Op::Refund { slot, .. }
+            | Op::Resolve { slot, .. } => slot,
+        }
+    }
+
+    fn step(&mut self, op: &Op) -> Result<(), TestCaseError> {
+        let slot = Self::slot(op);
+        let (ix, signer_indices, expected) = self.plan(op);
+        let signers: Vec<Keypair> = signer_indices.iter().map(|&p| self.players[p].insecure_clone()).collect();
+        let signer_refs: Vec<&Keypair> = signers.iter().collect();
+
+        let before = self.balances();
+        let result = common::send(&mut self.svm, &self.fee_payer, ix, &signer_refs);
+        let after = self.balances();
+        let logs = match &result {
+            Ok(meta) => meta.logs.clone(),
+            Err(failed) => failed.meta.logs.clone(),
+        };
+
+        prop_assert_eq!(result.is_ok(), expected.is_some(), "{:?}: {:?}\n{:#?}", op, result.as_ref().err().map(|f| &f.err), logs);
+
+        // Lamports only move between tracked accounts (the fee payer pays the fees).
+        let total_before: u128 = before.values().map(|&l| u128::from(l)).sum();
+        let total_after: u128 = after.values().map(|&l| u128::from(l)).sum();
+        prop_assert_eq!(total_before, total_after, "{:?} changed the total", op);
+
+        let mut expected_deltas: HashMap<Pubkey, i128> = HashMap::new();
+        if let Some((game, deltas)) = expected {
+            for (pubkey, delta) in deltas {
+                *expected_deltas.entry(pubkey).or_default() += delta;
+            }
+            self.games[slot] = game;
+            self.finished[slot] |= matches!(op, Op::Refund { .. } | Op::Resolve { .. });
+        }
+        let (game_escrow, vault) = self.addresses(slot);
+        let signer_pubkeys: Vec<Pubkey> = signers.iter().map(|s| s.pubkey()).collect();
+        for pubkey in self.tracked() {
+            let delta = i128::from(after[&pubkey]) - i128::from(before[&pubkey]);
+            prop_assert_eq!(
+                delta,
+                expected_deltas.get(&pubkey).copied().unwrap_or(0),
+                "{:?}: unexpected change of {}",
+                op,
+                pubkey
+            );
+            prop_assert!(
+                delta >= 0 || signer_pubkeys.contains(&pubkey) || pubkey == game_escrow || pubkey == vault,
+                "{:?}: {} lost lamports without signing",
+                op,
+                pubkey
+            );
+        }
+
+        for s in 0..SLOTS {
+            let (game_escrow, vault) = self.addresses(s);
+            let open = common::balance(&self.svm, &game_escrow) > 0;
+            prop_assert_eq!(open, self.games[s].is_some(), "slot {} escrow state", s);
+            if !open {
+                prop_assert_eq!(common::balance(&self.svm, &vault), 0, "slot {} vault not empty after close", s);
+            }
+            let receipt = self.svm.get_account(&self.receipt(s)).is_some_and(|a| a.owner == ID);
+            prop_assert_eq!(receipt, self.finished[s], "slot {} receipt", s);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn random_instruction_sequences_keep_escrow_invariants() {
+    let Some(program) = common::load_program() else { return };
+    let mut runner = TestRunner::default();
+    runner
+        .run(&prop::collection::vec(op(), 1..40), |ops| {
+            let mut harness = Harness::new(&program);
+            for op in &ops {
+                harness.step(op)?;
+            }
+            Ok(())
+        })
+        .unwrap();
+}
@@ -32,6 +32,42 @@ async function airdropTo(
   }
 }
 
+/** PDA ["resolve_config"]: the resolve authority set, passed to resolve, resolve_batch and refund. */
+function resolveConfigPda(program: Program<RpsEscrow>): anchor.web3.PublicKey {
+  return anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("resolve_config")], program.programId)[0];
+}
+
+/** PDA ["game_result", game_escrow]: the result receipt written by resolve, resolve_batch, resolve_with_attestation and refund. */
+function gameResultPda(program: Program<RpsEscrow>, gameEscrow: anchor.web3.PublicKey): anchor.web3.PublicKey {
+  return anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("game_result"), gameEscrow.toBuffer()],
+    program.programId
+  )[0];
+}
+
+/** PDA ["pause"]: the emergency pause flag, passed to create_game, create_challenge and join_game. */
+function pausePda(program: Program<RpsEscrow>): anchor.web3.PublicKey {
+  return anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("pause")], program.programId)[0];
+}
+
+/** Verdict message signed off-chain for resolve_with_attestation (see `verdict_message` in the program). */
+function verdictMessage(
+  gameEscrow: anchor.web3.PublicKey,
+  gameId: Buffer,
+  winner: anchor.web3.PublicKey,
+  expiry: number
+): Buffer {
+  const expiryBytes = Buffer.alloc(8);
+  expiryBytes.writeBigInt64LE(BigInt(expiry));
+  return Buffer.concat([
+    Buffer.from("rps-escrow:verdict:v1"),
+    gameEscrow.toBuffer(),
+    gameId,
+    winner.toBuffer(),
+    expiryBytes,
+  ]);
+}
+
 function loadKeypair(name: string): anchor.web3.Keypair {
   const keypath = path.join(__dirname, "..", name);
   const keypairData = JSON.parse(fs.readFileSync(keypath, "utf-8"));
@@ -82,6 +118,8 @@ describe("create game, deposit, join game, resolve game (happy path)", () => {
         creator: creator.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([creator])
@@ -100,6 +138,7 @@ describe("create game, deposit, join game, resolve game (happy path)", () => {
         joiner: joiner.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([joiner])
@@ -117,6 +156,8 @@ describe("create game, deposit, join game, resolve game (happy path)", () => {
       vault:vaultPda,
       winnerDestination: creator.publicKey,
       creator: creator.publicKey,
+      gameResult: gameResultPda(program, gameEscrowPda),
+      resolveConfig: resolveConfigPda(program),
       systemProgram: anchor.web3.SystemProgram.programId,
       treasury: treasury
     })
@@ -130,9 +171,31 @@ describe("create game, deposit, join game, resolve game (happy path)", () => {
     assert.isNull(vaultPdaInfo, "Vault PDA should be null");
     assert.isAbove(await provider.connection.getBalance(creator.publicKey),INITIAL_BALANCE, "Creator should have win");
     assert.isAbove(await provider.connection.getBalance(treasury),0, "Treasury should have the 3% fee");
-
+    const receipt = await provider.connection.getAccountInfo(gameResultPda(program, gameEscrowPda));
+    assert.ok(receipt?.owner.equals(program.programId), "Game result receipt should be owned by the program");
   })
 
+  it("should fail to create a game again with the id of the resolved game!", async () => {
+    try {
+      await program.methods
+        .createGame(Array.from(gameId), new anchor.BN(amount))
+        .accountsStrict({
+          creator: creator.publicKey,
+          gameEscrow: gameEscrowPda,
+          vault: vaultPda,
+          gameResult: gameResultPda(program, gameEscrowPda),
+          pause: pausePda(program),
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator])
+        .rpc();
+      expect.fail("Expected error");
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      assert.ok(message.includes("GameIdUsed"), `Expected "GameIdUsed" in: ${message}`);
+    }
+  });
+
 });
 
 
@@ -179,6 +242,8 @@ describe("try to create 2 same games", () => {
         creator: creator.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([creator])
@@ -192,6 +257,8 @@ describe("try to create 2 same games", () => {
         creator: creator.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([creator])
@@ -249,6 +316,8 @@ describe("create game, deposit, join game and try to resolve game with the wrong
         creator: creator.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([creator])
@@ -267,6 +336,7 @@ describe("create game, deposit, join game and try to resolve game with the wrong
         joiner: joiner.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([joiner])
@@ -285,6 +355,8 @@ describe("create game, deposit, join game and try to resolve game with the wrong
           gameEscrow: gameEscrowPda,
           vault:vaultPda,
           creator: creator.publicKey,
+          gameResult: gameResultPda(program, gameEscrowPda),
+          resolveConfig: resolveConfigPda(program),
           systemProgram: anchor.web3.SystemProgram.programId,
           winnerDestination: creator.publicKey,
           treasury: treasury
@@ -346,6 +418,8 @@ describe("create game with amount = 0 ", () => {
         creator: creator.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([creator])
@@ -404,6 +478,8 @@ describe("create game, deposit and try to resolve without joiner ", () => {
         creator: creator.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([creator])
@@ -425,6 +501,8 @@ describe("create game, deposit and try to resolve without joiner ", () => {
           gameEscrow: gameEscrowPda,
           vault:vaultPda,
           creator: creator.publicKey,
+          gameResult: gameResultPda(program, gameEscrowPda),
+          resolveConfig: resolveConfigPda(program),
           systemProgram: anchor.web3.SystemProgram.programId,
           winnerDestination: creator.publicKey,
           treasury: treasury
@@ -486,6 +564,8 @@ describe("create game, deposit, join game and try to resolve game with the wrong
         creator: creator.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([creator])
@@ -504,6 +584,7 @@ describe("create game, deposit, join game and try to resolve game with the wrong
         joiner: joiner.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([joiner])
@@ -522,6 +603,8 @@ describe("create game, deposit, join game and try to resolve game with the wrong
           gameEscrow: gameEscrowPda,
           vault:vaultPda,
           creator: creator.publicKey,
+          gameResult: gameResultPda(program, gameEscrowPda),
+          resolveConfig: resolveConfigPda(program),
           systemProgram: anchor.web3.SystemProgram.programId,
           winnerDestination: authority.publicKey,
           treasury: treasury
@@ -584,6 +667,8 @@ describe("create game, deposit, and try to join 2 times the same game", () => {
         creator: creator.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([creator])
@@ -602,6 +687,7 @@ describe("create game, deposit, and try to join 2 times the same game", () => {
         joiner: joiner.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([joiner])
@@ -618,6 +704,7 @@ describe("create game, deposit, and try to join 2 times the same game", () => {
           joiner: joiner2.publicKey,
           gameEscrow: gameEscrowPda,
           vault:vaultPda,
+          pause: pausePda(program),
           systemProgram: anchor.web3.SystemProgram.programId,
         })
         .signers([joiner2])
@@ -676,6 +763,8 @@ describe("create game, deposit, join and try to resolve with the correct winner
         creator: creator.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([creator])
@@ -694,6 +783,7 @@ describe("create game, deposit, join and try to resolve with the correct winner
         joiner: joiner.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([joiner])
@@ -711,6 +801,8 @@ describe("create game, deposit, join and try to resolve with the correct winner
           gameEscrow: gameEscrowPda,
           vault:vaultPda,
           creator: creator.publicKey,
+          gameResult: gameResultPda(program, gameEscrowPda),
+          resolveConfig: resolveConfigPda(program),
           systemProgram: anchor.web3.SystemProgram.programId,
           winnerDestination: authority.publicKey,
           treasury: treasury
@@ -771,6 +863,8 @@ describe("create game and cancel it before the joiner joins", () => {
         creator: creator.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([creator])
@@ -847,6 +941,8 @@ describe("create game and try to cancel it after the joiner joins", () => {
         creator: creator.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([creator])
@@ -865,6 +961,7 @@ describe("create game and try to cancel it after the joiner joins", () => {
         joiner: joiner.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([joiner])
@@ -940,6 +1037,8 @@ describe("create game and try to cancel it with the wrong creator", () => {
         creator: creator.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([creator])
@@ -1015,6 +1114,8 @@ describe("create game, deposit, join game and refund both joiner and creator", (
         creator: creator.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([creator])
@@ -1033,6 +1134,7 @@ describe("create game, deposit, join game and refund both joiner and creator", (
         joiner: joiner.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([joiner])
@@ -1051,6 +1153,8 @@ describe("create game, deposit, join game and refund both joiner and creator", (
       vault:vaultPda,
       creator: creator.publicKey,
       joiner: joiner.publicKey,
+      gameResult: gameResultPda(program, gameEscrowPda),
+      resolveConfig: resolveConfigPda(program),
       systemProgram: anchor.web3.SystemProgram.programId,
     })
     .signers([authority])
@@ -1059,6 +1163,8 @@ describe("create game, deposit, join game and refund both joiner and creator", (
     console.log("Your transaction signature", tx);
     const gameEscrowPdaInfo = await provider.connection.getAccountInfo(gameEscrowPda);
     assert.isNull(gameEscrowPdaInfo, "Game escrow PDA should be null");
+    const receipt = await provider.connection.getAccountInfo(gameResultPda(program, gameEscrowPda));
+    assert.ok(receipt?.owner.equals(program.programId), "Game result receipt should be owned by the program");
   })
 
 });
@@ -1111,6 +1217,8 @@ describe("create game, deposit, join game and resolve with wrong treasury", () =
         creator: creator.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([creator])
@@ -1129,6 +1237,7 @@ describe("create game, deposit, join game and resolve with wrong treasury", () =
         joiner: joiner.publicKey,
         gameEscrow: gameEscrowPda,
         vault:vaultPda,
+        pause: pausePda(program),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([joiner])
@@ -1149,6 +1258,8 @@ describe("create game, deposit, join game and resolve with wrong treasury", () =
       creator: creator.publicKey,
       winnerDestination: creator.publicKey,
       treasury: wrongTreasury.publicKey,
+      gameResult: gameResultPda(program, gameEscrowPda),
+      resolveConfig: resolveConfigPda(program),
       systemProgram: anchor.web3.SystemProgram.programId,
     })
     .signers([authority])
@@ -1160,3 +1271,547 @@ describe("create game, deposit, join game and resolve with wrong treasury", () =
     }
   });
 });
+
+
+describe("create challenge and try to join with a wallet that is not the opponent", () => {
+  // Configure the client to use the local cluster.
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.rpsEscrow as Program<RpsEscrow>;
+  const creator = anchor.web3.Keypair.generate();
+  const opponent = anchor.web3.Keypair.generate();
+  const stranger = anchor.web3.Keypair.generate();
+  // game_id is [u8; 16] on-chain: UUID without hyphens = 32 hex chars = 16 bytes
+  const gameIdStr = "e504f1b02e4e46b08d4189b3b5b47745";
+  const gameId = Buffer.from(gameIdStr, "hex");
+  const amount = 1_000_000_000; // 1 SOL in lamports
+
+  let gameEscrowPda: anchor.web3.PublicKey;
+  let vaultPda: anchor.web3.PublicKey;
+
+  before(async () => {
+    await airdropTo(provider, INITIAL_BALANCE, creator.publicKey, opponent.publicKey, stranger.publicKey);
+  });
+
+  it("Create challenge and deposit!", async () => {
+    [gameEscrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("game_escrow"), creator.publicKey.toBuffer(), gameId],
+      program.programId
+    );
+
+    [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), gameEscrowPda.toBuffer()],
+      program.programId
+    );
+
+    const tx = await program.methods
+      .createChallenge(Array.from(gameId), new anchor.BN(amount), opponent.publicKey)
+      .accountsStrict({
+        creator: creator.publicKey,
+        gameEscrow: gameEscrowPda,
+        vault: vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+
+    // The opponent follows the GameEscrow fields: option tag, then the key.
+    const escrowInfo = await provider.connection.getAccountInfo(gameEscrowPda);
+    const opponentSlot = escrowInfo.data.subarray(escrowInfo.data.length - 33);
+    assert.equal(opponentSlot[0], 1, "Opponent should be set on the escrow");
+    assert.ok(
+      new anchor.web3.PublicKey(opponentSlot.subarray(1)).equals(opponent.publicKey),
+      "Opponent should be pinned on the escrow"
+    );
+    assert.equal(await provider.connection.getBalance(vaultPda), amount, "vault should have the amount deposited");
+    console.log("Your transaction signature", tx);
+  });
+
+  it("should fail to join the challenge with a wallet that is not the opponent!", async () => {
+    try {
+      await program.methods
+        .joinGame()
+        .accountsStrict({
+          joiner: stranger.publicKey,
+          gameEscrow: gameEscrowPda,
+          vault: vaultPda,
+          pause: pausePda(program),
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([stranger])
+        .rpc();
+      expect.fail("Expected error");
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      assert.ok(message.includes("UnauthorizedJoiner"), `Expected "UnauthorizedJoiner" in: ${message}`);
+    }
+  });
+
+  it("Opponent joins the challenge!", async () => {
+    const tx = await program.methods
+      .joinGame()
+      .accountsStrict({
+        joiner: opponent.publicKey,
+        gameEscrow: gameEscrowPda,
+        vault: vaultPda,
+        pause: pausePda(program),
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([opponent])
+      .rpc();
+
+    assert.equal(await provider.connection.getBalance(vaultPda), amount * 2, "vault should hold both deposits");
+    console.log("Your transaction signature", tx);
+  });
+
+  it("should fail to challenge yourself!", async () => {
+    const otherGameId = Buffer.from("0a4c3b1e9d2f4c7a8b6e5d4c3b2a1908", "hex");
+    const [otherEscrow] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("game_escrow"), creator.publicKey.toBuffer(), otherGameId],
+      program.programId
+    );
+    const [otherVault] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), otherEscrow.toBuffer()],
+      program.programId
+    );
+    try {
+      await program.methods
+        .createChallenge(Array.from(otherGameId), new anchor.BN(amount), creator.publicKey)
+        .accountsStrict({
+          creator: creator.publicKey,
+          gameEscrow: otherEscrow,
+          vault: otherVault,
+          gameResult: gameResultPda(program, otherEscrow),
+          pause: pausePda(program),
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator])
+        .rpc();
+      expect.fail("Expected error");
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      assert.ok(message.includes("InvalidOpponent"), `Expected "InvalidOpponent" in: ${message}`);
+    }
+  });
+});
+
+describe("create two games, join both and resolve them in one batch", () => {
+  // Configure the client to use the local cluster.
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.rpsEscrow as Program<RpsEscrow>;
+  const creator = anchor.web3.Keypair.generate();
+  const joiner = anchor.web3.Keypair.generate();
+  const authority = loadKeypair("resolve_authority.json");
+  const treasury = new anchor.web3.PublicKey("Ft6kMwkButM1J7iHJBJTb8QFEBuoBPnG1jq83HMRE9mF");
+  const gameIds = [
+    Buffer.from("3f1d2c4b5a6978e8d7c6b5a493827160", "hex"),
+    Buffer.from("7a6b5c4d3e2f40918273645546372819", "hex"),
+  ];
+  const amount = 1_000_000_000; // 1 SOL in lamports
+
+  const pdas = gameIds.map((gameId) => {
+    const [gameEscrow] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("game_escrow"), creator.publicKey.toBuffer(), gameId],
+      program.programId
+    );
+    const [vault] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), gameEscrow.toBuffer()],
+      program.programId
+    );
+    return { gameEscrow, vault, gameResult: gameResultPda(program, gameEscrow) };
+  });
+
+  before(async () => {
+    await airdropTo(provider, INITIAL_BALANCE, creator.publicKey, joiner.publicKey, authority.publicKey);
+  });
+
+  it("Create and join both games!", async () => {
+    for (const [i, gameId] of gameIds.entries()) {
+      await program.methods
+        .createGame(Array.from(gameId), new anchor.BN(amount))
+        .accountsStrict({
+          creator: creator.publicKey,
+          gameEscrow: pdas[i].gameEscrow,
+          vault: pdas[i].vault,
+          gameResult: gameResultPda(program, pdas[i].gameEscrow),
+          pause: pausePda(program),
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([creator])
+        .rpc();
+      await program.methods
+        .joinGame()
+        .accountsStrict({
+          joiner: joiner.publicKey,
+          gameEscrow: pdas[i].gameEscrow,
+          vault: pdas[i].vault,
+          pause: pausePda(program),
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([joiner])
+        .rpc();
+    }
+  });
+
+  // One [game_escrow, vault, winner_destination, creator, game_result] group per winner.
+  const groups = (winners: anchor.web3.PublicKey[]) =>
+    winners.flatMap((winner, i) =>
+      [pdas[i].gameEscrow, pdas[i].vault, winner, creator.publicKey, pdas[i].gameResult].map((pubkey) => ({
+        pubkey,
+        isSigner: false,
+        isWritable: true,
+      }))
+    );
+
+  it("should fail to resolve a batch with a missing account group!", async () => {
+    const winners = [creator.publicKey, joiner.publicKey];
+    try {
+      await program.methods
+        .resolveBatch(winners)
+        .accountsStrict({
+          authority: authority.publicKey,
+          treasury: treasury,
+          resolveConfig: resolveConfigPda(program),
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .remainingAccounts(groups(winners).slice(0, 5))
+        .signers([authority])
+        .rpc();
+      expect.fail("Expected error");
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      assert.ok(message.includes("InvalidBatch"), `Expected "InvalidBatch" in: ${message}`);
+    }
+  });
+
+  it("Resolve both games in one batch!", async () => {
+    const winners = [creator.publicKey, joiner.publicKey];
+    const treasuryBefore = await provider.connection.getBalance(treasury);
+    const tx = await program.methods
+      .resolveBatch(winners)
+      .accountsStrict({
+        authority: authority.publicKey,
+        treasury: treasury,
+        resolveConfig: resolveConfigPda(program),
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts(groups(winners))
+      .signers([authority])
+      .rpc();
+
+    console.log("Your transaction signature", tx);
+    for (const { gameEscrow, vault, gameResult } of pdas) {
+      assert.isNull(await provider.connection.getAccountInfo(gameEscrow), "Game escrow PDA should be null");
+      assert.isNull(await provider.connection.getAccountInfo(vault), "Vault PDA should be null");
+      assert.isNotNull(await provider.connection.getAccountInfo(gameResult), "Game result receipt should exist");
+    }
+    const fee = (2 * amount * 3) / 100;
+    assert.equal(await provider.connection.getBalance(treasury), treasuryBefore + 2 * fee, "Treasury should have both fees");
+    assert.equal(
+      await provider.connection.getBalance(joiner.publicKey),
+      INITIAL_BALANCE - fee,
+      "Joiner should have lost one deposit and won the other pot minus the fee"
+    );
+  });
+});
+
+describe("pause the escrow, fail to create a game, then unpause", () => {
+  // Configure the client to use the local cluster.
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.rpsEscrow as Program<RpsEscrow>;
+  const creator = anchor.web3.Keypair.generate();
+  const authority = loadKeypair("resolve_authority.json");
+  const gameId = Buffer.from("9c1b7e2d4f6a4b8c9d0e1f2a3b4c5d6e", "hex");
+  const amount = 1_000_000_000; // 1 SOL in lamports
+
+  const [gameEscrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("game_escrow"), creator.publicKey.toBuffer(), gameId],
+    program.programId
+  );
+  const [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("vault"), gameEscrowPda.toBuffer()],
+    program.programId
+  );
+
+  const setPaused = (paused: boolean) =>
+    program.methods
+      .setPaused(paused)
+      .accountsStrict({
+        authority: authority.publicKey,
+        pause: pausePda(program),
+        resolveConfig: resolveConfigPda(program),
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+  const createGame = () =>
+    program.methods
+      .createGame(Array.from(gameId), new anchor.BN(amount))
+      .accountsStrict({
+        creator: creator.publicKey,
+        gameEscrow: gameEscrowPda,
+        vault: vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+
+  before(async () => {
+    await airdropTo(provider, INITIAL_BALANCE, creator.publicKey, authority.publicKey);
+  });
+
+  it("Pause the escrow!", async () => {
+    const tx = await setPaused(true);
+    const pause = await program.account.pauseState.fetch(pausePda(program));
+    assert.isTrue(pause.paused, "Escrow should be paused");
+    console.log("Your transaction signature", tx);
+  });
+
+  it("should fail to create a game while paused!", async () => {
+    try {
+      await createGame();
+      expect.fail("Expected error");
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      assert.ok(message.includes("Paused"), `Expected "Paused" in: ${message}`);
+    }
+  });
+
+  it("Unpause the escrow and create the game!", async () => {
+    await setPaused(false);
+    const tx = await createGame();
+    assert.equal(await provider.connection.getBalance(vaultPda), amount, "vault should have the amount deposited");
+    console.log("Your transaction signature", tx);
+  });
+});
+
+describe("create game, join game, and let the winner resolve it with the authority's verdict", () => {
+  // Configure the client to use the local cluster.
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.rpsEscrow as Program<RpsEscrow>;
+  const creator = anchor.web3.Keypair.generate();
+  const joiner = anchor.web3.Keypair.generate();
+  const authority = loadKeypair("resolve_authority.json");
+  const treasury = new anchor.web3.PublicKey("Ft6kMwkButM1J7iHJBJTb8QFEBuoBPnG1jq83HMRE9mF");
+  const gameId = Buffer.from("b7c3e9a14d2f4e6a8b0c1d2e3f4a5b6c", "hex");
+  const amount = 1_000_000_000; // 1 SOL in lamports
+
+  const [gameEscrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("game_escrow"), creator.publicKey.toBuffer(), gameId],
+    program.programId
+  );
+  const [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("vault"), gameEscrowPda.toBuffer()],
+    program.programId
+  );
+
+  before(async () => {
+    await airdropTo(provider, INITIAL_BALANCE, creator.publicKey, joiner.publicKey);
+  });
+
+  it("Create and join the game!", async () => {
+    await program.methods
+      .createGame(Array.from(gameId), new anchor.BN(amount))
+      .accountsStrict({
+        creator: creator.publicKey,
+        gameEscrow: gameEscrowPda,
+        vault: vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+    await program.methods
+      .joinGame()
+      .accountsStrict({
+        joiner: joiner.publicKey,
+        gameEscrow: gameEscrowPda,
+        vault: vaultPda,
+        pause: pausePda(program),
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([joiner])
+      .rpc();
+  });
+
+  // The Ed25519 instruction verifying `attester`'s verdict, then resolve_with_attestation, paid by the joiner.
+  const submitVerdict = async (attester: anchor.web3.Keypair) => {
+    const expiry = Math.floor(Date.now() / 1000) + 60;
+    const ed25519 = anchor.web3.Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: attester.secretKey,
+      message: verdictMessage(gameEscrowPda, gameId, joiner.publicKey, expiry),
+    });
+    const resolve = await program.methods
+      .resolveWithAttestation(joiner.publicKey, new anchor.BN(expiry))
+      .accountsStrict({
+        gameEscrow: gameEscrowPda,
+        vault: vaultPda,
+        winnerDestination: joiner.publicKey,
+        creator: creator.publicKey,
+        treasury: treasury,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        resolveConfig: resolveConfigPda(program),
+        instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .instruction();
+    const tx = new anchor.web3.Transaction().add(ed25519, resolve);
+    return anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [joiner]);
+  };
+
+  it("should fail to resolve with a verdict not signed by the resolve authority!", async () => {
+    try {
+      await submitVerdict(anchor.web3.Keypair.generate());
+      expect.fail("Expected error");
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      const logs = (error as { logs?: string[] }).logs ?? [];
+      assert.ok(
+        [message, ...logs].some((line) => line.includes("Unauthorized")),
+        `Expected "Unauthorized" in: ${message}`
+      );
+    }
+  });
+
+  it("Winner resolves the game with the authority's verdict!", async () => {
+    const joinerBefore = await provider.connection.getBalance(joiner.publicKey);
+    const tx = await submitVerdict(authority);
+
+    console.log("Your transaction signature", tx);
+    assert.isNull(await provider.connection.getAccountInfo(gameEscrowPda), "Game escrow PDA should be null");
+    assert.isNull(await provider.connection.getAccountInfo(vaultPda), "Vault PDA should be null");
+    const receipt = await provider.connection.getAccountInfo(gameResultPda(program, gameEscrowPda));
+    assert.ok(receipt?.owner.equals(program.programId), "Game result receipt should be owned by the program");
+    const winnerAmount = 2 * amount - (2 * amount * 3) / 100;
+    assert.isAbove(
+      await provider.connection.getBalance(joiner.publicKey),
+      joinerBefore + winnerAmount - 20_000,
+      "Joiner should have the pot minus the fee, less the transaction fee"
+    );
+  });
+});
+
+// Runs last: the resolve authority set stays on the validator. Its minimum stake is above every
+// other game of this file, so they keep single-signer resolve.
+describe("init a 2-of-2 resolve authority set and resolve a high-stake game with a co-signer", () => {
+  // Configure the client to use the local cluster.
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.rpsEscrow as Program<RpsEscrow>;
+  const creator = anchor.web3.Keypair.generate();
+  const joiner = anchor.web3.Keypair.generate();
+  const cosigner = anchor.web3.Keypair.generate();
+  const authority = loadKeypair("resolve_authority.json");
+  const treasury = new anchor.web3.PublicKey("Ft6kMwkButM1J7iHJBJTb8QFEBuoBPnG1jq83HMRE9mF");
+  const gameId = Buffer.from("5d2e8f1a3b4c4d6e9f0a1b2c3d4e5f60", "hex");
+  const amount = 10 * anchor.web3.LAMPORTS_PER_SOL;
+
+  const [gameEscrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("game_escrow"), creator.publicKey.toBuffer(), gameId],
+    program.programId
+  );
+  const [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("vault"), gameEscrowPda.toBuffer()],
+    program.programId
+  );
+
+  before(async () => {
+    await airdropTo(provider, INITIAL_BALANCE, creator.publicKey, joiner.publicKey, authority.publicKey);
+  });
+
+  it("Init the resolve authority set!", async () => {
+    const tx = await program.methods
+      .initResolveConfig([authority.publicKey, cosigner.publicKey], 2, new anchor.BN(amount))
+      .accountsStrict({
+        authority: authority.publicKey,
+        resolveConfig: resolveConfigPda(program),
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+    const config = await program.account.resolveConfig.fetch(resolveConfigPda(program));
+    assert.equal(config.threshold, 2);
+    assert.equal(config.authorities.length, 2);
+    console.log("Your transaction signature", tx);
+  });
+
+  it("Create and join a high-stake game!", async () => {
+    await program.methods
+      .createGame(Array.from(gameId), new anchor.BN(amount))
+      .accountsStrict({
+        creator: creator.publicKey,
+        gameEscrow: gameEscrowPda,
+        vault: vaultPda,
+        gameResult: gameResultPda(program, gameEscrowPda),
+        pause: pausePda(program),
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([creator])
+      .rpc();
+    await program.methods
+      .joinGame()
+      .accountsStrict({
+        joiner: joiner.publicKey,
+        gameEscrow: gameEscrowPda,
+        vault: vaultPda,
+        pause: pausePda(program),
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([joiner])
+      .rpc();
+  });
+
+  const resolveAccounts = () => ({
+    authority: authority.publicKey,
+    gameEscrow: gameEscrowPda,
+    vault: vaultPda,
+    winnerDestination: joiner.publicKey,
+    creator: creator.publicKey,
+    treasury: treasury,
+    gameResult: gameResultPda(program, gameEscrowPda),
+    resolveConfig: resolveConfigPda(program),
+    systemProgram: anchor.web3.SystemProgram.programId,
+  });
+
+  it("should fail to resolve the high-stake game with the resolve authority alone!", async () => {
+    try {
+      await program.methods
+        .resolve(joiner.publicKey)
+        .accountsStrict(resolveAccounts())
+        .signers([authority])
+        .rpc();
+      expect.fail("Expected error");
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      assert.ok(message.includes("NotEnoughSigners"), `Expected "NotEnoughSigners" in: ${message}`);
+    }
+  });
+
+  it("Resolve the high-stake game with the co-signer!", async () => {
+    const tx = await program.methods
+      .resolve(joiner.publicKey)
+      .accountsStrict(resolveAccounts())
+      .remainingAccounts([{ pubkey: cosigner.publicKey, isSigner: true, isWritable: false }])
+      .signers([authority, cosigner])
+      .rpc();
+
+    console.log("Your transaction signature", tx);
+    assert.isNull(await provider.connection.getAccountInfo(gameEscrowPda), "Game escrow PDA should be null");
+    assert.isAbove(await provider.connection.getBalance(joiner.publicKey), INITIAL_BALANCE, "Joiner should have won");
+  });
+});